@@ -31,6 +31,12 @@ pub struct LinkChecker {
     pub external_level: LinkCheckerLevel,
     /// A list of file glob patterns to skip link checking on
     pub ignored_files: Vec<String>,
+    /// How many seconds the successful external link checks are kept in the cache between runs.
+    /// `0` by default, meaning they are always checked again
+    pub success_cache_ttl: u64,
+    /// How many seconds the failed external link checks are kept in the cache between runs.
+    /// `0` by default, meaning they are always checked again
+    pub failure_cache_ttl: u64,
     #[serde(skip_serializing, skip_deserializing)] // not a typo, 2 are needed
     pub ignored_files_globset: Option<GlobSet>,
 }
@@ -41,4 +47,9 @@ impl LinkChecker {
         self.ignored_files_globset = Some(glob_set);
         Ok(())
     }
+
+    /// Whether the external link checks should be kept between runs
+    pub fn uses_cache(&self) -> bool {
+        self.success_cache_ttl > 0 || self.failure_cache_ttl > 0
+    }
 }
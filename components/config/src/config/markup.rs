@@ -1,6 +1,6 @@
 use giallo::{HighlightOptions, Registry, ThemeVariant};
 use serde::{Deserialize, Serialize};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use errors::{Result, bail};
 use utils::types::InsertAnchor;
@@ -18,6 +18,19 @@ impl Default for HighlightStyle {
     }
 }
 
+/// Where the footnotes are rendered
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FootnoteStyle {
+    /// Where they are defined
+    #[default]
+    Plain,
+    /// At the bottom in the style of GitHub, with back references
+    Bottom,
+    /// Next to their first reference, to be shown in the margin
+    Sidenote,
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum HighlightConfig {
@@ -131,6 +144,66 @@ impl Highlighting {
     }
 }
 
+/// The responsive versions generated for the colocated images of the content
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ResponsiveImages {
+    /// Whether to render the colocated images with a `srcset` of their resized versions
+    pub enabled: bool,
+    /// The widths the images are resized to. Images are never upscaled: the ones larger
+    /// than an image are replaced by its own width
+    pub widths: Vec<u32>,
+    /// The formats of the resized images, as in `resize_image`. The last one is used in the
+    /// `<img>` and the other ones in `<source>` elements of a `<picture>`
+    pub formats: Vec<String>,
+    /// The quality of the lossy formats, as in `resize_image`
+    pub quality: Option<u8>,
+    /// The value of the `sizes` attribute, if any
+    pub sizes: Option<String>,
+}
+
+/// The formats `resize_image` can convert images to
+pub const IMAGE_FORMATS: [&str; 6] = ["auto", "jpeg", "jpg", "png", "webp", "avif"];
+
+impl ResponsiveImages {
+    pub fn validate(&self) -> Result<()> {
+        if self.enabled && (self.widths.is_empty() || self.formats.is_empty()) {
+            bail!("Responsive images need at least one width and one format");
+        }
+        if self.widths.contains(&0) {
+            bail!("The widths of responsive images cannot be 0");
+        }
+        Self::validate_formats(&self.formats)
+    }
+
+    /// Errors on the formats `resize_image` doesn't know, so they are caught before
+    /// resizing the first image
+    pub fn validate_formats(formats: &[String]) -> Result<()> {
+        for format in formats {
+            if !IMAGE_FORMATS.contains(&format.as_str()) {
+                bail!(
+                    "Unknown format `{}` for responsive images: it should be one of `{}`",
+                    format,
+                    IMAGE_FORMATS.join("`, `")
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for ResponsiveImages {
+    fn default() -> ResponsiveImages {
+        ResponsiveImages {
+            enabled: false,
+            widths: vec![480, 960, 1920],
+            formats: vec!["avif".to_string(), "webp".to_string(), "auto".to_string()],
+            quality: None,
+            sizes: None,
+        }
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(default, deny_unknown_fields)]
 pub struct Markdown {
@@ -154,17 +227,85 @@ pub struct Markdown {
     /// Whether parsing of definition lists is enabled
     pub definition_list: bool,
     /// Whether footnotes are rendered at the bottom in the style of GitHub.
+    /// Same as `footnote_style = "bottom"`
     pub bottom_footnotes: bool,
+    /// Where footnotes are rendered
+    pub footnote_style: FootnoteStyle,
     /// Add loading="lazy" decoding="async" to img tags. When turned on, the alt text must be plain text. Defaults to false
     pub lazy_async_image: bool,
     /// Whether to insert a link for each header like the ones you can see in this site if you hover one
     /// The default template can be overridden by creating a `anchor-link.html` in the `templates` directory
     pub insert_anchor_links: InsertAnchor,
+    /// The level of the highest headings in the table of contents, 1 for `<h1>`
+    pub toc_min_level: u32,
+    /// The level of the deepest headings in the table of contents, 6 for `<h6>`
+    pub toc_max_level: u32,
+    /// Whether to number the headings of the table of contents like `2.3.1`
+    pub number_headings: bool,
     /// Whether to enable GitHub-style alerts
     pub github_alerts: bool,
+    /// Whether to render LaTeX math between `$` and `$$` to MathML
+    pub math: bool,
+    /// Whether to apply attribute lists like `{#id .class key=value}` written after images, links
+    /// and blocks to their HTML
+    pub attribute_lists: bool,
+    /// Whether to render `::: name` fenced containers with the `containers/{name}.html` templates
+    pub containers: bool,
+    /// Whether to resolve `[[Page Title]]` wiki links against the filename, slug and title of the pages
+    pub wiki_links: bool,
+    /// The responsive versions of the colocated images
+    pub images: ResponsiveImages,
+    /// The builtin transforms to run on the markdown events, in that order
+    pub transforms: Vec<String>,
+    /// The builtin renderers turning code blocks of some languages into HTML, eg `dot` for diagrams
+    pub fence_renderers: Vec<String>,
+    /// The BibTeX file `[@key]` citations are looked up in, relative to the site root.
+    /// Pages can set their own in their front matter
+    pub bibliography: Option<String>,
+    /// The root of the site, which the bibliography paths are relative to
+    #[serde(skip)]
+    pub base_path: Option<PathBuf>,
 }
 
+/// The names of the transforms that can be enabled with `markdown.transforms`
+pub const BUILTIN_TRANSFORMS: [&str; 1] = ["wrap_tables"];
+
+/// The names of the renderers that can be enabled with `markdown.fence_renderers`
+pub const BUILTIN_FENCE_RENDERERS: [&str; 1] = ["dot"];
+
 impl Markdown {
+    /// Errors on table of contents levels outside of 1 to 6 or the wrong way around,
+    /// on unknown transforms and fence renderers and on invalid responsive images options
+    pub fn validate(&self) -> Result<()> {
+        for name in &self.transforms {
+            if !BUILTIN_TRANSFORMS.contains(&name.as_str()) {
+                bail!(
+                    "Unknown markdown transform `{}` in `markdown.transforms`: it should be one of `{}`",
+                    name,
+                    BUILTIN_TRANSFORMS.join("`, `")
+                );
+            }
+        }
+        for name in &self.fence_renderers {
+            if !BUILTIN_FENCE_RENDERERS.contains(&name.as_str()) {
+                bail!(
+                    "Unknown fence renderer `{}` in `markdown.fence_renderers`: it should be one of `{}`",
+                    name,
+                    BUILTIN_FENCE_RENDERERS.join("`, `")
+                );
+            }
+        }
+        self.images.validate()?;
+
+        let (min, max) = (self.toc_min_level, self.toc_max_level);
+        if !(1..=6).contains(&min) || !(min..=6).contains(&max) {
+            bail!(
+                "Invalid `markdown.toc_min_level = {min}` and `markdown.toc_max_level = {max}` in config.toml: they must be between 1 and 6, the first one not above the second one"
+            );
+        }
+        Ok(())
+    }
+
     pub fn validate_external_links_class(&self) -> Result<()> {
         // Validate external link class doesn't contain quotes which would break HTML and aren't valid in CSS
         if let Some(class) = &self.external_links_class
@@ -175,6 +316,14 @@ impl Markdown {
         Ok(())
     }
 
+    /// The footnote style, taking `bottom_footnotes` into account
+    pub fn footnote_style(&self) -> FootnoteStyle {
+        match self.footnote_style {
+            FootnoteStyle::Plain if self.bottom_footnotes => FootnoteStyle::Bottom,
+            style => style,
+        }
+    }
+
     pub fn has_external_link_tweaks(&self) -> bool {
         self.external_links_target_blank
             || self.external_links_no_follow
@@ -230,9 +379,22 @@ impl Default for Markdown {
             smart_punctuation: false,
             definition_list: false,
             bottom_footnotes: false,
+            footnote_style: FootnoteStyle::Plain,
             lazy_async_image: false,
             insert_anchor_links: InsertAnchor::None,
+            toc_min_level: 1,
+            toc_max_level: 6,
+            number_headings: false,
             github_alerts: false,
+            math: false,
+            attribute_lists: false,
+            containers: false,
+            wiki_links: false,
+            images: ResponsiveImages::default(),
+            transforms: Vec::new(),
+            fence_renderers: Vec::new(),
+            bibliography: None,
+            base_path: None,
         }
     }
 }
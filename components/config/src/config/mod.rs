@@ -1,6 +1,7 @@
 pub mod languages;
 pub mod link_checker;
 pub mod markup;
+pub mod related;
 pub mod search;
 pub mod slugify;
 pub mod taxonomies;
@@ -13,10 +14,12 @@ use serde::{Deserialize, Serialize};
 use toml::Value as Toml;
 
 use crate::theme::Theme;
-use errors::{Result, anyhow, bail};
+use errors::{Context, Result, anyhow, bail};
 use utils::fs::read_file;
 use utils::globs::build_ignore_glob_set;
+use utils::schema::FrontMatterSchema;
 use utils::slugs::slugify_paths;
+use utils::types::ContentFormat;
 
 // We want a default base url for tests
 const DEFAULT_BASE_URL: &str = "http://a-website.com";
@@ -98,16 +101,28 @@ pub struct Config {
     pub output_dir: String,
     /// Whether dotfiles inside the output directory are preserved when rebuilding the site
     pub preserve_dotfiles_in_output: bool,
+    /// Where Zola keeps the data it can re-use between builds, like the `zola build --incremental` cache
+    pub cache_dir: String,
+    /// The formats to render pages and sections to in addition to HTML, eg `json` to render
+    /// `page.json` to `index.json` next to the `index.html`
+    pub output_formats: Vec<String>,
+    /// The extensions of the content formats other than Markdown to load pages from, eg `ipynb`
+    pub content_formats: Vec<String>,
 
     pub link_checker: link_checker::LinkChecker,
     /// The setup for which slugification strategies to use for paths, taxonomies and anchors
     pub slugify: slugify::Slugify,
     /// The search config, telling what to include in the search index
     pub search: search::Search,
+    /// The config for finding the related pages of each page
+    pub related: related::Related,
     /// The config for the Markdown rendering: syntax highlighting and everything
     pub markdown: markup::Markdown,
     /// All user params set in `[extra]` in the config
     pub extra: HashMap<String, Toml>,
+    /// What the `extra` and `taxonomies` of pages need to contain when no section above them
+    /// declares a schema
+    pub page_schema: Option<FrontMatterSchema>,
     /// Enables the generation of Sitemap.xml
     pub generate_sitemap: bool,
     /// Enables the generation of robots.txt
@@ -160,6 +175,13 @@ impl Config {
         config.add_default_language()?;
         config.slugify_taxonomies();
         config.link_checker.resolve_globset()?;
+        validate_output_formats(&config.output_formats)?;
+        validate_content_formats(&config.content_formats)?;
+        config.related.validate()?;
+        config.markdown.validate()?;
+        if let Some(ref schema) = config.page_schema {
+            schema.validate().context("Invalid `page_schema` in config.toml")?;
+        }
 
         let content_glob_set = build_ignore_glob_set(&config.ignored_content, "content")?;
         config.ignored_content_globset = Some(content_glob_set);
@@ -280,6 +302,9 @@ impl Config {
     /// with the config extra data
     pub fn merge_with_theme(&mut self, path: PathBuf, theme_name: &str) -> Result<()> {
         let theme = Theme::from_file(&path, theme_name)?;
+        if self.page_schema.is_none() {
+            self.page_schema = theme.page_schema.clone();
+        }
         self.add_theme_extra(&theme)
     }
 
@@ -308,6 +333,23 @@ impl Config {
         self.mode == Mode::Check
     }
 
+    pub fn is_in_serve_mode(&self) -> bool {
+        self.mode == Mode::Serve
+    }
+
+    /// The format of a content file, if it's a Markdown file or in one of the `content_formats`
+    pub fn content_format(&self, path: &Path) -> Option<ContentFormat> {
+        let format = ContentFormat::from_path(path)?;
+        match format {
+            ContentFormat::Markdown => Some(format),
+            _ => path
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .filter(|ext| self.content_formats.iter().any(|f| f == ext))
+                .map(|_| format),
+        }
+    }
+
     pub fn should_exclude_paginated_pages_in_sitemap(&self) -> bool {
         self.exclude_paginated_pages_in_sitemap == ExcludePaginatedPagesInSitemap::All
     }
@@ -370,6 +412,53 @@ impl Config {
     }
 }
 
+/// The extensions of the templates loaded from the `templates` directories, `*ml` being
+/// every extension ending with `ml` like `html` or `xml`
+pub const TEMPLATE_EXTENSIONS: [&str; 5] = ["*ml", "md", "txt", "json", "ics"];
+
+/// Output formats end up as file extensions so they need to be simple: `html` is always rendered
+/// and the other ones need to be an extension of the templates we load
+pub fn validate_output_formats(formats: &[String]) -> Result<()> {
+    for format in formats {
+        if format.is_empty() || !format.chars().all(|c| c.is_ascii_alphanumeric()) {
+            bail!("Output format `{}` should only contain ASCII letters and digits", format);
+        }
+        if format == "html" {
+            bail!("`html` is always rendered and can't be used as an output format");
+        }
+        let is_template_extension =
+            TEMPLATE_EXTENSIONS.iter().any(|ext| match ext.strip_prefix('*') {
+                Some(suffix) => format.ends_with(suffix),
+                None => format == ext,
+            });
+        if !is_template_extension {
+            bail!(
+                "Output format `{}` isn't supported: templates are only loaded for the `{}` extensions",
+                format,
+                TEMPLATE_EXTENSIONS.join("`, `")
+            );
+        }
+    }
+    Ok(())
+}
+
+/// The extensions of the content formats sites need to opt into with `content_formats`
+pub const OPT_IN_CONTENT_FORMATS: [&str; 3] = ["dj", "org", "ipynb"];
+
+/// Files in the other formats are assets, so a typo would silently turn pages into assets
+pub fn validate_content_formats(formats: &[String]) -> Result<()> {
+    for format in formats {
+        if !OPT_IN_CONTENT_FORMATS.contains(&format.as_str()) {
+            bail!(
+                "Content format `{}` isn't supported: it should be one of `{}`",
+                format,
+                OPT_IN_CONTENT_FORMATS.join("`, `")
+            );
+        }
+    }
+    Ok(())
+}
+
 // merge TOML data that can be a table, or anything else
 pub fn merge(into: &mut Toml, from: &Toml) -> Result<()> {
     match (from.is_table(), into.is_table()) {
@@ -429,11 +518,16 @@ impl Default for Config {
             translations: HashMap::new(),
             output_dir: "public".to_string(),
             preserve_dotfiles_in_output: false,
+            cache_dir: ".zola-cache".to_string(),
+            output_formats: Vec::new(),
+            content_formats: Vec::new(),
             link_checker: link_checker::LinkChecker::default(),
             slugify: slugify::Slugify::default(),
             search: search::Search::default(),
+            related: related::Related::default(),
             markdown: markup::Markdown::default(),
             extra: HashMap::new(),
+            page_schema: None,
             generate_sitemap: true,
             generate_robots_txt: true,
             exclude_paginated_pages_in_sitemap: ExcludePaginatedPagesInSitemap::None,
@@ -525,6 +619,55 @@ base_url = "https://replace-this-with-your-url.com"
         assert_eq!(config.title.unwrap(), "My site".to_string());
     }
 
+    #[test]
+    fn errors_on_invalid_toc_levels() {
+        let config = r#"
+base_url = "https://replace-this-with-your-url.com"
+
+[markdown]
+toc_min_level = 3
+toc_max_level = 2
+        "#;
+
+        let err = Config::parse(config).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid `markdown.toc_min_level = 3` and `markdown.toc_max_level = 2` in config.toml: they must be between 1 and 6, the first one not above the second one"
+        );
+    }
+
+    #[test]
+    fn errors_on_unknown_transforms() {
+        let config = r#"
+base_url = "https://replace-this-with-your-url.com"
+
+[markdown]
+transforms = ["wrap_tables", "wrap_tabels"]
+        "#;
+
+        let err = Config::parse(config).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Unknown markdown transform `wrap_tabels` in `markdown.transforms`: it should be one of `wrap_tables`"
+        );
+    }
+
+    #[test]
+    fn errors_on_unknown_image_formats() {
+        let config = r#"
+base_url = "https://replace-this-with-your-url.com"
+
+[markdown.images]
+formats = ["webp", "jxl"]
+        "#;
+
+        let err = Config::parse(config).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Unknown format `jxl` for responsive images: it should be one of `auto`, `jpeg`, `jpg`, `png`, `webp`, `avif`"
+        );
+    }
+
     #[test]
     fn errors_when_invalid_type() {
         let config = r#"
@@ -649,6 +792,33 @@ truc = "default"
         );
     }
 
+    #[test]
+    fn can_parse_page_schema() {
+        let config_str = r#"
+base_url = "https://replace-this-with-your-url.com"
+
+[page_schema.extra.cover_image]
+type = "string"
+required = true
+        "#;
+        let config = Config::parse(config_str).unwrap();
+        let schema = config.page_schema.unwrap();
+        assert!(schema.extra["cover_image"].required);
+
+        let theme = Theme::parse("[page_schema.taxonomies.tags]\nrequired = true").unwrap();
+        assert!(theme.page_schema.unwrap().taxonomies["tags"].required);
+
+        let config_str = r#"
+base_url = "https://replace-this-with-your-url.com"
+
+[page_schema.extra.layout]
+required = true
+default = "wide"
+        "#;
+        let err = Config::parse(config_str).unwrap_err();
+        assert_eq!(err.to_string(), "Invalid `page_schema` in config.toml");
+    }
+
     const CONFIG_TRANSLATION: &str = r#"
 base_url = "https://remplace-par-ton-url.fr"
 default_language = "fr"
@@ -963,6 +1133,130 @@ output_dir = "docs"
         assert_eq!(config.output_dir, "docs".to_string());
     }
 
+    #[test]
+    fn can_set_cache_dir() {
+        let config = r#"
+title = "My site"
+base_url = "https://replace-this-with-your-url.com"
+cache_dir = "target/zola"
+        "#;
+
+        let config = Config::parse(config).unwrap();
+        assert_eq!(config.cache_dir, "target/zola".to_string());
+        assert_eq!(Config::default().cache_dir, ".zola-cache".to_string());
+    }
+
+    #[test]
+    fn can_set_output_formats() {
+        let config = r#"
+title = "My site"
+base_url = "https://replace-this-with-your-url.com"
+output_formats = ["json", "txt", "xml"]
+        "#;
+
+        let config = Config::parse(config).unwrap();
+        assert_eq!(config.output_formats, vec!["json", "txt", "xml"]);
+        assert!(Config::default().output_formats.is_empty());
+    }
+
+    #[test]
+    fn can_enable_content_formats() {
+        let config = Config::parse(
+            r#"
+base_url = "https://replace-this-with-your-url.com"
+content_formats = ["org"]
+        "#,
+        )
+        .unwrap();
+        assert_eq!(config.content_format(Path::new("a.md")), Some(ContentFormat::Markdown));
+        assert_eq!(config.content_format(Path::new("a.org")), Some(ContentFormat::Org));
+        assert_eq!(config.content_format(Path::new("a.dj")), None);
+        assert_eq!(config.content_format(Path::new("a.ipynb")), None);
+        assert_eq!(config.content_format(Path::new("a.txt")), None);
+
+        let err = Config::parse(
+            r#"
+base_url = "https://replace-this-with-your-url.com"
+content_formats = ["rst"]
+        "#,
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Content format `rst` isn't supported: it should be one of `dj`, `org`, `ipynb`"
+        );
+    }
+
+    #[test]
+    fn errors_on_invalid_output_formats() {
+        for format in ["html", "", "index.json", "../json", "csv"] {
+            let config = format!(
+                r#"
+title = "My site"
+base_url = "https://replace-this-with-your-url.com"
+output_formats = ["{}"]
+        "#,
+                format
+            );
+            assert!(Config::parse(&config).is_err(), "{format}");
+        }
+    }
+
+    #[test]
+    fn can_set_related() {
+        let config = r#"
+title = "My site"
+base_url = "https://replace-this-with-your-url.com"
+
+[related]
+limit = 3
+content = 0.5
+date = 2
+        "#;
+
+        let config = Config::parse(config).unwrap();
+        assert!(config.related.is_enabled());
+        assert_eq!(config.related.limit, 3);
+        assert_eq!(config.related.taxonomies, 1.0);
+        assert_eq!(config.related.content, 0.5);
+        assert_eq!(config.related.date, 2.0);
+        assert!(!Config::default().related.is_enabled());
+    }
+
+    #[test]
+    fn errors_on_invalid_related_weights() {
+        let config = r#"
+title = "My site"
+base_url = "https://replace-this-with-your-url.com"
+
+[related]
+limit = 3
+taxonomies = -1
+        "#;
+
+        let err = Config::parse(config).unwrap_err();
+        assert!(err.to_string().contains("`related.taxonomies`"));
+    }
+
+    #[test]
+    fn can_set_footnote_style() {
+        let config = r#"
+title = "My site"
+base_url = "https://replace-this-with-your-url.com"
+
+[markdown]
+footnote_style = "sidenote"
+        "#;
+
+        let config = Config::parse(config).unwrap();
+        assert_eq!(config.markdown.footnote_style(), markup::FootnoteStyle::Sidenote);
+
+        let mut config = Config::default_for_test();
+        assert_eq!(config.markdown.footnote_style(), markup::FootnoteStyle::Plain);
+        config.markdown.bottom_footnotes = true;
+        assert_eq!(config.markdown.footnote_style(), markup::FootnoteStyle::Bottom);
+    }
+
     // TODO: Tests for valid themes; need extra scaffolding (test site) for custom themes.
 
     #[test]
@@ -0,0 +1,44 @@
+use serde::{Deserialize, Serialize};
+
+use errors::{Result, bail};
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Related {
+    /// How many related pages to find for each page. `0` by default, which disables it
+    pub limit: usize,
+    /// How much sharing taxonomy terms counts in the ranking. `1.0` by default.
+    pub taxonomies: f64,
+    /// How much the similarity (TF-IDF) of the rendered content counts in the ranking. `1.0` by default.
+    pub content: f64,
+    /// How much being published around the same date counts in the ranking. `0.0` by default.
+    pub date: f64,
+    /// After how many days apart the date score of two pages is halved. `30` by default.
+    pub date_half_life: u32,
+}
+
+impl Related {
+    pub fn is_enabled(&self) -> bool {
+        self.limit > 0
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        for (name, weight) in
+            [("taxonomies", self.taxonomies), ("content", self.content), ("date", self.date)]
+        {
+            if !weight.is_finite() || weight < 0.0 {
+                bail!("`related.{}` should be a positive number, got {}", name, weight);
+            }
+        }
+        if self.date_half_life == 0 {
+            bail!("`related.date_half_life` should be at least 1 day");
+        }
+        Ok(())
+    }
+}
+
+impl Default for Related {
+    fn default() -> Self {
+        Related { limit: 0, taxonomies: 1.0, content: 1.0, date: 0.0, date_half_life: 30 }
+    }
+}
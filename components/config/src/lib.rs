@@ -4,14 +4,18 @@ mod theme;
 use std::path::Path;
 
 pub use crate::config::{
-    Config,
+    Config, TEMPLATE_EXTENSIONS,
     languages::LanguageOptions,
     link_checker::LinkChecker,
     link_checker::LinkCheckerLevel,
-    markup::{HighlightConfig, HighlightStyle, Highlighting, Markdown},
+    markup::{
+        FootnoteStyle, HighlightConfig, HighlightStyle, Highlighting, Markdown, ResponsiveImages,
+    },
+    related::Related,
     search::{IndexFormat, Search},
     slugify::Slugify,
     taxonomies::TaxonomyConfig,
+    validate_output_formats,
 };
 use errors::Result;
 pub use giallo::Registry;
@@ -6,14 +6,17 @@ use toml::Value as Toml;
 
 use errors::{Context, Result, bail};
 use utils::fs::read_file;
+use utils::schema::FrontMatterSchema;
 
 /// Holds the data from a `theme.toml` file.
-/// There are other fields than `extra` in it but Zola
+/// There are other fields than `extra` and `page_schema` in it but Zola
 /// itself doesn't care about them.
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Theme {
     /// All user params set in [extra] in the theme.toml
     pub extra: HashMap<String, Toml>,
+    /// The `[page_schema]` of the theme, used when the site doesn't declare one
+    pub page_schema: Option<FrontMatterSchema>,
 }
 
 impl Theme {
@@ -25,17 +28,24 @@ impl Theme {
         };
 
         let mut extra = HashMap::new();
+        let mut page_schema = None;
         if let Some(theme_table) = theme.as_table() {
             if let Some(ex) = theme_table.get("extra")
                 && ex.is_table()
             {
                 extra = ex.clone().try_into().unwrap();
             }
+            if let Some(schema) = theme_table.get("page_schema") {
+                let schema: FrontMatterSchema =
+                    schema.clone().try_into().context("Invalid `page_schema` in theme.toml")?;
+                schema.validate().context("Invalid `page_schema` in theme.toml")?;
+                page_schema = Some(schema);
+            }
         } else {
             bail!("Expected the `theme.toml` to be a TOML table")
         }
 
-        Ok(Theme { extra })
+        Ok(Theme { extra, page_schema })
     }
 
     /// Parses a theme file from the given path
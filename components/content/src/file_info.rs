@@ -28,7 +28,7 @@ pub fn find_content_components<P: AsRef<Path>>(path: P) -> Vec<String> {
 /// Struct that contains all the information about the actual file
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
 pub struct FileInfo {
-    /// The full path to the .md file
+    /// The full path to the .md file, or the .dj or .org file for pages in those formats
     pub path: PathBuf,
     /// The on-disk filename, will differ from the `name` when there is a language code in it
     pub filename: String,
@@ -58,13 +58,15 @@ impl FileInfo {
         let file_path = path.to_path_buf();
         let mut parent = file_path.parent().expect("Get parent of page").to_path_buf();
         let name = path.file_stem().unwrap().to_string_lossy().to_string();
+        // Pages can also be Djot or Org files
+        let extension = path.extension().map_or("md".into(), |e| e.to_string_lossy());
         let canonical = parent.join(&name);
         let mut components =
             find_content_components(file_path.strip_prefix(base_path).unwrap_or(&file_path));
         let relative = if !components.is_empty() {
-            format!("{}/{}.md", components.join("/"), name)
+            format!("{}/{}.{}", components.join("/"), name, extension)
         } else {
-            format!("{}.md", name)
+            format!("{}.{}", name, extension)
         };
         let mut colocated_path = None;
 
@@ -190,6 +192,16 @@ mod tests {
         assert_eq!(file.colocated_path, Some("posts/tutorials/python/".to_string()));
     }
 
+    #[test]
+    fn keeps_extension_of_other_formats_in_relative_path() {
+        let file = FileInfo::new_page(
+            Path::new("/home/vincent/code/site/content/posts/tutorials/python.org"),
+            &PathBuf::new(),
+        );
+        assert_eq!(file.relative, "posts/tutorials/python.org");
+        assert_eq!(file.name, "python");
+    }
+
     #[test]
     fn doesnt_fail_with_multiple_content_directories_in_path() {
         let file = FileInfo::new_page(
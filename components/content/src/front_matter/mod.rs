@@ -3,5 +3,5 @@ mod section;
 mod split;
 
 pub use page::PageFrontMatter;
-pub use section::SectionFrontMatter;
-pub use split::{split_page_content, split_section_content};
+pub use section::{Cascade, PaginateSource, SectionFrontMatter};
+pub use split::{split_notebook_content, split_page_content, split_section_content};
@@ -6,9 +6,12 @@ use time::format_description::well_known::Rfc3339;
 use time::macros::{format_description, time};
 use time::{Date, OffsetDateTime, PrimitiveDateTime};
 
-use errors::{Result, bail};
+use config::{ResponsiveImages, validate_output_formats};
+use errors::{Result, anyhow, bail};
 use utils::de::{fix_toml_dates, from_unknown_datetime};
 
+use crate::PublishState;
+use crate::front_matter::section::{Cascade, merge_extra};
 use crate::front_matter::split::RawFrontMatter;
 
 /// The front matter of every page
@@ -39,6 +42,12 @@ pub struct PageFrontMatter {
     pub datetime_tuple: Option<(i32, u8, u8)>,
     /// Whether this page is a draft
     pub draft: bool,
+    /// The page is hidden until that date
+    #[serde(default, deserialize_with = "from_unknown_datetime")]
+    pub publish_date: Option<String>,
+    /// The page is hidden from that date
+    #[serde(default, deserialize_with = "from_unknown_datetime")]
+    pub expiry_date: Option<String>,
     /// Prevent generation of a folder for current page
     /// Defaults to `true`
     #[serde(skip_serializing)]
@@ -62,14 +71,56 @@ pub struct PageFrontMatter {
     /// Specify a template different from `page.html` to use for that page
     #[serde(skip_serializing)]
     pub template: Option<String>,
+    /// The formats to render in addition to HTML, overriding the `output_formats` of the config
+    #[serde(skip_serializing)]
+    pub output_formats: Option<Vec<String>>,
     /// Whether the page is included in the search index
     /// Defaults to `true` but is only used if search if explicitly enabled in the config.
     #[serde(skip_serializing)]
     pub in_search_index: bool,
+    /// The BibTeX file the citations of the page are looked up in, overriding the
+    /// `markdown.bibliography` of the config
+    #[serde(skip_serializing)]
+    pub bibliography: Option<String>,
+    /// Overrides of the `[markdown.images]` config for the colocated images of the page
+    pub images: Option<ImagesFrontMatter>,
+    /// Overrides `markdown.toc_min_level` of the config
+    #[serde(skip_serializing)]
+    pub toc_min_level: Option<u32>,
+    /// Overrides `markdown.toc_max_level` of the config
+    #[serde(skip_serializing)]
+    pub toc_max_level: Option<u32>,
+    /// Overrides `markdown.number_headings` of the config
+    #[serde(skip_serializing)]
+    pub number_headings: Option<bool>,
     /// Any extra parameter present in the front matter
     pub extra: Map<String, Value>,
 }
 
+/// The responsive images options a page can override, see `config::ResponsiveImages`
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ImagesFrontMatter {
+    pub enabled: Option<bool>,
+    pub widths: Option<Vec<u32>>,
+    pub formats: Option<Vec<String>>,
+    pub quality: Option<u8>,
+    pub sizes: Option<String>,
+}
+
+impl ImagesFrontMatter {
+    /// Returns the options of the config with the ones set in the front matter replaced
+    pub fn apply(&self, images: &ResponsiveImages) -> ResponsiveImages {
+        ResponsiveImages {
+            enabled: self.enabled.unwrap_or(images.enabled),
+            widths: self.widths.clone().unwrap_or_else(|| images.widths.clone()),
+            formats: self.formats.clone().unwrap_or_else(|| images.formats.clone()),
+            quality: self.quality.or(images.quality),
+            sizes: self.sizes.clone().or_else(|| images.sizes.clone()),
+        }
+    }
+}
+
 /// Parse a string for a datetime coming from one of the supported TOML format
 /// There are three alternatives:
 /// 1. an offset datetime (plain RFC3339)
@@ -87,6 +138,41 @@ fn parse_datetime(d: &str) -> Option<OffsetDateTime> {
         .ok()
 }
 
+/// Errors if the `publish_date` or the `expiry_date` can't be parsed or if the content would
+/// expire before being published
+pub(crate) fn validate_schedule(
+    publish_date: Option<&str>,
+    expiry_date: Option<&str>,
+) -> Result<()> {
+    let parse = |name: &str, date: Option<&str>| -> Result<Option<OffsetDateTime>> {
+        date.map(|d| parse_datetime(d).ok_or_else(|| anyhow!("`{name}` could not be parsed: {d}.")))
+            .transpose()
+    };
+    let publish = parse("publish_date", publish_date)?;
+    let expiry = parse("expiry_date", expiry_date)?;
+    if let (Some(publish), Some(expiry)) = (publish, expiry)
+        && expiry <= publish
+    {
+        bail!("`expiry_date` needs to be after `publish_date`");
+    }
+    Ok(())
+}
+
+/// Whether content with these dates, already validated by `validate_schedule`, is published at `now`
+pub(crate) fn publish_state(
+    publish_date: Option<&str>,
+    expiry_date: Option<&str>,
+    now: OffsetDateTime,
+) -> PublishState {
+    if publish_date.and_then(parse_datetime).is_some_and(|date| date > now) {
+        PublishState::Future
+    } else if expiry_date.and_then(parse_datetime).is_some_and(|date| date <= now) {
+        PublishState::Expired
+    } else {
+        PublishState::Published
+    }
+}
+
 impl PageFrontMatter {
     pub fn parse(raw: &RawFrontMatter) -> Result<PageFrontMatter> {
         let mut f: PageFrontMatter = raw.deserialize()?;
@@ -103,6 +189,14 @@ impl PageFrontMatter {
             bail!("`path` can't be empty if present")
         }
 
+        if let Some(ref formats) = f.output_formats {
+            validate_output_formats(formats)?;
+        }
+
+        if let Some(formats) = f.images.as_ref().and_then(|i| i.formats.as_ref()) {
+            ResponsiveImages::validate_formats(formats)?;
+        }
+
         f.extra = match fix_toml_dates(f.extra) {
             Value::Object(o) => o,
             _ => unreachable!("Got something other than a table in page extra"),
@@ -124,6 +218,8 @@ impl PageFrontMatter {
             bail!("`date` could not be parsed: {}.", date);
         }
 
+        validate_schedule(f.publish_date.as_deref(), f.expiry_date.as_deref())?;
+
         Ok(f)
     }
 
@@ -141,6 +237,22 @@ impl PageFrontMatter {
     pub fn weight(&self) -> usize {
         self.weight.unwrap()
     }
+
+    /// Adds the values of the cascade of an ancestor section the page doesn't set itself
+    pub fn apply_cascade(&mut self, cascade: &Cascade) {
+        if self.authors.is_empty() {
+            self.authors = cascade.authors.clone();
+        }
+        for (taxonomy, terms) in &cascade.taxonomies {
+            self.taxonomies.entry(taxonomy.clone()).or_insert_with(|| terms.clone());
+        }
+        merge_extra(&mut self.extra, &cascade.extra);
+    }
+
+    /// Whether the page is published at `now`, which is the build time
+    pub fn publish_state(&self, now: OffsetDateTime) -> PublishState {
+        publish_state(self.publish_date.as_deref(), self.expiry_date.as_deref(), now)
+    }
 }
 
 impl Default for PageFrontMatter {
@@ -156,6 +268,8 @@ impl Default for PageFrontMatter {
             datetime: None,
             datetime_tuple: None,
             draft: false,
+            publish_date: None,
+            expiry_date: None,
             render: true,
             slug: None,
             path: None,
@@ -164,6 +278,12 @@ impl Default for PageFrontMatter {
             authors: Vec::new(),
             aliases: Vec::new(),
             template: None,
+            output_formats: None,
+            bibliography: None,
+            images: None,
+            toc_min_level: None,
+            toc_max_level: None,
+            number_headings: None,
             extra: Map::new(),
         }
     }
@@ -171,7 +291,11 @@ impl Default for PageFrontMatter {
 
 #[cfg(test)]
 mod tests {
+    use config::ResponsiveImages;
+
+    use crate::PublishState;
     use crate::front_matter::page::PageFrontMatter;
+    use crate::front_matter::section::SectionFrontMatter;
     use crate::front_matter::split::RawFrontMatter;
     use tera::to_value;
     use test_case::test_case;
@@ -564,4 +688,133 @@ authors:
             res2.authors
         );
     }
+
+    #[test_case(&RawFrontMatter::Toml(r#"
+[images]
+widths = [320, 640]
+sizes = "50vw"
+"#); "toml")]
+    #[test_case(&RawFrontMatter::Yaml(r#"
+images:
+    widths: [320, 640]
+    sizes: 50vw
+"#); "yaml")]
+    fn can_override_responsive_images(content: &RawFrontMatter) {
+        let res = PageFrontMatter::parse(content).unwrap();
+        let config = ResponsiveImages { enabled: true, ..ResponsiveImages::default() };
+        let images = res.images.unwrap().apply(&config);
+        assert!(images.enabled);
+        assert_eq!(images.widths, vec![320, 640]);
+        assert_eq!(images.formats, config.formats);
+        assert_eq!(images.sizes, Some("50vw".to_owned()));
+    }
+
+    #[test]
+    fn errors_on_unknown_responsive_image_formats() {
+        let content = RawFrontMatter::Toml("[images]\nformats = [\"gif\"]");
+        let res = PageFrontMatter::parse(&content);
+        assert!(res.is_err());
+    }
+
+    #[test_case(&RawFrontMatter::Toml(r#"
+publish_date = 2024-06-01
+expiry_date = 2024-12-31T23:59:59Z
+"#); "toml")]
+    #[test_case(&RawFrontMatter::Yaml(r#"
+publish_date: 2024-06-01
+expiry_date: 2024-12-31T23:59:59Z
+"#); "yaml")]
+    fn can_find_publish_state(content: &RawFrontMatter) {
+        let res = PageFrontMatter::parse(content).unwrap();
+        assert_eq!(res.publish_state(datetime!(2024-05-31 23:00 UTC)), PublishState::Future);
+        assert_eq!(res.publish_state(datetime!(2024-06-01 0:00 UTC)), PublishState::Published);
+        assert_eq!(res.publish_state(datetime!(2024-12-31 23:59:59 UTC)), PublishState::Expired);
+    }
+
+    #[test]
+    fn errors_on_expiry_before_publish_date() {
+        let content = RawFrontMatter::Toml("publish_date = 2024-06-01\nexpiry_date = 2024-06-01");
+        let res = PageFrontMatter::parse(&content);
+        assert_eq!(res.unwrap_err().to_string(), "`expiry_date` needs to be after `publish_date`");
+    }
+
+    #[test]
+    fn can_apply_cascade() {
+        let section = SectionFrontMatter::parse(&RawFrontMatter::Toml(
+            r#"
+[cascade]
+authors = ["Jane"]
+[cascade.taxonomies]
+tags = ["rust"]
+categories = ["guides"]
+[cascade.extra]
+series = "Getting started"
+[cascade.extra.banner]
+image = "banner.png"
+alt = "A banner"
+"#,
+        ))
+        .unwrap();
+        let mut page = PageFrontMatter::parse(&RawFrontMatter::Toml(
+            r#"
+[taxonomies]
+tags = ["zola"]
+[extra.banner]
+alt = "Another banner"
+"#,
+        ))
+        .unwrap();
+        page.apply_cascade(section.cascade.as_ref().unwrap());
+
+        assert_eq!(page.authors, ["Jane"]);
+        assert_eq!(page.taxonomies["tags"], ["zola"]);
+        assert_eq!(page.taxonomies["categories"], ["guides"]);
+        assert_eq!(page.extra["series"], to_value("Getting started").unwrap());
+        assert_eq!(page.extra["banner"]["image"], to_value("banner.png").unwrap());
+        assert_eq!(page.extra["banner"]["alt"], to_value("Another banner").unwrap());
+    }
+
+    #[test]
+    fn errors_on_invalid_cascade_path() {
+        let res = SectionFrontMatter::parse(&RawFrontMatter::Toml("[cascade]\npath = \"a/[b\""));
+        assert!(res.unwrap_err().to_string().starts_with("Invalid `path` in `cascade`"));
+    }
+
+    #[test]
+    fn can_check_page_against_section_schema() {
+        let section = SectionFrontMatter::parse(&RawFrontMatter::Toml(
+            r#"
+[page_schema.extra.layout]
+enum = ["wide", "narrow"]
+default = "narrow"
+[page_schema.taxonomies.tags]
+required = true
+"#,
+        ))
+        .unwrap();
+        let mut page =
+            PageFrontMatter::parse(&RawFrontMatter::Toml("[extra]\nlayuot = \"wide\"")).unwrap();
+        let errors =
+            section.page_schema.as_ref().unwrap().check(&mut page.extra, &mut page.taxonomies);
+        assert_eq!(
+            errors,
+            [
+                "unknown key `extra.layuot`, did you mean `extra.layout`?",
+                "missing required taxonomy `tags`",
+            ]
+        );
+        assert_eq!(page.extra["layout"], "narrow");
+    }
+
+    #[test]
+    fn errors_on_invalid_page_schema() {
+        let res = SectionFrontMatter::parse(&RawFrontMatter::Toml(
+            "[page_schema.extra.layout]\ntype = \"strnig\"",
+        ));
+        assert!(res.is_err());
+        let res = SectionFrontMatter::parse(&RawFrontMatter::Toml(
+            "[page_schema.extra.layout]\ntype = \"integer\"\ndefault = \"wide\"",
+        ));
+        assert_eq!(res.unwrap_err().to_string(), "Invalid `page_schema`");
+    }
 }
@@ -1,15 +1,88 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use globset::{Glob, GlobMatcher};
 use serde::{Deserialize, Serialize};
 use tera::{Map, Value};
+use time::OffsetDateTime;
 
-use errors::Result;
-use utils::de::fix_toml_dates;
+use config::validate_output_formats;
+use errors::{Context, Result, anyhow, bail};
+use utils::de::{fix_toml_dates, from_unknown_datetime};
+use utils::schema::FrontMatterSchema;
 use utils::types::InsertAnchor;
 
-use crate::SortBy;
+use crate::front_matter::page::{publish_state, validate_schedule};
 use crate::front_matter::split::RawFrontMatter;
+use crate::{PublishState, SortBy};
 
 const DEFAULT_PAGINATE_PATH: &str = "page";
 
+/// Somewhere a section can take the pages it paginates from, instead of its own pages
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PaginateSource {
+    /// The path to the `_index.md` of a section, relative to the `content` directory
+    pub section: Option<String>,
+    /// The name of a taxonomy, used with `term`
+    pub taxonomy: Option<String>,
+    /// The name or slug of a term of `taxonomy`
+    pub term: Option<String>,
+}
+
+/// The compiled glob of a cascade `path`, compared through its glob
+#[derive(Debug, Clone)]
+pub struct PathMatcher(GlobMatcher);
+
+impl PartialEq for PathMatcher {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.glob() == other.0.glob()
+    }
+}
+
+impl Eq for PathMatcher {}
+
+/// Front matter values a section passes on to its descendant pages and subsections, unless
+/// they set them themselves
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Cascade {
+    /// Only cascade to the files matching this glob, relative to the directory of the section
+    pub path: Option<String>,
+    /// `path` compiled when parsing the front matter
+    #[serde(skip)]
+    pub path_matcher: Option<PathMatcher>,
+    /// Only used by pages
+    pub authors: Vec<String>,
+    /// Only used by pages, a taxonomy set in a page replaces the terms of the cascade
+    pub taxonomies: HashMap<String, Vec<String>>,
+    /// Merged with the `extra` of the descendants, key by key
+    pub extra: Map<String, Value>,
+}
+
+impl Cascade {
+    /// Whether the cascade of the section in `section_dir` applies to the file at `path`
+    pub fn applies_to(&self, section_dir: &Path, path: &Path) -> bool {
+        let Some(PathMatcher(ref matcher)) = self.path_matcher else {
+            return true;
+        };
+        path.strip_prefix(section_dir).is_ok_and(|relative| matcher.is_match(relative))
+    }
+}
+
+/// Adds the values of `from` missing in `into`, merging the tables they both have
+pub(crate) fn merge_extra(into: &mut Map<String, Value>, from: &Map<String, Value>) {
+    for (key, value) in from {
+        match (into.get_mut(key), value) {
+            (Some(Value::Object(into)), Value::Object(from)) => merge_extra(into, from),
+            (Some(_), _) => (),
+            (None, _) => {
+                into.insert(key.clone(), value.clone());
+            }
+        }
+    }
+}
+
 /// The front matter of every section
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(default, deny_unknown_fields)]
@@ -27,9 +100,18 @@ pub struct SectionFrontMatter {
     pub weight: usize,
     /// whether the section is a draft
     pub draft: bool,
+    /// The section, its pages and its subsections are hidden until that date
+    #[serde(default, deserialize_with = "from_unknown_datetime")]
+    pub publish_date: Option<String>,
+    /// The section, its pages and its subsections are hidden from that date
+    #[serde(default, deserialize_with = "from_unknown_datetime")]
+    pub expiry_date: Option<String>,
     /// Optional template, if we want to specify which template to render for that section
     #[serde(skip_serializing)]
     pub template: Option<String>,
+    /// The formats to render in addition to HTML, overriding the `output_formats` of the config
+    #[serde(skip_serializing)]
+    pub output_formats: Option<Vec<String>>,
     /// How many pages to be displayed per paginated page. No pagination will happen if this isn't set
     #[serde(skip_serializing)]
     pub paginate_by: Option<usize>,
@@ -39,6 +121,10 @@ pub struct SectionFrontMatter {
     /// Path to be used by pagination: the page number will be appended after it. Defaults to `page`.
     #[serde(skip_serializing)]
     pub paginate_path: String,
+    /// Paginate over the pages of other sections and/or taxonomy terms rather than the pages of
+    /// this section. Requires `paginate_by`.
+    #[serde(skip_serializing)]
+    pub paginate_source: Vec<PaginateSource>,
     /// Whether to insert a link for each header like the ones you can see in this site if you hover one
     /// The default template can be overridden by creating a `anchor-link.html` in the `templates` directory
     pub insert_anchor_links: Option<InsertAnchor>,
@@ -70,6 +156,21 @@ pub struct SectionFrontMatter {
     /// Whether to generate a feed for the current section
     #[serde(skip_serializing)]
     pub generate_feeds: bool,
+    /// Overrides `markdown.toc_min_level` of the config
+    #[serde(skip_serializing)]
+    pub toc_min_level: Option<u32>,
+    /// Overrides `markdown.toc_max_level` of the config
+    #[serde(skip_serializing)]
+    pub toc_max_level: Option<u32>,
+    /// Overrides `markdown.number_headings` of the config
+    #[serde(skip_serializing)]
+    pub number_headings: Option<bool>,
+    /// The values passed on to the descendants of the section
+    #[serde(skip_serializing)]
+    pub cascade: Option<Cascade>,
+    /// What the `extra` and `taxonomies` of the pages below the section need to contain
+    #[serde(skip_serializing)]
+    pub page_schema: Option<FrontMatterSchema>,
     /// Any extra parameter present in the front matter
     pub extra: Map<String, Value>,
 }
@@ -78,14 +179,58 @@ impl SectionFrontMatter {
     pub fn parse(raw: &RawFrontMatter) -> Result<SectionFrontMatter> {
         let mut f: SectionFrontMatter = raw.deserialize()?;
 
+        if let Some(ref formats) = f.output_formats {
+            validate_output_formats(formats)?;
+        }
+
+        if !f.paginate_source.is_empty() && !f.is_paginated() {
+            bail!("`paginate_source` requires `paginate_by` to be set");
+        }
+        for source in &f.paginate_source {
+            match (&source.section, &source.taxonomy, &source.term) {
+                (Some(_), None, None) | (None, Some(_), Some(_)) => (),
+                _ => bail!(
+                    "Each `paginate_source` needs either a `section` or both a `taxonomy` and a `term`"
+                ),
+            }
+        }
+
+        validate_schedule(f.publish_date.as_deref(), f.expiry_date.as_deref())?;
+
         f.extra = match fix_toml_dates(f.extra) {
             Value::Object(o) => o,
             _ => unreachable!("Got something other than a table in section extra"),
         };
 
+        if let Some(ref mut cascade) = f.cascade {
+            if let Some(ref glob) = cascade.path {
+                let glob =
+                    Glob::new(glob).map_err(|e| anyhow!("Invalid `path` in `cascade`: {}", e))?;
+                cascade.path_matcher = Some(PathMatcher(glob.compile_matcher()));
+            }
+            cascade.extra = match fix_toml_dates(std::mem::take(&mut cascade.extra)) {
+                Value::Object(o) => o,
+                _ => unreachable!("Got something other than a table in cascade extra"),
+            };
+        }
+
+        if let Some(ref schema) = f.page_schema {
+            schema.validate().context("Invalid `page_schema`")?;
+        }
+
         Ok(f)
     }
 
+    /// Whether the section is published at `now`, which is the build time
+    pub fn publish_state(&self, now: OffsetDateTime) -> PublishState {
+        publish_state(self.publish_date.as_deref(), self.expiry_date.as_deref(), now)
+    }
+
+    /// Adds the values of the cascade of an ancestor the section doesn't set itself
+    pub fn apply_cascade(&mut self, cascade: &Cascade) {
+        merge_extra(&mut self.extra, &cascade.extra);
+    }
+
     /// Only applies to section, whether it is paginated or not.
     pub fn is_paginated(&self) -> bool {
         match self.paginate_by {
@@ -103,9 +248,11 @@ impl Default for SectionFrontMatter {
             sort_by: SortBy::None,
             weight: 0,
             template: None,
+            output_formats: None,
             paginate_by: None,
             paginate_reversed: false,
             paginate_path: DEFAULT_PAGINATE_PATH.to_string(),
+            paginate_source: Vec::new(),
             render: true,
             redirect_to: None,
             insert_anchor_links: None,
@@ -114,8 +261,15 @@ impl Default for SectionFrontMatter {
             page_template: None,
             aliases: Vec::new(),
             generate_feeds: false,
+            toc_min_level: None,
+            toc_max_level: None,
+            number_headings: None,
+            cascade: None,
+            page_schema: None,
             extra: Map::new(),
             draft: false,
+            publish_date: None,
+            expiry_date: None,
         }
     }
 }
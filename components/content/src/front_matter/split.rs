@@ -1,6 +1,7 @@
 use std::path::Path;
 
 use errors::{Context, Result, bail};
+use markdown::Notebook;
 use once_cell::sync::Lazy;
 use regex::Regex;
 
@@ -22,6 +23,8 @@ static YAML_RE: Lazy<Regex> = Lazy::new(|| {
 pub enum RawFrontMatter<'a> {
     Toml(&'a str),
     Yaml(&'a str),
+    /// The metadata of Jupyter notebooks
+    Json(&'a str),
 }
 
 impl RawFrontMatter<'_> {
@@ -35,6 +38,7 @@ impl RawFrontMatter<'_> {
                 Ok(d) => d,
                 Err(e) => bail!("YAML deserialize error: {:?}", e),
             },
+            RawFrontMatter::Json(s) => serde_json::from_str(s)?,
         };
         Ok(f)
     }
@@ -96,6 +100,27 @@ pub fn split_page_content<'c>(
     Ok((meta, content))
 }
 
+/// Split a Jupyter notebook between its front matter, the `zola` object of its metadata,
+/// and the notebook itself
+pub fn split_notebook_content(
+    file_path: &Path,
+    content: &str,
+) -> Result<(PageFrontMatter, Notebook)> {
+    let notebook = Notebook::parse(content)
+        .with_context(|| format!("Failed to parse `{}`", file_path.to_string_lossy()))?;
+    let Some(front_matter) = notebook.front_matter() else {
+        bail!(
+            "Couldn't find front matter in `{}`. Did you forget to add a `zola` object to the notebook metadata?",
+            file_path.to_string_lossy()
+        );
+    };
+    let front_matter = serde_json::to_string(front_matter)?;
+    let meta = PageFrontMatter::parse(&RawFrontMatter::Json(&front_matter)).with_context(|| {
+        format!("Error when parsing front matter of page `{}`", file_path.to_string_lossy())
+    })?;
+    Ok((meta, notebook))
+}
+
 #[cfg(test)]
 mod tests {
     use std::path::Path;
@@ -4,6 +4,7 @@ mod file_info;
 mod library;
 mod page;
 mod pagination;
+mod related;
 mod section;
 mod ser;
 mod sorting;
@@ -12,9 +13,9 @@ mod types;
 mod utils;
 
 pub use file_info::FileInfo;
-pub use front_matter::{PageFrontMatter, SectionFrontMatter};
+pub use front_matter::{Cascade, PageFrontMatter, PaginateSource, SectionFrontMatter};
 pub use library::Library;
-pub use page::Page;
+pub use page::{Page, slug_from_filename};
 pub use pagination::Paginator;
 pub use section::Section;
 pub use taxonomies::{Taxonomy, TaxonomyTerm};
@@ -1,12 +1,16 @@
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 
 use ahash::{AHashMap, AHashSet};
 use config::Config;
+use errors::{Result, bail};
+use utils::schema::FrontMatterSchema;
 
+use crate::related::find_related;
 use crate::ser::TranslatedContent;
 use crate::sorting::sort_pages;
 use crate::taxonomies::{Taxonomy, TaxonomyFound};
-use crate::{Page, Section, SortBy};
+use crate::{Cascade, Page, Section, SortBy};
 
 macro_rules! set {
     ($($key:expr,)+) => (set!($($key),+));
@@ -38,6 +42,15 @@ pub struct Library {
     taxo_name_to_slug: AHashMap<String, String>,
 }
 
+/// The filename of the sections in the given language
+fn index_filename(lang: &str, config: &Config) -> String {
+    if lang == config.default_language {
+        "_index.md".to_owned()
+    } else {
+        format!("_index.{}.md", lang)
+    }
+}
+
 impl Library {
     pub fn new(config: &Config) -> Self {
         let mut lib = Self::default();
@@ -146,6 +159,41 @@ impl Library {
         }
     }
 
+    /// The markdown paths of the pages and sections wiki links can point to, by language and then
+    /// by lowercased filename, slug and title. A colocated page and a section use the name of
+    /// their directory as filename.
+    /// This is called _before_ rendering the markdown of the pages/sections
+    pub fn find_wiki_link_targets(&self) -> HashMap<String, HashMap<String, Vec<String>>> {
+        let mut targets: HashMap<String, HashMap<String, Vec<String>>> = HashMap::new();
+        for page in self.pages.values() {
+            let filename = match page.file.colocated_path {
+                Some(ref path) => path.trim_end_matches('/').rsplit('/').next().unwrap_or_default(),
+                None => &page.file.name,
+            };
+            let names = targets.entry(page.lang.clone()).or_default();
+            for name in [Some(filename), Some(page.slug.as_str()), page.meta.title.as_deref()]
+                .into_iter()
+                .flatten()
+            {
+                names.entry(name.to_lowercase()).or_default().push(page.file.relative.clone());
+            }
+        }
+        for section in self.sections.values() {
+            let names = targets.entry(section.lang.clone()).or_default();
+            for name in
+                [section.file.components.last(), section.meta.title.as_ref()].into_iter().flatten()
+            {
+                names.entry(name.to_lowercase()).or_default().push(section.file.relative.clone());
+            }
+        }
+
+        for paths in targets.values_mut().flat_map(|names| names.values_mut()) {
+            paths.sort();
+            paths.dedup();
+        }
+        targets
+    }
+
     /// This is called _before_ rendering the markdown the pages/sections
     pub fn find_taxonomies(&self, config: &Config) -> Vec<Taxonomy> {
         let mut taxonomies = Vec::new();
@@ -174,6 +222,14 @@ impl Library {
     /// Sort all sections pages according to sorting method given
     /// Pages that cannot be sorted are set to the section.ignored_pages instead
     pub fn sort_section_pages(&mut self) {
+        // Pages of unsorted sections are still listed in the same order on every build
+        // so the build cache can re-use what depends on them
+        for section in self.sections.values_mut() {
+            if section.meta.sort_by == SortBy::None {
+                section.pages.sort();
+            }
+        }
+
         let mut updates = AHashMap::new();
         for (path, section) in &self.sections {
             let pages: Vec<_> = section.pages.iter().map(|p| &self.pages[p]).collect();
@@ -210,6 +266,52 @@ impl Library {
         }
     }
 
+    /// The `[cascade]` tables applying to the page or section at `path` from the sections in
+    /// `dir` and above it, in the same language, the closest section first
+    pub fn cascades_for(
+        &self,
+        path: &Path,
+        dir: &Path,
+        lang: &str,
+        config: &Config,
+    ) -> Vec<&Cascade> {
+        let filename = index_filename(lang, config);
+        let mut cascades = Vec::new();
+        let mut dir = Some(dir);
+        while let Some(current) = dir {
+            if let Some(section) = self.sections.get(&current.join(&filename))
+                && let Some(ref cascade) = section.meta.cascade
+                && cascade.applies_to(current, path)
+            {
+                cascades.push(cascade);
+            }
+            // Only sections of the `content` directory are in the library
+            dir = current.parent();
+        }
+        cascades
+    }
+
+    /// The `page_schema` of the closest section in `dir` or above it in the same language
+    /// declaring one, or the one of the config
+    pub fn page_schema_for<'a>(
+        &'a self,
+        dir: &Path,
+        lang: &str,
+        config: &'a Config,
+    ) -> Option<&'a FrontMatterSchema> {
+        let filename = index_filename(lang, config);
+        let mut dir = Some(dir);
+        while let Some(current) = dir {
+            if let Some(section) = self.sections.get(&current.join(&filename))
+                && let Some(ref schema) = section.meta.page_schema
+            {
+                return Some(schema);
+            }
+            dir = current.parent();
+        }
+        config.page_schema.as_ref()
+    }
+
     /// Find out the direct subsections of each subsection if there are some
     /// as well as the pages for each section
     pub fn populate_sections(&mut self, config: &Config, content_path: &Path) {
@@ -275,7 +377,8 @@ impl Library {
 
             if let Some(children) = subsections.get(path) {
                 let mut children: Vec<_> = children.clone();
-                children.sort_by(|a, b| sections_weight[a].cmp(&sections_weight[b]));
+                // Sections with the same weight are sorted by path to get the same order every build
+                children.sort_by(|a, b| sections_weight[a].cmp(&sections_weight[b]).then(a.cmp(b)));
                 section.subsections = children;
             }
             if let Some(parents) = ancestors.get(path) {
@@ -338,6 +441,95 @@ impl Library {
         self.sort_section_pages();
     }
 
+    /// Finds the pages of the sections paginating over other sections or taxonomy terms.
+    /// When there are several sources, the pages are merged and sorted with the `sort_by` of the section.
+    pub fn populate_paginate_sources(
+        &mut self,
+        taxonomies: &[Taxonomy],
+        content_path: &Path,
+    ) -> Result<()> {
+        let mut found = Vec::new();
+        for (path, section) in &self.sections {
+            if section.meta.paginate_source.is_empty() {
+                continue;
+            }
+
+            let mut seen = AHashSet::new();
+            let mut pages = Vec::new();
+            for source in &section.meta.paginate_source {
+                let source_pages = if let Some(ref section_path) = source.section {
+                    let full_path = content_path.join(section_path.trim_start_matches("@/"));
+                    match self.sections.get(&full_path) {
+                        Some(s) => &s.pages,
+                        None => bail!(
+                            "Section `{}` in the `paginate_source` of {:?} not found",
+                            section_path,
+                            path
+                        ),
+                    }
+                } else {
+                    let name = source.taxonomy.as_deref().unwrap();
+                    let term = source.term.as_deref().unwrap();
+                    let item = taxonomies
+                        .iter()
+                        .find(|t| t.kind.name == name && t.lang == section.lang)
+                        .and_then(|t| t.items.iter().find(|i| i.name == term || i.slug == term));
+                    match item {
+                        Some(i) => &i.pages,
+                        None => bail!(
+                            "Term `{}` of taxonomy `{}` in the `paginate_source` of {:?} not found",
+                            term,
+                            name,
+                            path
+                        ),
+                    }
+                };
+                pages.extend(source_pages.iter().filter(|p| seen.insert(*p)).cloned());
+            }
+
+            if section.meta.paginate_source.len() > 1 && section.meta.sort_by != SortBy::None {
+                let (mut sorted, unsortable) =
+                    sort_pages(&self.find_pages_by_path(&pages), section.meta.sort_by);
+                // Like for taxonomies, the pages that can't be sorted are still there at the end
+                sorted.extend(unsortable);
+                pages = sorted;
+            }
+            found.push((path.clone(), pages));
+        }
+
+        for (path, pages) in found {
+            self.sections.get_mut(&path).unwrap().paginate_source_pages = Some(pages);
+        }
+        Ok(())
+    }
+
+    /// Finds the related pages of every rendered page among the pages of the same language.
+    /// This needs to be called _after_ rendering the markdown since it compares the rendered content.
+    pub fn populate_related(&mut self, config: &Config) {
+        for page in self.pages.values_mut() {
+            page.related.clear();
+        }
+        if !config.related.is_enabled() {
+            return;
+        }
+
+        let mut by_lang: AHashMap<&str, Vec<&Page>> = AHashMap::new();
+        for page in self.pages.values().filter(|p| p.meta.render) {
+            by_lang.entry(&page.lang).or_default().push(page);
+        }
+
+        let mut found = Vec::new();
+        for (_, mut pages) in by_lang {
+            pages.sort_by(|a, b| a.file.path.cmp(&b.file.path));
+            let related = find_related(&pages, &config.related);
+            found.extend(pages.iter().map(|p| p.file.path.clone()).zip(related));
+        }
+
+        for (path, related) in found {
+            self.pages.get_mut(&path).unwrap().related = related;
+        }
+    }
+
     /// Find all the orphan pages: pages that are in a folder without an `_index.md`
     pub fn get_all_orphan_pages(&self) -> Vec<&Page> {
         self.pages.iter().filter(|(_, p)| p.ancestors.is_empty()).map(|(_, p)| p).collect()
@@ -362,6 +554,8 @@ impl Library {
                 translations.push(TranslatedContent { lang, permalink, title, path });
             }
         }
+        // The paths are stored in a set so we sort them to always get the same order
+        translations.sort_by(|a, b| a.lang.cmp(b.lang).then_with(|| a.path.cmp(b.path)));
 
         translations
     }
@@ -378,7 +572,7 @@ impl Library {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{FileInfo, SortBy};
+    use crate::{FileInfo, PaginateSource, SortBy};
     use config::{LanguageOptions, TaxonomyConfig};
     use std::collections::HashMap;
     use utils::slugs::SlugifyStrategy;
@@ -687,6 +881,164 @@ mod tests {
         assert_eq!(authors.items[0].permalink, "http://a-website.com/authors/vincent-prouillet/");
     }
 
+    #[test]
+    fn can_populate_paginate_sources() {
+        let mut config = Config::default_for_test();
+        config.languages.get_mut("en").unwrap().taxonomies =
+            vec![TaxonomyConfig { name: "authors".to_string(), ..TaxonomyConfig::default() }];
+        config.slugify_taxonomies();
+        let mut library = Library::new(&config);
+        library.insert_section(create_section(
+            "content/blog/_index.md",
+            "en",
+            0,
+            false,
+            SortBy::Date,
+        ));
+        library.insert_section(create_section(
+            "content/news/_index.md",
+            "en",
+            0,
+            false,
+            SortBy::Date,
+        ));
+        let mut archive = create_section("content/archive/_index.md", "en", 0, false, SortBy::Date);
+        archive.meta.paginate_by = Some(2);
+        archive.meta.paginate_source = vec![
+            PaginateSource { section: Some("blog/_index.md".to_owned()), ..Default::default() },
+            PaginateSource { section: Some("@/news/_index.md".to_owned()), ..Default::default() },
+            PaginateSource {
+                taxonomy: Some("authors".to_owned()),
+                term: Some("Vincent Prouillet".to_owned()),
+                ..Default::default()
+            },
+        ];
+        library.insert_section(archive);
+        let mut by_author =
+            create_section("content/vincent/_index.md", "en", 0, false, SortBy::None);
+        by_author.meta.paginate_by = Some(2);
+        by_author.meta.paginate_source = vec![PaginateSource {
+            taxonomy: Some("authors".to_owned()),
+            term: Some("vincent-prouillet".to_owned()),
+            ..Default::default()
+        }];
+        library.insert_section(by_author);
+
+        let pages = vec![
+            ("content/blog/rust.md", "2022-01-01", "Vincent Prouillet"),
+            ("content/blog/python.md", "2022-03-03", "Someone else"),
+            ("content/news/release.md", "2022-02-02", "Vincent Prouillet"),
+        ];
+        for (path, date, author) in pages {
+            let mut page = create_page(path, "en", PageSort::Date(date));
+            page.meta.taxonomies.insert("authors".to_owned(), vec![author.to_owned()]);
+            library.insert_page(page);
+        }
+        let mut undated = create_page("content/news/undated.md", "en", PageSort::None);
+        undated.meta.taxonomies.insert("authors".to_owned(), vec!["Vincent Prouillet".to_owned()]);
+        library.insert_page(undated);
+        library.populate_sections(&config, Path::new("content"));
+        let taxonomies = library.find_taxonomies(&config);
+        library.populate_paginate_sources(&taxonomies, Path::new("content")).unwrap();
+
+        // Pages from several sources are merged and sorted, the ones that can't be sorted last
+        let archive = &library.sections[&PathBuf::from("content/archive/_index.md")];
+        assert!(archive.pages.is_empty());
+        assert_eq!(
+            archive.pages_to_paginate(),
+            &[
+                PathBuf::from("content/blog/python.md"),
+                PathBuf::from("content/news/release.md"),
+                PathBuf::from("content/blog/rust.md"),
+                PathBuf::from("content/news/undated.md"),
+            ]
+        );
+        let by_author = &library.sections[&PathBuf::from("content/vincent/_index.md")];
+        assert_eq!(
+            by_author.pages_to_paginate(),
+            &[
+                PathBuf::from("content/news/release.md"),
+                PathBuf::from("content/blog/rust.md"),
+                PathBuf::from("content/news/undated.md"),
+            ]
+        );
+        let blog = &library.sections[&PathBuf::from("content/blog/_index.md")];
+        assert_eq!(blog.pages_to_paginate(), &blog.pages[..]);
+
+        // Unknown sources are errors
+        library
+            .sections
+            .get_mut(&PathBuf::from("content/vincent/_index.md"))
+            .unwrap()
+            .meta
+            .paginate_source[0]
+            .term = Some("nobody".to_owned());
+        let err = library.populate_paginate_sources(&taxonomies, Path::new("content")).unwrap_err();
+        assert!(err.to_string().contains("Term `nobody` of taxonomy `authors`"));
+    }
+
+    #[test]
+    fn can_populate_related() {
+        let mut config = Config::default_for_test();
+        config.languages.insert("fr".to_owned(), LanguageOptions::default());
+        config.languages.get_mut("en").unwrap().taxonomies =
+            vec![TaxonomyConfig { name: "tags".to_string(), ..TaxonomyConfig::default() }];
+        config.slugify_taxonomies();
+        config.related.limit = 2;
+        let mut library = Library::new(&config);
+
+        let pages = vec![
+            (
+                "content/rust.md",
+                "2022-01-01",
+                vec!["rust", "code"],
+                "<p>Borrow checker and lifetimes</p>",
+            ),
+            ("content/zig.md", "2022-01-02", vec!["code"], "<p>Comptime and allocators</p>"),
+            ("content/more-rust.md", "2023-06-01", vec!["rust"], "<p>Lifetimes again</p>"),
+            ("content/cake.md", "2022-01-01", vec![], "<p>Flour, sugar, eggs</p>"),
+            ("content/rust.fr.md", "2022-01-01", vec![], "<p>Borrow checker</p>"),
+        ];
+        for (path, date, tags, content) in pages {
+            let lang = if path.ends_with(".fr.md") { "fr" } else { "en" };
+            let mut page = create_page(path, lang, PageSort::Date(date));
+            if !tags.is_empty() {
+                page.meta
+                    .taxonomies
+                    .insert("tags".to_owned(), tags.into_iter().map(|t| t.to_owned()).collect());
+            }
+            page.content = content.to_owned();
+            library.insert_page(page);
+        }
+        library.populate_related(&config);
+
+        fn related(library: &Library, path: &str) -> Vec<PathBuf> {
+            library.pages[&PathBuf::from(path)].related.clone()
+        }
+        assert_eq!(
+            related(&library, "content/rust.md"),
+            vec![PathBuf::from("content/more-rust.md"), PathBuf::from("content/zig.md")]
+        );
+        // Nothing in common with anything
+        assert!(related(&library, "content/cake.md").is_empty());
+        // Pages in other languages are never related
+        assert!(related(&library, "content/rust.fr.md").is_empty());
+
+        // Ranking by date only
+        config.related.taxonomies = 0.0;
+        config.related.content = 0.0;
+        config.related.date = 1.0;
+        library.populate_related(&config);
+        assert_eq!(
+            related(&library, "content/rust.md"),
+            vec![PathBuf::from("content/cake.md"), PathBuf::from("content/zig.md")]
+        );
+
+        config.related.limit = 0;
+        library.populate_related(&config);
+        assert!(related(&library, "content/rust.md").is_empty());
+    }
+
     #[test]
     fn can_make_multiple_language_taxonomies() {
         let mut config = Config::default_for_test();
@@ -763,6 +1115,34 @@ mod tests {
         assert_eq!(tax.items.len(), 4);
     }
 
+    #[test]
+    fn can_find_wiki_link_targets() {
+        let mut page1 = create_page("content/blog/hello.md", "en", PageSort::Title("Hello World"));
+        page1.slug = "hello".to_owned();
+        let mut page2 = create_page("content/notes/hello/index.md", "en", PageSort::None);
+        page2.slug = "greetings".to_owned();
+        let mut page3 = create_page("content/blog/hello.fr.md", "fr", PageSort::None);
+        page3.slug = "bonjour".to_owned();
+        let mut library = Library::default();
+        library.insert_page(page1);
+        library.insert_page(page2);
+        library.insert_page(page3);
+        let mut section = create_section("content/notes/_index.md", "en", 0, false, SortBy::None);
+        section.meta.title = Some("My Notes".to_owned());
+        library.insert_section(section);
+
+        let targets = library.find_wiki_link_targets();
+        assert_eq!(targets["en"]["hello world"], vec!["blog/hello.md"]);
+        assert_eq!(targets["en"]["greetings"], vec!["notes/hello/index.md"]);
+        // Both pages are named hello: one by its filename, the other by its directory
+        assert_eq!(targets["en"]["hello"], vec!["blog/hello.md", "notes/hello/index.md"]);
+        assert_eq!(targets["fr"]["hello"], vec!["blog/hello.fr.md"]);
+        assert_eq!(targets["fr"]["bonjour"], vec!["blog/hello.fr.md"]);
+        // Sections are named after their directory
+        assert_eq!(targets["en"]["notes"], vec!["notes/_index.md"]);
+        assert_eq!(targets["en"]["my notes"], vec!["notes/_index.md"]);
+    }
+
     #[test]
     fn can_fill_backlinks() {
         let mut page1 = create_page("page1.md", "en", PageSort::None);
@@ -1,6 +1,7 @@
 /// A page, can be a blog post or a basic page
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 
 use once_cell::sync::Lazy;
 use regex::Regex;
@@ -8,18 +9,20 @@ use tera::{Context as TeraContext, Tera};
 
 use config::Config;
 use errors::{Context, Result};
-use markdown::{RenderContext, render_content};
+use markdown::{
+    Citation, FenceCache, MarkdownTransform, RenderContext, bibliography_path, render_content,
+};
 use utils::slugs::slugify_paths;
 use utils::table_of_contents::Heading;
 use utils::templates::{ShortcodeDefinition, render_template};
-use utils::types::InsertAnchor;
+use utils::types::{ContentFormat, InsertAnchor};
 
 use crate::file_info::FileInfo;
-use crate::front_matter::{PageFrontMatter, split_page_content};
+use crate::front_matter::{PageFrontMatter, split_notebook_content, split_page_content};
 use crate::library::Library;
 use crate::ser::SerializingPage;
 use crate::utils::get_reading_analytics;
-use crate::utils::{find_related_assets, has_anchor};
+use crate::utils::{find_related_assets, has_anchor, render_output_format};
 use utils::anchors::has_anchor_id;
 use utils::fs::read_file;
 
@@ -31,6 +34,19 @@ static RFC3339_DATE: Lazy<Regex> = Lazy::new(|| {
     ).unwrap()
 });
 
+/// The slug of a page from its filename, or the name of its directory for colocated pages,
+/// without the date it starts with unless `slugify.paths_keep_dates` is set, and that date
+pub fn slug_from_filename(name: &str, config: &Config) -> (String, Option<String>) {
+    let Some(caps) = RFC3339_DATE.captures(name) else {
+        return (slugify_paths(name, config.slugify.paths), None);
+    };
+    let slug = match caps.name("slug") {
+        Some(slug) if !config.slugify.paths_keep_dates => slug.as_str(),
+        _ => name,
+    };
+    (slugify_paths(slug, config.slugify.paths), Some(caps["datetime"].to_string()))
+}
+
 #[derive(Clone, Debug, Default, PartialEq, Eq)]
 pub struct Page {
     /// All info about the actual file
@@ -82,6 +98,15 @@ pub struct Page {
     pub internal_links: Vec<(String, Option<String>)>,
     /// The list of all links to external webpages. They can be validated by the `link_checker`.
     pub external_links: Vec<String>,
+    /// The pages related to that one, most related first. Only filled when `related.limit` is set
+    /// in the config, after the markdown has been rendered
+    pub related: Vec<PathBuf>,
+    /// The bibliography entries cited in the content, in the order of the reference list
+    pub citations: Vec<Citation>,
+    /// The files included in code blocks of the content, which `zola serve` watches
+    pub included_files: Vec<PathBuf>,
+    /// The colocated images of the content resized for responsive images
+    pub responsive_images: Vec<PathBuf>,
 }
 
 impl Page {
@@ -100,19 +125,29 @@ impl Page {
         config: &Config,
         base_path: &Path,
     ) -> Result<Page> {
-        let (meta, content) = split_page_content(file_path, content)?;
+        // Notebooks are JSON with the front matter in their metadata
+        let (meta, content, notebook) =
+            if ContentFormat::from_path(file_path) == Some(ContentFormat::Notebook) {
+                let (meta, notebook) = split_notebook_content(file_path, content)?;
+                (meta, content, Some(notebook))
+            } else {
+                let (meta, content) = split_page_content(file_path, content)?;
+                (meta, content, None)
+            };
         let mut page = Page::new(file_path, meta, base_path);
 
         page.lang =
             page.file.find_language(&config.default_language, &config.other_languages_codes())?;
 
         page.raw_content = content.to_string();
-        let (word_count, reading_time) = get_reading_analytics(&page.raw_content);
+        // Only the Markdown cells of notebooks are counted, not the code and its outputs
+        let (word_count, reading_time) = match notebook {
+            Some(notebook) => get_reading_analytics(&notebook.markdown()),
+            None => get_reading_analytics(&page.raw_content),
+        };
         page.word_count = Some(word_count);
         page.reading_time = Some(reading_time);
 
-        let mut slug_from_dated_filename = None;
-
         let file_path_for_slug = if page.file.name == "index" {
             if let Some(parent) = page.file.path.parent() {
                 parent.file_name().unwrap().to_str().unwrap().to_string()
@@ -123,27 +158,18 @@ impl Page {
             page.file.name.to_string()
         };
 
-        if let Some(ref caps) = RFC3339_DATE.captures(&file_path_for_slug) {
-            if caps.name("slug").is_some() {
-                if !config.slugify.paths_keep_dates {
-                    slug_from_dated_filename =
-                        Some(caps.name("slug").unwrap().as_str().to_string());
-                }
-            }
-            if page.meta.date.is_none() {
-                page.meta.date = Some(caps.name("datetime").unwrap().as_str().to_string());
-                page.meta.date_to_datetime();
-            }
+        let (slug_from_filename, date_from_filename) =
+            slug_from_filename(&file_path_for_slug, config);
+        if page.meta.date.is_none()
+            && let Some(date) = date_from_filename
+        {
+            page.meta.date = Some(date);
+            page.meta.date_to_datetime();
         }
 
-        page.slug = {
-            if let Some(ref slug) = page.meta.slug {
-                slugify_paths(slug, config.slugify.paths)
-            } else if let Some(slug) = slug_from_dated_filename {
-                slugify_paths(&slug, config.slugify.paths)
-            } else {
-                slugify_paths(&file_path_for_slug, config.slugify.paths)
-            }
+        page.slug = match page.meta.slug {
+            Some(ref slug) => slugify_paths(slug, config.slugify.paths),
+            None => slug_from_filename,
         };
 
         page.path = if let Some(ref p) = page.meta.path {
@@ -204,6 +230,7 @@ impl Page {
 
     /// We need access to all pages url to render links relative to content
     /// so that can't happen at the same time as parsing
+    #[allow(clippy::too_many_arguments)]
     pub fn render_markdown(
         &mut self,
         permalinks: &HashMap<String, String>,
@@ -211,7 +238,15 @@ impl Page {
         config: &Config,
         anchor_insert: InsertAnchor,
         shortcode_definitions: &HashMap<String, ShortcodeDefinition>,
+        wiki_links: &HashMap<String, Vec<String>>,
+        imageproc: Option<&Arc<Mutex<imageproc::Processor>>>,
+        fence_cache: Option<&Arc<FenceCache>>,
+        transforms: &[Arc<dyn MarkdownTransform>],
     ) -> Result<()> {
+        let images = self.meta.images.as_ref().map(|images| images.apply(&config.markdown.images));
+        if let Some(ref images) = images {
+            images.validate()?;
+        }
         let mut context = RenderContext::new(
             tera,
             config,
@@ -221,7 +256,29 @@ impl Page {
             anchor_insert,
         );
         context.set_shortcode_definitions(shortcode_definitions);
+        context.set_wiki_links(wiki_links);
+        context.add_transforms(transforms);
+        if let Some(ref images) = images {
+            context.set_images(images);
+        }
+        if let Some(imageproc) = imageproc {
+            context.set_imageproc(imageproc.clone());
+        }
+        if let Some(fence_cache) = fence_cache {
+            context.set_fence_cache(fence_cache.clone());
+        }
+        context.set_heading_options(
+            self.meta.toc_min_level,
+            self.meta.toc_max_level,
+            self.meta.number_headings,
+        );
         context.set_current_page_path(&self.file.relative);
+        if let Some(format) = ContentFormat::from_path(&self.file.path) {
+            context.set_format(format);
+        }
+        if let Some(bibliography) = &self.meta.bibliography {
+            context.set_bibliography(bibliography);
+        }
         context.tera_context.insert("page", &SerializingPage::new(self, None, false));
 
         let res = render_content(&self.raw_content, &context)
@@ -232,17 +289,63 @@ impl Page {
         self.toc = res.toc;
         self.external_links = res.external_links;
         self.internal_links = res.internal_links;
+        self.citations = res.citations;
+        self.included_files = res.included_files;
+        self.responsive_images = res.responsive_images;
 
         Ok(())
     }
 
+    /// The BibTeX file the citations of the page are looked up in, if any
+    pub fn bibliography_path(&self, config: &Config) -> Option<PathBuf> {
+        bibliography_path(config, self.meta.bibliography.as_deref())
+    }
+
     /// Renders the page using the default layout, unless specified in front-matter
     pub fn render_html(&self, tera: &Tera, config: &Config, library: &Library) -> Result<String> {
-        let tpl_name = match self.meta.template {
+        render_template(
+            self.template_name(),
+            tera,
+            self.template_context(config, library),
+            &config.theme,
+        )
+        .with_context(|| format!("Failed to render page '{}'", self.file.path.display()))
+    }
+
+    /// The formats the page is rendered to in addition to HTML
+    pub fn output_formats<'a>(&'a self, config: &'a Config) -> &'a [String] {
+        self.meta.output_formats.as_deref().unwrap_or(&config.output_formats)
+    }
+
+    /// Renders the page for an output format, eg `page.json` for the `json` format.
+    /// Returns `None` if there is no template for that format and it wasn't asked for in the front-matter.
+    pub fn render_output_format(
+        &self,
+        format: &str,
+        tera: &Tera,
+        config: &Config,
+        library: &Library,
+    ) -> Result<Option<String>> {
+        render_output_format(
+            self.template_name(),
+            format,
+            self.meta.output_formats.is_some(),
+            tera,
+            self.template_context(config, library),
+            config,
+        )
+        .with_context(|| format!("Failed to render page '{}'", self.file.path.display()))
+    }
+
+    pub fn template_name(&self) -> &str {
+        match self.meta.template {
             Some(ref l) => l,
             None => "page.html",
-        };
+        }
+    }
 
+    /// The context the page templates are rendered with
+    pub fn template_context(&self, config: &Config, library: &Library) -> TeraContext {
         let mut context = TeraContext::new();
         context.insert("config", &config.serialize(&self.lang));
         context.insert("current_url", &self.permalink);
@@ -250,9 +353,7 @@ impl Page {
         context.insert("zola_version", env!("CARGO_PKG_VERSION"));
         context.insert("page", &self.serialize(library));
         context.insert("lang", &self.lang);
-
-        render_template(tpl_name, tera, context, &config.theme)
-            .with_context(|| format!("Failed to render page '{}'", self.file.path.display()))
+        context
     }
 
     /// Creates a vectors of asset URLs.
@@ -308,6 +409,7 @@ mod tests {
     use crate::Page;
     use config::{Config, LanguageOptions};
     use utils::slugs::SlugifyStrategy;
+    use utils::templates::{ShortcodeDefinition, ShortcodeFileType};
     use utils::types::InsertAnchor;
 
     #[test]
@@ -329,6 +431,10 @@ Hello world"#;
             &config,
             InsertAnchor::None,
             &HashMap::new(),
+            &HashMap::new(),
+            None,
+            None,
+            &[],
         )
         .unwrap();
 
@@ -338,6 +444,252 @@ Hello world"#;
         assert_eq!(page.content, "<p>Hello world</p>\n".to_string());
     }
 
+    #[test]
+    fn can_override_heading_options_in_front_matter() {
+        let config = Config::default_for_test();
+        let content = r#"
++++
+title = "Spec"
+toc_min_level = 2
+number_headings = true
++++
+# Spec
+
+## Scope
+
+## Terms"#;
+        let mut page =
+            Page::parse(Path::new("spec.md"), content, &config, &PathBuf::new()).unwrap();
+        page.render_markdown(
+            &HashMap::default(),
+            &ZOLA_TERA,
+            &config,
+            InsertAnchor::None,
+            &HashMap::new(),
+            &HashMap::new(),
+            None,
+            None,
+            &[],
+        )
+        .unwrap();
+
+        assert_eq!(page.toc.len(), 2);
+        assert_eq!(page.toc[1].number.as_deref(), Some("2"));
+        assert!(
+            page.content
+                .contains(r#"<h2 id="terms"><span class="heading-number">2</span> Terms</h2>"#)
+        );
+    }
+
+    #[test]
+    fn errors_on_invalid_heading_options_in_front_matter() {
+        let config = Config::default_for_test();
+        let content = r#"
++++
+toc_min_level = 7
++++
+# Spec"#;
+        let mut page =
+            Page::parse(Path::new("spec.md"), content, &config, &PathBuf::new()).unwrap();
+        let err = page
+            .render_markdown(
+                &HashMap::default(),
+                &ZOLA_TERA,
+                &config,
+                InsertAnchor::None,
+                &HashMap::new(),
+                &HashMap::new(),
+                None,
+                None,
+                &[],
+            )
+            .unwrap_err();
+
+        assert!(format!("{err:?}").contains("Invalid `toc_min_level = 7` and `toc_max_level = 6`"));
+    }
+
+    #[test]
+    fn can_use_page_values_in_shortcode_arguments() {
+        let config = Config::default_for_test();
+        let mut tera = ZOLA_TERA.clone();
+        tera.add_raw_template(
+            "shortcodes/figure.html",
+            r#"<img src="{{ src }}" alt="{{ opts.alt }}" width="{{ opts.width }}">"#,
+        )
+        .unwrap();
+        let mut shortcodes = HashMap::new();
+        shortcodes.insert(
+            "figure".to_string(),
+            ShortcodeDefinition::new(ShortcodeFileType::Html, "shortcodes/figure.html"),
+        );
+        let content = r#"
++++
+title = "Cats"
+[extra]
+cover = "cat.png"
++++
+{{ figure(src=page.extra.cover, opts={alt=page.title, width=300}) }}"#;
+        let mut page =
+            Page::parse(Path::new("cats.md"), content, &config, &PathBuf::new()).unwrap();
+        page.render_markdown(
+            &HashMap::default(),
+            &tera,
+            &config,
+            InsertAnchor::None,
+            &shortcodes,
+            &HashMap::new(),
+            None,
+            None,
+            &[],
+        )
+        .unwrap();
+        assert_eq!(page.content, r#"<img src="cat.png" alt="Cats" width="300">"#);
+
+        let content = "+++\n+++\n{{ figure(src=page.extra.cover) }}";
+        let mut page =
+            Page::parse(Path::new("cats.md"), content, &config, &PathBuf::new()).unwrap();
+        let err = page
+            .render_markdown(
+                &HashMap::default(),
+                &tera,
+                &config,
+                InsertAnchor::None,
+                &shortcodes,
+                &HashMap::new(),
+                None,
+                None,
+                &[],
+            )
+            .unwrap_err();
+        assert!(
+            format!("{:#}", err).contains("Found a reference to `page.extra.cover`"),
+            "{:#}",
+            err
+        );
+    }
+
+    #[test]
+    fn can_render_djot_page() {
+        let config = Config::default_for_test();
+        let content =
+            "+++\ntitle = \"Notes\"\n+++\n# Notes\n\nSome _notes_.\n\n<!-- more -->\n\nMore.\n";
+        let mut page =
+            Page::parse(Path::new("notes.dj"), content, &config, &PathBuf::new()).unwrap();
+        page.render_markdown(
+            &HashMap::default(),
+            &ZOLA_TERA,
+            &config,
+            InsertAnchor::None,
+            &HashMap::new(),
+            &HashMap::new(),
+            None,
+            None,
+            &[],
+        )
+        .unwrap();
+
+        assert_eq!(page.slug, "notes");
+        assert_eq!(page.meta.title.as_deref(), Some("Notes"));
+        assert_eq!(
+            page.summary.as_deref(),
+            Some("<h1 id=\"notes\">Notes</h1>\n<p>Some <em>notes</em>.</p>")
+        );
+        assert_eq!(page.toc[0].id, "notes");
+    }
+
+    #[test]
+    fn can_parse_notebook_page() {
+        let config = Config::default_for_test();
+        let content = r##"{
+  "metadata": {"zola": {"title": "Analysis", "date": "2024-03-01", "taxonomies": {"tags": ["data"]}}},
+  "cells": [
+    {"cell_type": "markdown", "metadata": {}, "source": ["# Analysis\n", "Three more words"]},
+    {"cell_type": "code", "metadata": {}, "source": "print('not counted')", "outputs": []}
+  ],
+  "nbformat": 4,
+  "nbformat_minor": 5
+}"##;
+        let page =
+            Page::parse(Path::new("2024-03-01-analysis.ipynb"), content, &config, &PathBuf::new())
+                .unwrap();
+
+        assert_eq!(page.slug, "analysis");
+        assert_eq!(page.meta.title.as_deref(), Some("Analysis"));
+        assert_eq!(page.meta.datetime_tuple, Some((2024, 3, 1)));
+        assert_eq!(page.meta.taxonomies["tags"], ["data"]);
+        assert_eq!(page.word_count, Some(4));
+    }
+
+    #[test]
+    fn errors_on_notebook_without_front_matter() {
+        let config = Config::default_for_test();
+        let content = r#"{"metadata": {}, "cells": [], "nbformat": 4, "nbformat_minor": 5}"#;
+        let res = Page::parse(Path::new("analysis.ipynb"), content, &config, &PathBuf::new());
+
+        assert_eq!(
+            res.unwrap_err().to_string(),
+            "Couldn't find front matter in `analysis.ipynb`. Did you forget to add a `zola` object to the notebook metadata?"
+        );
+    }
+
+    #[test]
+    fn can_cite_from_bibliography_in_front_matter() {
+        let tmp_dir = tempdir().expect("create temp dir");
+        create_dir(tmp_dir.path().join("content")).unwrap();
+        let mut f = File::create(tmp_dir.path().join("content").join("refs.bib")).unwrap();
+        f.write_all(b"@book{knuth84, author = {Donald E. Knuth}, title = {The TeXbook}}").unwrap();
+        let mut config = Config::default_for_test();
+        config.markdown.base_path = Some(tmp_dir.path().to_path_buf());
+        let content = r#"
++++
+bibliography = "@/refs.bib"
++++
+As in [@knuth84]."#;
+        let mut page =
+            Page::parse(Path::new("post.md"), content, &config, &PathBuf::new()).unwrap();
+        page.render_markdown(
+            &HashMap::default(),
+            &ZOLA_TERA,
+            &config,
+            InsertAnchor::None,
+            &HashMap::new(),
+            &HashMap::new(),
+            None,
+            None,
+            &[],
+        )
+        .unwrap();
+
+        assert!(page.content.contains(r##"[<a href="#ref-knuth84">1</a>]"##));
+        assert!(page.content.contains(r#"<li id="ref-knuth84""#));
+        assert_eq!(page.citations.len(), 1);
+        assert_eq!(page.citations[0].fields["title"], "The TeXbook");
+    }
+
+    #[test]
+    fn can_render_output_formats_from_front_matter() {
+        let mut config = Config::default_for_test();
+        config.output_formats = vec!["txt".to_string()];
+        let content = r#"
++++
+title = "Hello"
+output_formats = ["json"]
++++
+Hello world"#;
+        let page = Page::parse(Path::new("post.md"), content, &config, &PathBuf::new()).unwrap();
+        assert_eq!(page.output_formats(&config), &["json".to_string()]);
+
+        // Asking for a format in the front matter requires its template
+        let mut tera = tera::Tera::default();
+        let library = crate::Library::default();
+        let err = page.render_output_format("json", &tera, &config, &library).unwrap_err();
+        assert!(format!("{:?}", err).contains("needs a `page.json` template"));
+
+        tera.add_raw_template("page.json", "{{ page.title }}").unwrap();
+        let output = page.render_output_format("json", &tera, &config, &library).unwrap();
+        assert_eq!(output, Some("Hello".to_string()));
+    }
+
     #[test]
     fn can_parse_author() {
         let config = Config::default_for_test();
@@ -357,6 +709,10 @@ Hello world"#;
             &config,
             InsertAnchor::None,
             &HashMap::new(),
+            &HashMap::new(),
+            None,
+            None,
+            &[],
         )
         .unwrap();
 
@@ -527,6 +883,10 @@ Hello world
             &config,
             InsertAnchor::None,
             &HashMap::new(),
+            &HashMap::new(),
+            None,
+            None,
+            &[],
         )
         .unwrap();
         assert_eq!(page.summary, Some("<p>Hello world</p>".to_string()));
@@ -561,6 +921,10 @@ And here's another. [^3]
             &config,
             InsertAnchor::None,
             &HashMap::new(),
+            &HashMap::new(),
+            None,
+            None,
+            &[],
         )
         .unwrap();
         assert_eq!(
@@ -595,6 +959,10 @@ And here's another. [^3]
             &config,
             InsertAnchor::None,
             &HashMap::new(),
+            &HashMap::new(),
+            None,
+            None,
+            &[],
         )
         .unwrap();
         assert_eq!(
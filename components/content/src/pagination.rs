@@ -12,6 +12,7 @@ use crate::Section;
 use crate::library::Library;
 use crate::ser::{SectionSerMode, SerializingPage, SerializingSection};
 use crate::taxonomies::{Taxonomy, TaxonomyTerm};
+use crate::utils::render_output_format;
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 enum PaginationRoot<'a> {
@@ -59,7 +60,7 @@ pub struct Paginator<'a> {
     pub permalink: String,
     path: String,
     pub paginate_path: String,
-    template: String,
+    pub template: String,
     /// Whether this is the index section, we need it for the template name
     is_index: bool,
 }
@@ -70,8 +71,8 @@ impl<'a> Paginator<'a> {
     pub fn from_section(section: &'a Section, library: &'a Library) -> Paginator<'a> {
         let paginate_by = section.meta.paginate_by.unwrap();
         let mut paginator = Paginator {
-            all_pages: Cow::from(&section.pages[..]),
-            pagers: Vec::with_capacity(section.pages.len() / paginate_by),
+            all_pages: Cow::from(section.pages_to_paginate()),
+            pagers: Vec::with_capacity(section.pages_to_paginate().len() / paginate_by),
             paginate_by,
             paginate_reversed: section.meta.paginate_reversed,
             root: PaginationRoot::Section(section),
@@ -225,6 +226,38 @@ impl<'a> Paginator<'a> {
         tera: &Tera,
         library: &Library,
     ) -> Result<String> {
+        let context = self.pager_context(pager, config, library);
+        render_template(&self.template, tera, context, &config.theme)
+            .with_context(|| format!("Failed to render pager {}", pager.index))
+    }
+
+    /// Renders a pager for an output format, like `Section::render_output_format` does
+    /// for sections that are not paginated
+    pub fn render_pager_output_format(
+        &self,
+        pager: &Pager,
+        format: &str,
+        config: &Config,
+        tera: &Tera,
+        library: &Library,
+    ) -> Result<Option<String>> {
+        let from_front_matter = match self.root {
+            PaginationRoot::Section(s) => s.meta.output_formats.is_some(),
+            PaginationRoot::Taxonomy(..) => false,
+        };
+        render_output_format(
+            &self.template,
+            format,
+            from_front_matter,
+            tera,
+            self.pager_context(pager, config, library),
+            config,
+        )
+        .with_context(|| format!("Failed to render pager {}", pager.index))
+    }
+
+    /// The context a pager is rendered with
+    pub fn pager_context(&self, pager: &Pager, config: &Config, library: &Library) -> Context {
         let mut context = Context::new();
         match self.root {
             PaginationRoot::Section(s) => {
@@ -245,9 +278,7 @@ impl<'a> Paginator<'a> {
         context.insert("current_url", &pager.permalink);
         context.insert("current_path", &pager.path);
         context.insert("paginator", &self.build_paginator_context(pager));
-
-        render_template(&self.template, tera, context, &config.theme)
-            .with_context(|| format!("Failed to render pager {}", pager.index))
+        context
     }
 }
 
@@ -0,0 +1,199 @@
+//! Finds the related pages of each page, ranking the other pages of the same language by:
+//! - how many taxonomy terms they share (Jaccard index of the terms)
+//! - how similar their rendered content is (cosine similarity of the TF-IDF vectors)
+//! - how close their dates are (halved every `date_half_life` days)
+//!
+//! Everything is computed from sorted collections so the result is the same across builds.
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::PathBuf;
+
+use config::Related;
+use rayon::prelude::*;
+
+use crate::Page;
+
+/// Words shorter than that are mostly noise and are ignored for the content similarity
+const MIN_WORD_LENGTH: usize = 3;
+
+/// What we need from a page to compare it to the others
+struct Document<'a> {
+    path: &'a PathBuf,
+    terms: BTreeSet<(&'a str, &'a str)>,
+    /// The TF-IDF vector of the content, normalised to a length of 1
+    words: BTreeMap<String, f64>,
+    /// Unix timestamp in seconds
+    timestamp: Option<i64>,
+}
+
+/// Returns the words of the HTML, ignoring the tags and entities
+fn words(html: &str) -> Vec<String> {
+    let mut text = String::with_capacity(html.len());
+    let mut in_tag = false;
+    let mut in_entity = false;
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => {
+                in_tag = false;
+                text.push(' ');
+            }
+            '&' if !in_tag => in_entity = true,
+            ';' if in_entity => {
+                in_entity = false;
+                text.push(' ');
+            }
+            _ if in_tag => (),
+            _ if in_entity && (c.is_alphanumeric() || c == '#') => (),
+            _ => {
+                in_entity = false;
+                text.push(c);
+            }
+        }
+    }
+
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| w.chars().count() >= MIN_WORD_LENGTH)
+        .map(|w| w.to_lowercase())
+        .collect()
+}
+
+/// Computes the normalised TF-IDF vector of every document
+fn tf_idf(contents: &[Vec<String>]) -> Vec<BTreeMap<String, f64>> {
+    let term_frequencies: Vec<BTreeMap<&str, usize>> = contents
+        .iter()
+        .map(|words| {
+            let mut counts = BTreeMap::new();
+            for word in words {
+                *counts.entry(word.as_str()).or_insert(0) += 1;
+            }
+            counts
+        })
+        .collect();
+
+    let mut document_frequencies: BTreeMap<&str, usize> = BTreeMap::new();
+    for counts in &term_frequencies {
+        for word in counts.keys() {
+            *document_frequencies.entry(word).or_insert(0) += 1;
+        }
+    }
+
+    let num_documents = contents.len() as f64;
+    term_frequencies
+        .iter()
+        .zip(contents)
+        .map(|(counts, words)| {
+            let mut vector: BTreeMap<String, f64> = counts
+                .iter()
+                .map(|(word, count)| {
+                    let tf = *count as f64 / words.len() as f64;
+                    let idf = (num_documents / document_frequencies[word] as f64).ln();
+                    (word.to_string(), tf * idf)
+                })
+                .filter(|(_, weight)| *weight > 0.0)
+                .collect();
+            let norm = vector.values().map(|w| w * w).sum::<f64>().sqrt();
+            if norm > 0.0 {
+                vector.values_mut().for_each(|w| *w /= norm);
+            }
+            vector
+        })
+        .collect()
+}
+
+fn taxonomy_score(a: &Document, b: &Document) -> f64 {
+    let shared = a.terms.intersection(&b.terms).count();
+    if shared == 0 {
+        return 0.0;
+    }
+    shared as f64 / (a.terms.len() + b.terms.len() - shared) as f64
+}
+
+fn content_score(a: &Document, b: &Document) -> f64 {
+    let (smaller, larger) =
+        if a.words.len() <= b.words.len() { (&a.words, &b.words) } else { (&b.words, &a.words) };
+    smaller.iter().filter_map(|(word, weight)| larger.get(word).map(|w| w * weight)).sum()
+}
+
+fn date_score(a: &Document, b: &Document, half_life: u32) -> f64 {
+    match (a.timestamp, b.timestamp) {
+        (Some(a), Some(b)) => {
+            let days = (a - b).abs() as f64 / 86400.0;
+            0.5_f64.powf(days / half_life as f64)
+        }
+        _ => 0.0,
+    }
+}
+
+/// Returns the paths of the related pages of each page given, in the same order.
+/// All the pages given are expected to be in the same language.
+pub(crate) fn find_related(pages: &[&Page], config: &Related) -> Vec<Vec<PathBuf>> {
+    let contents: Vec<_> = if config.content > 0.0 {
+        pages.par_iter().map(|p| words(&p.content)).collect()
+    } else {
+        vec![Vec::new(); pages.len()]
+    };
+    let documents: Vec<_> = pages
+        .iter()
+        .zip(tf_idf(&contents))
+        .map(|(page, words)| Document {
+            path: &page.file.path,
+            terms: page
+                .meta
+                .taxonomies
+                .iter()
+                .flat_map(|(name, terms)| terms.iter().map(move |t| (name.as_str(), t.as_str())))
+                .collect(),
+            words,
+            timestamp: page.meta.datetime.map(|d| d.unix_timestamp()),
+        })
+        .collect();
+
+    documents
+        .par_iter()
+        .enumerate()
+        .map(|(i, document)| {
+            let mut scored: Vec<(f64, &PathBuf)> = documents
+                .iter()
+                .enumerate()
+                .filter(|(j, _)| *j != i)
+                .map(|(_, other)| {
+                    let score = config.taxonomies * taxonomy_score(document, other)
+                        + config.content * content_score(document, other)
+                        + config.date * date_score(document, other, config.date_half_life);
+                    (score, other.path)
+                })
+                .filter(|(score, _)| *score > 0.0)
+                .collect();
+            scored.sort_by(|a, b| match b.0.total_cmp(&a.0) {
+                Ordering::Equal => a.1.cmp(b.1),
+                o => o,
+            });
+            scored.into_iter().take(config.limit).map(|(_, p)| p.clone()).collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_get_words_from_html() {
+        assert_eq!(
+            words("<p class=\"intro\">Hello <em>Rust</em>&amp;Zola, it&#39;s 2024!</p>"),
+            vec!["hello", "rust", "zola", "2024"]
+        );
+    }
+
+    #[test]
+    fn tf_idf_ignores_words_in_every_document() {
+        let contents =
+            vec![words("the rust compiler"), words("the zola compiler"), words("the cake recipe")];
+        let vectors = tf_idf(&contents);
+        assert!(!vectors[0].contains_key("the"));
+        assert!(vectors[0].contains_key("compiler"));
+        let norm: f64 = vectors[2].values().map(|w| w * w).sum();
+        assert!((norm - 1.0).abs() < 1e-9);
+    }
+}
@@ -1,11 +1,12 @@
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 
 use tera::{Context as TeraContext, Tera};
 
 use config::Config;
 use errors::{Context, Result};
-use markdown::{RenderContext, render_content};
+use markdown::{FenceCache, MarkdownTransform, RenderContext, render_content};
 use utils::fs::read_file;
 use utils::net::is_external_link;
 use utils::table_of_contents::Heading;
@@ -15,7 +16,7 @@ use crate::file_info::FileInfo;
 use crate::front_matter::{SectionFrontMatter, split_section_content};
 use crate::library::Library;
 use crate::ser::{SectionSerMode, SerializingSection};
-use crate::utils::{find_related_assets, get_reading_analytics, has_anchor};
+use crate::utils::{find_related_assets, get_reading_analytics, has_anchor, render_output_format};
 
 // Default is used to create a default index section if there is no _index.md in the root content directory
 #[derive(Clone, Debug, Default, PartialEq, Eq)]
@@ -42,6 +43,9 @@ pub struct Section {
     pub pages: Vec<PathBuf>,
     /// All pages that cannot be sorted in this section
     pub ignored_pages: Vec<PathBuf>,
+    /// The pages found from `paginate_source` in the front matter, if it is set.
+    /// Only known once the taxonomies have been found.
+    pub paginate_source_pages: Option<Vec<PathBuf>>,
     /// The list of parent sections relative paths
     pub ancestors: Vec<String>,
     /// All direct subsections
@@ -62,6 +66,8 @@ pub struct Section {
     pub internal_links: Vec<(String, Option<String>)>,
     /// The list of all links to external webpages. They can be validated by the `link_checker`.
     pub external_links: Vec<String>,
+    /// The files included in code blocks of the content, which `zola serve` watches
+    pub included_files: Vec<PathBuf>,
 }
 
 impl Section {
@@ -144,12 +150,17 @@ impl Section {
 
     /// We need access to all pages url to render links relative to content
     /// so that can't happen at the same time as parsing
+    #[allow(clippy::too_many_arguments)]
     pub fn render_markdown(
         &mut self,
         permalinks: &HashMap<String, String>,
         tera: &Tera,
         config: &Config,
         shortcode_definitions: &HashMap<String, ShortcodeDefinition>,
+        wiki_links: &HashMap<String, Vec<String>>,
+        imageproc: Option<&Arc<Mutex<imageproc::Processor>>>,
+        fence_cache: Option<&Arc<FenceCache>>,
+        transforms: &[Arc<dyn MarkdownTransform>],
     ) -> Result<()> {
         let mut context = RenderContext::new(
             tera,
@@ -160,6 +171,19 @@ impl Section {
             self.meta.insert_anchor_links.unwrap_or(config.markdown.insert_anchor_links),
         );
         context.set_shortcode_definitions(shortcode_definitions);
+        context.set_wiki_links(wiki_links);
+        context.add_transforms(transforms);
+        if let Some(imageproc) = imageproc {
+            context.set_imageproc(imageproc.clone());
+        }
+        if let Some(fence_cache) = fence_cache {
+            context.set_fence_cache(fence_cache.clone());
+        }
+        context.set_heading_options(
+            self.meta.toc_min_level,
+            self.meta.toc_max_level,
+            self.meta.number_headings,
+        );
         context.set_current_page_path(&self.file.relative);
         context
             .tera_context
@@ -178,23 +202,56 @@ impl Section {
         }
 
         self.internal_links = res.internal_links;
+        self.included_files = res.included_files;
 
         Ok(())
     }
 
     /// Renders the page using the default layout, unless specified in front-matter
     pub fn render_html(&self, tera: &Tera, config: &Config, library: &Library) -> Result<String> {
-        let tpl_name = self.get_template_name();
+        render_template(
+            self.get_template_name(),
+            tera,
+            self.template_context(config, library),
+            &config.theme,
+        )
+        .with_context(|| format!("Failed to render section '{}'", self.file.path.display()))
+    }
+
+    /// The formats the section is rendered to in addition to HTML
+    pub fn output_formats<'a>(&'a self, config: &'a Config) -> &'a [String] {
+        self.meta.output_formats.as_deref().unwrap_or(&config.output_formats)
+    }
+
+    /// Renders the section for an output format, eg `section.json` for the `json` format.
+    /// Returns `None` if there is no template for that format and it wasn't asked for in the front-matter.
+    pub fn render_output_format(
+        &self,
+        format: &str,
+        tera: &Tera,
+        config: &Config,
+        library: &Library,
+    ) -> Result<Option<String>> {
+        render_output_format(
+            self.get_template_name(),
+            format,
+            self.meta.output_formats.is_some(),
+            tera,
+            self.template_context(config, library),
+            config,
+        )
+        .with_context(|| format!("Failed to render section '{}'", self.file.path.display()))
+    }
 
+    /// The context the section templates are rendered with
+    pub fn template_context(&self, config: &Config, library: &Library) -> TeraContext {
         let mut context = TeraContext::new();
         context.insert("config", &config.serialize(&self.lang));
         context.insert("current_url", &self.permalink);
         context.insert("current_path", &self.path);
         context.insert("section", &SerializingSection::new(self, SectionSerMode::Full(library)));
         context.insert("lang", &self.lang);
-
-        render_template(tpl_name, tera, context, &config.theme)
-            .with_context(|| format!("Failed to render section '{}'", self.file.path.display()))
+        context
     }
 
     /// Is this the index section?
@@ -226,6 +283,11 @@ impl Section {
         }
     }
 
+    /// The pages to paginate: the ones from `paginate_source` if it is set, the section pages otherwise
+    pub fn pages_to_paginate(&self) -> &[PathBuf] {
+        self.paginate_source_pages.as_deref().unwrap_or(&self.pages)
+    }
+
     pub fn serialize<'a>(&'a self, library: &'a Library) -> SerializingSection<'a> {
         SerializingSection::new(self, SectionSerMode::Full(library))
     }
@@ -326,6 +388,33 @@ Bonjour le monde"#
         assert_eq!(section.permalink, "http://a-website.com/fr/hello/nested/");
     }
 
+    #[test]
+    fn validates_paginate_source() {
+        let config = Config::default();
+        let parse = |front_matter: &str| {
+            let content = format!("+++\n{}\n+++\n", front_matter);
+            Section::parse(Path::new("content/all/_index.md"), &content, &config, &PathBuf::new())
+        };
+
+        let section = parse(
+            r#"paginate_by = 5
+paginate_source = [{ section = "blog/_index.md" }, { taxonomy = "tags", term = "rust" }]"#,
+        )
+        .unwrap();
+        assert_eq!(section.meta.paginate_source.len(), 2);
+
+        let err = parse(r#"paginate_source = [{ section = "blog/_index.md" }]"#).unwrap_err();
+        assert!(format!("{:?}", err).contains("requires `paginate_by`"));
+        let err = parse(
+            r#"paginate_by = 5
+paginate_source = [{ taxonomy = "tags" }]"#,
+        )
+        .unwrap_err();
+        assert!(
+            format!("{:?}", err).contains("either a `section` or both a `taxonomy` and a `term`")
+        );
+    }
+
     // https://zola.discourse.group/t/rfc-i18n/13/17?u=keats
     #[test]
     fn can_make_links_to_translated_sections_without_double_trailing_slash() {
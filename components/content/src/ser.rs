@@ -5,6 +5,7 @@ use serde::Serialize;
 
 use crate::library::Library;
 use crate::{Page, Section};
+use markdown::Citation;
 use tera::{Map, Value};
 use utils::table_of_contents::Heading;
 
@@ -61,6 +62,7 @@ pub struct SerializingPage<'a> {
     components: &'a [String],
     summary: &'a Option<String>,
     toc: &'a [Heading],
+    citations: &'a [Citation],
     word_count: Option<usize>,
     reading_time: Option<usize>,
     assets: &'a [String],
@@ -68,6 +70,7 @@ pub struct SerializingPage<'a> {
     lang: &'a str,
     lower: Option<Box<SerializingPage<'a>>>,
     higher: Option<Box<SerializingPage<'a>>>,
+    related: Vec<SerializingPage<'a>>,
     translations: Vec<TranslatedContent<'a>>,
     backlinks: Vec<BackLink<'a>>,
 }
@@ -84,6 +87,7 @@ impl<'a> SerializingPage<'a> {
         }
         let mut lower = None;
         let mut higher = None;
+        let mut related = vec![];
         let mut translations = vec![];
         let mut backlinks = vec![];
 
@@ -99,6 +103,12 @@ impl<'a> SerializingPage<'a> {
                     .higher
                     .as_ref()
                     .map(|p| Box::new(Self::new(&lib.pages[p], Some(lib), false)));
+                related = page
+                    .related
+                    .iter()
+                    .filter_map(|p| lib.pages.get(p))
+                    .map(|p| Self::new(p, Some(lib), false))
+                    .collect();
             }
 
             backlinks = find_backlinks(&page.file.relative, lib);
@@ -125,6 +135,7 @@ impl<'a> SerializingPage<'a> {
             components: &page.components,
             summary: &page.summary,
             toc: &page.toc,
+            citations: &page.citations,
             word_count: page.word_count,
             reading_time: page.reading_time,
             assets: &page.serialized_assets,
@@ -132,6 +143,7 @@ impl<'a> SerializingPage<'a> {
             lang: &page.lang,
             lower,
             higher,
+            related,
             translations,
             backlinks,
         }
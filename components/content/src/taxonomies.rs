@@ -205,7 +205,7 @@ impl Taxonomy {
         config: &Config,
         library: &Library,
     ) -> Result<String> {
-        let context = self.build_term_context(item, config, library);
+        let context = self.term_context(item, config, library);
 
         // Check for taxon-specific template, or use generic as fallback.
         let specific_template = format!("{}/single.html", self.kind.name);
@@ -216,12 +216,8 @@ impl Taxonomy {
             .with_context(|| format!("Failed to render single term {} page.", self.kind.name))
     }
 
-    fn build_term_context(
-        &self,
-        item: &TaxonomyTerm,
-        config: &Config,
-        library: &Library,
-    ) -> Context {
+    /// The context the page of a term is rendered with
+    pub fn term_context(&self, item: &TaxonomyTerm, config: &Config, library: &Library) -> Context {
         let mut context = Context::new();
         context.insert("config", &config.serialize(&self.lang));
         context.insert("lang", &self.lang);
@@ -238,6 +234,19 @@ impl Taxonomy {
         config: &Config,
         library: &Library,
     ) -> Result<String> {
+        let context = self.list_context(config, library);
+
+        // Check for taxon-specific template, or use generic as fallback.
+        let specific_template = format!("{}/list.html", self.kind.name);
+        let template = check_template_fallbacks(&specific_template, tera, &config.theme)
+            .unwrap_or("taxonomy_list.html");
+
+        render_template(template, tera, context, &config.theme)
+            .with_context(|| format!("Failed to render a list of {} page.", self.kind.name))
+    }
+
+    /// The context the list of the terms is rendered with
+    pub fn list_context(&self, config: &Config, library: &Library) -> Context {
         let mut context = Context::new();
         context.insert("config", &config.serialize(&self.lang));
         let terms: Vec<SerializedTaxonomyTerm> = self
@@ -250,14 +259,7 @@ impl Taxonomy {
         context.insert("taxonomy", &self.kind);
         context.insert("current_url", &self.permalink);
         context.insert("current_path", &self.path);
-
-        // Check for taxon-specific template, or use generic as fallback.
-        let specific_template = format!("{}/list.html", self.kind.name);
-        let template = check_template_fallbacks(&specific_template, tera, &config.theme)
-            .unwrap_or("taxonomy_list.html");
-
-        render_template(template, tera, context, &config.theme)
-            .with_context(|| format!("Failed to render a list of {} page.", self.kind.name))
+        context
     }
 
     pub fn to_serialized<'a>(&'a self, library: &'a Library) -> SerializedTaxonomy<'a> {
@@ -306,7 +308,7 @@ mod tests {
         let term = TaxonomyTerm::new("rust", &conf.default_language, "tags", pages, &conf);
         let lib = Library::default();
 
-        let ctx = tax.build_term_context(&term, &conf, &lib);
+        let ctx = tax.term_context(&term, &conf, &lib);
 
         assert_eq!(ctx.get("current_path").and_then(|x| x.as_str()), Some("/tags/rust/"));
 
@@ -22,3 +22,13 @@ pub enum SortBy {
     /// No sorting
     None,
 }
+
+/// Whether content is published at build time according to its `publish_date` and `expiry_date`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PublishState {
+    Published,
+    /// The publish date is in the future
+    Future,
+    /// The expiry date has passed
+    Expired,
+}
@@ -4,8 +4,11 @@ use unicode_segmentation::UnicodeSegmentation;
 use walkdir::WalkDir;
 
 use config::Config;
+use errors::{Context, Result, bail};
+use tera::{Context as TeraContext, Tera};
 use utils::fs::is_temp_file;
 use utils::table_of_contents::Heading;
+use utils::templates::{check_template_fallbacks, render_template};
 
 pub fn has_anchor(headings: &[Heading], anchor: &str) -> bool {
     for heading in headings {
@@ -20,6 +23,37 @@ pub fn has_anchor(headings: &[Heading], anchor: &str) -> bool {
     false
 }
 
+/// The name of the template rendering the given output format, eg `page.json` for the `json`
+/// format of content rendered with `page.html`
+pub fn output_format_template(html_template: &str, format: &str) -> String {
+    let stem = html_template.strip_suffix(".html").unwrap_or(html_template);
+    format!("{}.{}", stem, format)
+}
+
+/// Renders the template of an output format with the same context as the HTML one.
+/// Formats enabled for the whole site are skipped for content that doesn't have a template for
+/// them but it is an error if the format was asked for in the front matter.
+pub fn render_output_format(
+    html_template: &str,
+    format: &str,
+    from_front_matter: bool,
+    tera: &Tera,
+    context: TeraContext,
+    config: &Config,
+) -> Result<Option<String>> {
+    let tpl_name = output_format_template(html_template, format);
+    if check_template_fallbacks(&tpl_name, tera, &config.theme).is_none() {
+        if from_front_matter {
+            bail!("Output format `{}` needs a `{}` template but it wasn't found", format, tpl_name);
+        }
+        return Ok(None);
+    }
+
+    render_template(&tpl_name, tera, context, &config.theme)
+        .with_context(|| format!("Failed to render the `{}` output format", format))
+        .map(Some)
+}
+
 /// Looks into the current folder for the path and see if there's anything that is not a .md
 /// file. Those will be copied next to the rendered .html file
 /// If `recursive` is set to `true`, it will add all subdirectories assets as well. This should
@@ -36,14 +70,11 @@ pub fn find_related_assets(path: &Path, config: &Config, recursive: bool) -> Vec
     for entry in builder.into_iter().filter_map(std::result::Result::ok) {
         let entry_path = entry.path();
 
-        if entry_path.is_file() && !is_temp_file(entry_path) {
-            match entry_path.extension() {
-                Some(e) => match e.to_str() {
-                    Some("md") => continue,
-                    _ => assets.push(entry_path.to_path_buf()),
-                },
-                None => assets.push(entry_path.to_path_buf()),
-            }
+        if entry_path.is_file()
+            && !is_temp_file(entry_path)
+            && config.content_format(entry_path).is_none()
+        {
+            assets.push(entry_path.to_path_buf());
         }
     }
 
@@ -118,6 +149,23 @@ mod tests {
         }
     }
 
+    #[test]
+    fn keeps_files_in_disabled_content_formats_as_assets() {
+        let tmp_dir = tempdir().expect("create temp dir");
+        let path = tmp_dir.path();
+        File::create(path.join("index.md")).unwrap();
+        File::create(path.join("notes.org")).unwrap();
+        File::create(path.join("draft.dj")).unwrap();
+
+        let assets = find_related_assets(path, &Config::default(), false);
+        assert_eq!(assets, [path.join("draft.dj"), path.join("notes.org")]);
+
+        let mut config = Config::default();
+        config.content_formats = vec!["org".to_string()];
+        let assets = find_related_assets(path, &config, false);
+        assert_eq!(assets, [path.join("draft.dj")]);
+    }
+
     #[test]
     fn can_find_related_assets_non_recursive() {
         let tmp_dir = tempdir().expect("create temp dir");
@@ -157,6 +205,7 @@ mod tests {
                 id: "1".to_string(),
                 permalink: String::new(),
                 title: String::new(),
+                number: None,
                 children: vec![],
             },
             Heading {
@@ -164,6 +213,7 @@ mod tests {
                 id: "1-1".to_string(),
                 permalink: String::new(),
                 title: String::new(),
+                number: None,
                 children: vec![],
             },
             Heading {
@@ -171,6 +221,7 @@ mod tests {
                 id: "1-1-1".to_string(),
                 permalink: String::new(),
                 title: String::new(),
+                number: None,
                 children: vec![],
             },
             Heading {
@@ -178,6 +229,7 @@ mod tests {
                 id: "1-2".to_string(),
                 permalink: String::new(),
                 title: String::new(),
+                number: None,
                 children: vec![],
             },
         ];
@@ -192,12 +244,14 @@ mod tests {
             id: "1".to_string(),
             permalink: String::new(),
             title: String::new(),
+            number: None,
             children: vec![
                 Heading {
                     level: 2,
                     id: "1-1".to_string(),
                     permalink: String::new(),
                     title: String::new(),
+                    number: None,
                     children: vec![],
                 },
                 Heading {
@@ -205,6 +259,7 @@ mod tests {
                     id: "1-1-1".to_string(),
                     permalink: String::new(),
                     title: String::new(),
+                    number: None,
                     children: vec![],
                 },
                 Heading {
@@ -212,6 +267,7 @@ mod tests {
                     id: "1-2".to_string(),
                     permalink: String::new(),
                     title: String::new(),
+                    number: None,
                     children: vec![],
                 },
             ],
@@ -258,4 +314,11 @@ mod tests {
         assert_eq!(word_count, 4);
         assert_eq!(reading_time, 1);
     }
+
+    #[test]
+    fn can_get_output_format_template() {
+        assert_eq!(output_format_template("page.html", "json"), "page.json");
+        assert_eq!(output_format_template("blog/post.html", "txt"), "blog/post.txt");
+        assert_eq!(output_format_template("custom", "json"), "custom.json");
+    }
 }
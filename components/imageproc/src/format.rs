@@ -79,6 +79,7 @@ impl Format {
                 }?;
                 Ok(Avif { quality: q, speed: s })
             }
+            // Kept in sync with config::IMAGE_FORMATS
             _ => Err(anyhow!("Invalid image format: {}", format)),
         }
     }
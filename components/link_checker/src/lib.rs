@@ -1,15 +1,19 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
 use std::result;
 use std::sync::{Arc, RwLock};
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use once_cell::sync::Lazy;
 use reqwest::header::{ACCEPT, HeaderMap};
 use reqwest::{StatusCode, blocking::Client};
+use serde::{Deserialize, Serialize};
 
 use config::LinkChecker;
-use errors::anyhow;
+use errors::Context;
 
-use utils::anchors::has_anchor_id;
+use utils::anchors::find_anchor_ids;
+use utils::fs::{create_file, read_file};
 
 pub type Result = result::Result<StatusCode, String>;
 
@@ -30,6 +34,8 @@ pub fn message(res: &Result) -> String {
 // Keep history of link checks so a rebuild doesn't have to check again
 static LINKS: Lazy<Arc<RwLock<HashMap<String, Result>>>> =
     Lazy::new(|| Arc::new(RwLock::new(HashMap::new())));
+// The link checks of the previous runs, see `load_cache`
+static DISK_CACHE: Lazy<RwLock<Option<DiskCache>>> = Lazy::new(|| RwLock::new(None));
 // Make sure to create only a single Client so that we can reuse the connections
 static CLIENT: Lazy<Client> = Lazy::new(|| {
     Client::builder()
@@ -38,6 +44,121 @@ static CLIENT: Lazy<Client> = Lazy::new(|| {
         .expect("reqwest client build")
 });
 
+/// The result of checking a URL, without its fragment, as stored on disk
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct CachedLink {
+    /// The HTTP status code, if we got a response
+    status: Option<u16>,
+    /// What went wrong, if the URL is broken
+    error: Option<String>,
+    /// When the URL was checked, as a UNIX timestamp in seconds
+    checked_at: u64,
+    /// The ids and names of the elements of the page, only there if we had to check an anchor
+    anchors: Option<BTreeSet<String>>,
+}
+
+impl CachedLink {
+    fn new(res: &Result, anchors: Option<BTreeSet<String>>) -> Self {
+        CachedLink {
+            status: res.as_ref().ok().map(|code| code.as_u16()),
+            error: res.as_ref().err().cloned(),
+            checked_at: now(),
+            anchors,
+        }
+    }
+
+    fn result(&self) -> Result {
+        match (&self.error, self.status.and_then(|s| StatusCode::from_u16(s).ok())) {
+            (None, Some(code)) => Ok(code),
+            (Some(error), _) => Err(error.clone()),
+            (None, None) => Err("Invalid cached link check".to_string()),
+        }
+    }
+
+    /// Whether that check can still be used according to the TTLs in the config
+    fn is_fresh(&self, config: &LinkChecker, now: u64) -> bool {
+        let ttl = if is_valid(&self.result()) {
+            config.success_cache_ttl
+        } else {
+            config.failure_cache_ttl
+        };
+        now.saturating_sub(self.checked_at) < ttl
+    }
+}
+
+#[derive(Debug, Default)]
+struct DiskCache {
+    path: PathBuf,
+    /// Whether to ignore the checks loaded from the disk, they are still overwritten when saving
+    refresh: bool,
+    links: HashMap<String, CachedLink>,
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default()
+}
+
+/// Loads the link checks of the previous runs from the given file so `check_url` can re-use them
+/// according to the TTLs of the config.
+/// If `refresh` is true, all the links are checked again but the file is still updated.
+pub fn load_cache(path: &Path, refresh: bool) -> errors::Result<()> {
+    let links = if path.exists() {
+        // A corrupted cache is not worth failing for, we'll just check everything again
+        serde_json::from_str(&read_file(path)?).unwrap_or_default()
+    } else {
+        HashMap::new()
+    };
+    *DISK_CACHE.write().unwrap() = Some(DiskCache { path: path.to_path_buf(), refresh, links });
+    Ok(())
+}
+
+/// Writes the link checks to the file given to `load_cache`, if it was called
+pub fn save_cache() -> errors::Result<()> {
+    let guard = DISK_CACHE.read().unwrap();
+    if let Some(cache) = guard.as_ref() {
+        // Sorted so the file doesn't change for nothing
+        let links: BTreeMap<_, _> = cache.links.iter().collect();
+        let content = serde_json::to_string_pretty(&links)?;
+        create_file(&cache.path, &content)
+            .with_context(|| format!("Failed to save the link checks to {:?}", cache.path))?;
+    }
+    Ok(())
+}
+
+/// Splits the URL between the page and the anchor to look for in it, if any
+fn split_anchor<'a>(url: &'a str, config: &LinkChecker) -> (&'a str, Option<&'a str>) {
+    let check_anchor = !config.skip_anchor_prefixes.iter().any(|prefix| url.starts_with(prefix));
+    if check_anchor && has_anchor(url) {
+        let index = url.find('#').unwrap();
+        (&url[..index], Some(&url[index + 1..]))
+    } else {
+        (url.split('#').next().unwrap(), None)
+    }
+}
+
+/// Checks the anchor against the anchors found on the page, if the page itself is fine
+fn check_anchor(res: Result, anchor: Option<&str>, anchors: Option<&BTreeSet<String>>) -> Result {
+    match (res, anchor, anchors) {
+        (Ok(_), Some(anchor), Some(anchors)) if !anchors.contains(anchor) => {
+            Err(format!("Anchor `#{}` not found on page", anchor))
+        }
+        (res, _, _) => res,
+    }
+}
+
+/// Finds a check of the page from a previous run still valid for that URL
+fn find_in_disk_cache(page: &str, anchor: Option<&str>, config: &LinkChecker) -> Option<Result> {
+    let guard = DISK_CACHE.read().unwrap();
+    let cache = guard.as_ref().filter(|c| !c.refresh)?;
+    let cached = cache.links.get(page).filter(|c| c.is_fresh(config, now()))?;
+    let res = cached.result();
+    // We need the anchors of the page to check the anchor of a working link
+    if anchor.is_some() && res.is_ok() && cached.anchors.is_none() {
+        return None;
+    }
+    Some(check_anchor(res, anchor, cached.anchors.as_ref()))
+}
+
 pub fn check_url(url: &str, config: &LinkChecker) -> Result {
     {
         let guard = LINKS.read().unwrap();
@@ -46,34 +167,35 @@ pub fn check_url(url: &str, config: &LinkChecker) -> Result {
         }
     }
 
+    let (page, anchor) = split_anchor(url, config);
+    if config.uses_cache()
+        && let Some(res) = find_in_disk_cache(page, anchor, config)
+    {
+        LINKS.write().unwrap().insert(url.to_string(), res.clone());
+        return res;
+    }
+
     let mut headers = HeaderMap::new();
     headers.insert(ACCEPT, "text/html".parse().unwrap());
     headers.append(ACCEPT, "*/*".parse().unwrap());
 
     // TODO: pass the client to the check_url, do not pass the config
 
-    let check_anchor = !config.skip_anchor_prefixes.iter().any(|prefix| url.starts_with(prefix));
-
     // Need to actually do the link checking
-    let res = match CLIENT.get(url).headers(headers).send() {
-        Ok(ref mut response) if check_anchor && has_anchor(url) => {
-            let body = {
-                let mut buf: Vec<u8> = vec![];
-                response.copy_to(&mut buf).map_err(|e| e.to_string())?;
-                match String::from_utf8(buf) {
-                    Ok(s) => s,
-                    Err(_) => return Err("The page didn't return valid UTF-8".to_string()),
-                }
-            };
-
-            match check_page_for_anchor(url, body) {
-                Ok(_) => Ok(response.status()),
-                Err(e) => Err(e.to_string()),
+    let (page_res, anchors) = match CLIENT.get(url).headers(headers).send() {
+        Ok(ref mut response) if anchor.is_some() => {
+            let mut buf: Vec<u8> = vec![];
+            match response.copy_to(&mut buf).map_err(|e| e.to_string()) {
+                Ok(_) => match String::from_utf8(buf) {
+                    Ok(body) => (Ok(response.status()), Some(find_anchor_ids(&body))),
+                    Err(_) => (Err("The page didn't return valid UTF-8".to_string()), None),
+                },
+                Err(e) => (Err(e), None),
             }
         }
         Ok(response) => {
             if response.status().is_success() || response.status() == StatusCode::NOT_MODIFIED {
-                Ok(response.status())
+                (Ok(response.status()), None)
             } else {
                 let error_string = if response.status().is_informational() {
                     format!("Informational status code ({}) received", response.status())
@@ -87,12 +209,19 @@ pub fn check_url(url: &str, config: &LinkChecker) -> Result {
                     format!("Non-success status code ({}) received", response.status())
                 };
 
-                Err(error_string)
+                (Err(error_string), None)
             }
         }
-        Err(e) => Err(e.to_string()),
+        Err(e) => (Err(e.to_string()), None),
     };
 
+    if config.uses_cache()
+        && let Some(cache) = DISK_CACHE.write().unwrap().as_mut()
+    {
+        cache.links.insert(page.to_string(), CachedLink::new(&page_res, anchors.clone()));
+    }
+
+    let res = check_anchor(page_res, anchor, anchors.as_ref());
     LINKS.write().unwrap().insert(url.to_string(), res.clone());
     res
 }
@@ -107,23 +236,14 @@ fn has_anchor(url: &str) -> bool {
     }
 }
 
-fn check_page_for_anchor(url: &str, body: String) -> errors::Result<()> {
-    let index = url.find('#').unwrap();
-    let anchor = url.get(index + 1..).unwrap();
-
-    if has_anchor_id(&body, anchor) {
-        Ok(())
-    } else {
-        Err(anyhow!("Anchor `#{}` not found on page", anchor))
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::{
-        LINKS, LinkChecker, check_page_for_anchor, check_url, has_anchor, is_valid, message,
+        CachedLink, LINKS, LinkChecker, check_anchor, check_url, find_anchor_ids, has_anchor,
+        is_valid, load_cache, message, save_cache, split_anchor,
     };
     use reqwest::StatusCode;
+    use tempfile::tempdir;
 
     // NOTE: HTTP mock paths below are randomly generated to avoid name
     // collisions. Mocks with the same path can sometimes bleed between tests
@@ -273,7 +393,8 @@ mod tests {
     fn can_validate_anchors_with_double_quotes() {
         let url = "https://doc.rust-lang.org/std/iter/trait.Iterator.html#method.collect";
         let body = r#"<body><h3 id="method.collect">collect</h3></body>"#.to_string();
-        let res = check_page_for_anchor(url, body);
+        let (_, anchor) = split_anchor(url, &LinkChecker::default());
+        let res = check_anchor(Ok(StatusCode::OK), anchor, Some(&find_anchor_ids(&body)));
         assert!(res.is_ok());
     }
 
@@ -282,7 +403,8 @@ mod tests {
     fn can_validate_anchors_in_capital() {
         let url = "https://doc.rust-lang.org/std/iter/trait.Iterator.html#method.collect";
         let body = r#"<body><h3 ID="method.collect">collect</h3></body>"#.to_string();
-        let res = check_page_for_anchor(url, body);
+        let (_, anchor) = split_anchor(url, &LinkChecker::default());
+        let res = check_anchor(Ok(StatusCode::OK), anchor, Some(&find_anchor_ids(&body)));
         assert!(res.is_ok());
     }
 
@@ -290,7 +412,8 @@ mod tests {
     fn can_validate_anchors_with_single_quotes() {
         let url = "https://doc.rust-lang.org/std/iter/trait.Iterator.html#method.collect";
         let body = "<body><h3 id='method.collect'>collect</h3></body>".to_string();
-        let res = check_page_for_anchor(url, body);
+        let (_, anchor) = split_anchor(url, &LinkChecker::default());
+        let res = check_anchor(Ok(StatusCode::OK), anchor, Some(&find_anchor_ids(&body)));
         assert!(res.is_ok());
     }
 
@@ -298,7 +421,8 @@ mod tests {
     fn can_validate_anchors_without_quotes() {
         let url = "https://doc.rust-lang.org/std/iter/trait.Iterator.html#method.collect";
         let body = "<body><h3 id=method.collect>collect</h3></body>".to_string();
-        let res = check_page_for_anchor(url, body);
+        let (_, anchor) = split_anchor(url, &LinkChecker::default());
+        let res = check_anchor(Ok(StatusCode::OK), anchor, Some(&find_anchor_ids(&body)));
         assert!(res.is_ok());
     }
 
@@ -306,7 +430,8 @@ mod tests {
     fn can_validate_anchors_with_name_attr() {
         let url = "https://doc.rust-lang.org/std/iter/trait.Iterator.html#method.collect";
         let body = r#"<body><h3 name="method.collect">collect</h3></body>"#.to_string();
-        let res = check_page_for_anchor(url, body);
+        let (_, anchor) = split_anchor(url, &LinkChecker::default());
+        let res = check_anchor(Ok(StatusCode::OK), anchor, Some(&find_anchor_ids(&body)));
         assert!(res.is_ok());
     }
 
@@ -314,7 +439,8 @@ mod tests {
     fn can_fail_when_anchor_not_found() {
         let url = "https://doc.rust-lang.org/std/iter/trait.Iterator.html#me";
         let body = r#"<body><h3 id="method.collect">collect</h3></body>"#.to_string();
-        let res = check_page_for_anchor(url, body);
+        let (_, anchor) = split_anchor(url, &LinkChecker::default());
+        let res = check_anchor(Ok(StatusCode::OK), anchor, Some(&find_anchor_ids(&body)));
         assert!(res.is_err());
     }
 
@@ -393,4 +519,58 @@ mod tests {
         let nonexistent = format!("{}{}", server.url(), "/guvqcqwmth#nonexistent");
         assert!(!is_valid(&check_url(&nonexistent, &config)));
     }
+
+    #[test]
+    fn uses_ttl_depending_on_the_result() {
+        let config =
+            LinkChecker { success_cache_ttl: 100, failure_cache_ttl: 10, ..Default::default() };
+        let success = CachedLink::new(&Ok(StatusCode::OK), None);
+        let failure = CachedLink::new(&Err("Client error".to_string()), None);
+        let now = success.checked_at;
+        assert!(success.is_fresh(&config, now + 50));
+        assert!(!success.is_fresh(&config, now + 100));
+        assert!(failure.is_fresh(&config, now + 5));
+        assert!(!failure.is_fresh(&config, now + 50));
+        assert_eq!(failure.result(), Err("Client error".to_string()));
+        // Nothing is kept without TTLs
+        assert!(!success.is_fresh(&LinkChecker::default(), now));
+    }
+
+    #[test]
+    fn can_reuse_link_checks_from_disk() {
+        let dir = tempdir().unwrap();
+        let cache_path = dir.path().join("links.json");
+        let config = LinkChecker { success_cache_ttl: 3600, ..Default::default() };
+        let mut server = mockito::Server::new();
+        let mock = server
+            .mock("GET", "/pq8mzsk2vd")
+            .with_header("Content-Type", "text/html")
+            .with_body(r#"<body><h2 id="cached">Cached</h2></body>"#)
+            .expect(1)
+            .create();
+        let url = format!("{}/pq8mzsk2vd", server.url());
+        let with_anchor = format!("{}#cached", url);
+        let with_missing_anchor = format!("{}#missing", url);
+
+        load_cache(&cache_path, false).unwrap();
+        assert!(is_valid(&check_url(&with_anchor, &config)));
+        save_cache().unwrap();
+        mock.assert();
+        assert!(std::fs::read_to_string(&cache_path).unwrap().contains(r#""cached""#));
+
+        // A new run, without the in-memory checks and the server
+        drop(server);
+        LINKS.write().unwrap().retain(|k, _| !k.starts_with(&url));
+        load_cache(&cache_path, false).unwrap();
+        assert!(is_valid(&check_url(&with_anchor, &config)));
+        assert_eq!(
+            check_url(&with_missing_anchor, &config),
+            Err("Anchor `#missing` not found on page".to_string())
+        );
+
+        // Refreshing ignores the cache
+        LINKS.write().unwrap().retain(|k, _| !k.starts_with(&url));
+        load_cache(&cache_path, true).unwrap();
+        assert!(!is_valid(&check_url(&with_anchor, &config)));
+    }
 }
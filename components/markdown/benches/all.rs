@@ -96,7 +96,7 @@ fn bench_render_content_with_highlighting(b: &mut test::Bencher) {
         &permalinks_ctx,
         InsertAnchor::None,
     );
-    let shortcode_def = utils::templates::get_shortcodes(&tera);
+    let shortcode_def = utils::templates::get_shortcodes(&tera).unwrap();
     context.set_shortcode_definitions(&shortcode_def);
     b.iter(|| render_content(CONTENT, &context).unwrap());
 }
@@ -117,7 +117,7 @@ fn bench_render_content_without_highlighting(b: &mut test::Bencher) {
         &permalinks_ctx,
         InsertAnchor::None,
     );
-    let shortcode_def = utils::templates::get_shortcodes(&tera);
+    let shortcode_def = utils::templates::get_shortcodes(&tera).unwrap();
     context.set_shortcode_definitions(&shortcode_def);
     b.iter(|| render_content(CONTENT, &context).unwrap());
 }
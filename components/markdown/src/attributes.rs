@@ -0,0 +1,457 @@
+//! Attribute lists like `{#id .class key=value}` following images, links and blocks, enabled with
+//! `markdown.attribute_lists`.
+//!
+//! They are found in the events of pulldown-cmark before rendering:
+//! - right after an image or a link: `![diagram](a.png){.wide loading=eager}`
+//! - on the last line of a paragraph, for that paragraph
+//! - alone in a paragraph, for the block before it: a table, a list, a blockquote, a code block or
+//!   a paragraph
+use std::collections::HashMap;
+use std::ops::Range;
+
+use pulldown_cmark::{Alignment, CowStr, Event, Tag, TagEnd};
+use pulldown_cmark_escape::escape_html;
+
+/// The events of pulldown-cmark with their range in the markdown
+type OffsetEvents<'a> = Vec<(Event<'a>, Range<usize>)>;
+
+/// The attributes of an attribute list, in the order they were written
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct Attributes {
+    attributes: Vec<(String, String)>,
+}
+
+impl Attributes {
+    /// Parses an attribute list, including its braces.
+    /// Returns `None` if it isn't one, eg `{not an attribute list}`.
+    pub(crate) fn parse(s: &str) -> Option<Attributes> {
+        let inner = s.trim().strip_prefix('{')?.strip_suffix('}')?;
+        let mut attributes = Attributes::default();
+        let mut chars = inner.chars().peekable();
+
+        loop {
+            while chars.next_if(|c| c.is_whitespace()).is_some() {}
+            let Some(c) = chars.next() else { break };
+            match c {
+                '#' | '.' => {
+                    let mut value = String::new();
+                    while let Some(c) = chars.next_if(|c| is_name_char(*c)) {
+                        value.push(c);
+                    }
+                    if value.is_empty() {
+                        return None;
+                    }
+                    attributes.push(if c == '#' { "id" } else { "class" }, value);
+                }
+                c if c.is_ascii_alphabetic() || c == '_' => {
+                    let mut key = c.to_string();
+                    while let Some(c) = chars.next_if(|c| is_name_char(*c)) {
+                        key.push(c);
+                    }
+                    // Event handlers like `onclick` would let the content run scripts
+                    if key.to_ascii_lowercase().starts_with("on") {
+                        return None;
+                    }
+                    chars.next_if_eq(&'=')?;
+                    let closing_quote = match chars.peek() {
+                        Some('"') => Some('"'),
+                        Some('\'') => Some('\''),
+                        // Smart punctuation already turned the quotes into typographic ones
+                        Some('“') => Some('”'),
+                        Some('‘') => Some('’'),
+                        _ => None,
+                    };
+                    let mut value = String::new();
+                    if let Some(quote) = closing_quote {
+                        chars.next();
+                        loop {
+                            match chars.next()? {
+                                c if c == quote => break,
+                                c => value.push(c),
+                            }
+                        }
+                    } else {
+                        while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+                            value.push(c);
+                        }
+                        if value.is_empty() {
+                            return None;
+                        }
+                    }
+                    attributes.push(&key, value);
+                }
+                _ => return None,
+            }
+        }
+
+        if attributes.attributes.is_empty() { None } else { Some(attributes) }
+    }
+
+    /// Adds an attribute, merging classes and replacing any other attribute with the same name
+    fn push(&mut self, key: &str, value: String) {
+        match self.attributes.iter_mut().find(|(k, _)| k == key) {
+            Some((_, v)) if key == "class" => {
+                v.push(' ');
+                v.push_str(&value);
+            }
+            Some((_, v)) => *v = value,
+            None => self.attributes.push((key.to_string(), value)),
+        }
+    }
+
+    /// Adds the attributes of another list, eg when several lists apply to the same block
+    pub(crate) fn extend(&mut self, other: Attributes) {
+        for (key, value) in other.attributes {
+            self.push(&key, value);
+        }
+    }
+
+    pub(crate) fn contains(&self, key: &str) -> bool {
+        self.attributes.iter().any(|(k, _)| k == key)
+    }
+
+    /// Writes the attributes as ` key="value"`, escaping the values
+    pub(crate) fn write_html(&self, buffer: &mut String) {
+        for (key, value) in &self.attributes {
+            buffer.push(' ');
+            buffer.push_str(key);
+            buffer.push_str("=\"");
+            escape_html(&mut *buffer, value).expect("Could not write to buffer");
+            buffer.push('"');
+        }
+    }
+
+    /// Adds the attributes to the opening tag at the start of the HTML given.
+    /// The classes and `rel` values are added to the existing ones while the other attributes
+    /// replace the existing ones.
+    pub(crate) fn apply(&self, html: &str) -> String {
+        let Some(end) = html.find('>') else {
+            return html.to_string();
+        };
+        let (tag, rest) = html.split_at(end);
+        let (tag, self_closing) = match tag.strip_suffix('/') {
+            Some(t) => (t.trim_end(), true),
+            None => (tag, false),
+        };
+        let name_end = tag.find(char::is_whitespace).unwrap_or(tag.len());
+
+        let mut buffer = String::with_capacity(html.len() + 50);
+        buffer.push_str(&tag[..name_end]);
+        let mut existing = Vec::new();
+        for (key, value) in parse_html_attributes(&tag[name_end..]) {
+            let added = self.attributes.iter().find(|(k, _)| *k == key);
+            match added {
+                Some((_, v)) if key == "class" || key == "rel" => {
+                    let mut value = value.to_string();
+                    value.push(' ');
+                    escape_html(&mut value, v).expect("Could not write to buffer");
+                    existing.push((key, value));
+                }
+                Some(_) => (),
+                None => existing.push((key, value.to_string())),
+            }
+        }
+        for (key, value) in &existing {
+            buffer.push(' ');
+            buffer.push_str(key);
+            buffer.push_str("=\"");
+            buffer.push_str(value);
+            buffer.push('"');
+        }
+        let added = Attributes {
+            attributes: self
+                .attributes
+                .iter()
+                .filter(|(k, _)| {
+                    !((k == "class" || k == "rel") && existing.iter().any(|(key, _)| key == k))
+                })
+                .cloned()
+                .collect(),
+        };
+        added.write_html(&mut buffer);
+        if self_closing {
+            buffer.push_str(" /");
+        }
+        buffer.push_str(rest);
+        buffer
+    }
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '-' | '_' | ':')
+}
+
+/// Parses the `key="value"` attributes of the tags we generate, which are always quoted
+fn parse_html_attributes(s: &str) -> Vec<(&str, &str)> {
+    let mut attributes = Vec::new();
+    let mut rest = s.trim_start();
+    while let Some(eq) = rest.find("=\"") {
+        let key = rest[..eq].trim();
+        let Some(len) = rest[eq + 2..].find('"') else { break };
+        attributes.push((key, &rest[eq + 2..eq + 2 + len]));
+        rest = rest[eq + 2 + len + 1..].trim_start();
+    }
+    attributes
+}
+
+/// Renders a single opening tag the way pulldown-cmark would
+pub(crate) fn tag_to_html(tag: Tag) -> String {
+    let mut html = String::new();
+    pulldown_cmark::html::push_html(&mut html, std::iter::once(Event::Start(tag)));
+    html
+}
+
+fn is_block_with_attributes(tag: &Tag) -> bool {
+    matches!(
+        tag,
+        Tag::Paragraph | Tag::BlockQuote(_) | Tag::CodeBlock(_) | Tag::List(_) | Tag::Table(_)
+    )
+}
+
+fn is_block(tag: &Tag) -> bool {
+    !matches!(
+        tag,
+        Tag::Emphasis
+            | Tag::Strong
+            | Tag::Strikethrough
+            | Tag::Superscript
+            | Tag::Subscript
+            | Tag::Link { .. }
+            | Tag::Image { .. }
+    )
+}
+
+fn is_inline_end(tag: &TagEnd) -> bool {
+    matches!(
+        tag,
+        TagEnd::Emphasis
+            | TagEnd::Strong
+            | TagEnd::Strikethrough
+            | TagEnd::Superscript
+            | TagEnd::Subscript
+            | TagEnd::Link
+            | TagEnd::Image
+    )
+}
+
+/// Returns the text of the events if they are only text, as pulldown-cmark splits it on
+/// some characters
+fn only_text(events: &[(Event, Range<usize>)]) -> Option<String> {
+    let mut text = String::new();
+    for (event, _) in events {
+        match event {
+            Event::Text(t) => text.push_str(t),
+            _ => return None,
+        }
+    }
+    Some(text)
+}
+
+/// Removes the attribute lists from the events and returns, along with the remaining events,
+/// the attributes of the start events they apply to by index in the remaining events.
+pub(crate) fn extract_attributes<'a>(
+    events: OffsetEvents<'a>,
+) -> (OffsetEvents<'a>, HashMap<usize, Attributes>) {
+    let mut attributes: HashMap<usize, Attributes> = HashMap::new();
+    let mut out: OffsetEvents = Vec::with_capacity(events.len());
+    // The index of the start of the open blocks and of the last block closed inside them
+    let mut blocks: Vec<(usize, Option<usize>)> = vec![(0, None)];
+    let mut inlines: Vec<usize> = Vec::new();
+    let mut events = events.into_iter().peekable();
+
+    while let Some((event, range)) = events.next() {
+        match event {
+            Event::Start(ref tag) if is_block(tag) => {
+                blocks.push((out.len(), None));
+                out.push((event, range));
+            }
+            Event::Start(Tag::Link { .. } | Tag::Image { .. }) => {
+                inlines.push(out.len());
+                out.push((event, range));
+            }
+            Event::End(TagEnd::Link | TagEnd::Image) => {
+                let start = inlines.pop().expect("a link or an image to be open");
+                out.push((event, range));
+                // The attribute list can be split over several text events
+                let mut text = String::new();
+                let mut consumed = Vec::new();
+                while let Some((Event::Text(t), _)) = events.peek() {
+                    if consumed.is_empty() && !t.starts_with('{') {
+                        break;
+                    }
+                    text.push_str(t);
+                    consumed.push(events.next().unwrap());
+                    if text.contains('}') {
+                        break;
+                    }
+                }
+                let attributes_end = text.find('}').map(|i| i + 1);
+                match attributes_end.and_then(|end| Attributes::parse(&text[..end]).zip(Some(end)))
+                {
+                    Some((attrs, end)) => {
+                        attributes.entry(start).or_default().extend(attrs);
+                        let remaining = &text[end..];
+                        if !remaining.is_empty() {
+                            let range = consumed.last().unwrap().1.clone();
+                            let start = range.end.saturating_sub(remaining.len()).max(range.start);
+                            out.push((
+                                Event::Text(CowStr::from(remaining.to_string())),
+                                start..range.end,
+                            ));
+                        }
+                    }
+                    None => out.extend(consumed),
+                }
+            }
+            Event::End(TagEnd::Paragraph) => {
+                let (start, _) = blocks.pop().expect("a paragraph to be open");
+                let content = &out[start + 1..];
+                if let Some(attrs) = only_text(content).and_then(|t| Attributes::parse(&t)) {
+                    // Alone in a paragraph: applies to the previous block if there is one
+                    let previous = blocks.last().and_then(|(_, last)| *last);
+                    if let Some(previous) = previous.filter(
+                        |i| matches!(&out[*i].0, Event::Start(t) if is_block_with_attributes(t)),
+                    ) {
+                        attributes.entry(previous).or_default().extend(attrs);
+                        out.truncate(start);
+                        continue;
+                    }
+                } else if let Some(line_start) =
+                    content.iter().rposition(|(e, _)| matches!(e, Event::SoftBreak))
+                {
+                    // On the last line of the paragraph
+                    let line_start = start + 1 + line_start;
+                    if let Some(attrs) =
+                        only_text(&out[line_start + 1..]).and_then(|t| Attributes::parse(&t))
+                    {
+                        out.truncate(line_start);
+                        attributes.entry(start).or_default().extend(attrs);
+                    }
+                }
+                out.push((event, range));
+                blocks.last_mut().unwrap().1 = Some(start);
+            }
+            Event::End(ref tag) if !is_inline_end(tag) => {
+                let (start, _) = blocks.pop().expect("a block to be open");
+                out.push((event, range));
+                blocks.last_mut().unwrap().1 = Some(start);
+            }
+            _ => out.push((event, range)),
+        }
+    }
+
+    (out, attributes)
+}
+
+/// Adds the attributes to the tables, given in the order of the tables in the events.
+/// This is done after the transforms have run so they still see the tables as such.
+pub(crate) fn apply_table_attributes<'a>(
+    events: Vec<Event<'a>>,
+    mut table_attributes: Vec<Option<Attributes>>,
+) -> Vec<Event<'a>> {
+    if table_attributes.iter().all(Option::is_none) {
+        return events;
+    }
+    table_attributes.reverse();
+
+    let mut out = Vec::with_capacity(events.len());
+    // pulldown-cmark would use the alignments of the previous table since we don't give it the
+    // start of the table so we write the cells ourselves
+    let mut alignments: Option<Vec<Alignment>> = None;
+    let mut in_head = false;
+    let mut cell = 0;
+    for event in events {
+        match event {
+            Event::Start(Tag::Table(aligns)) => match table_attributes.pop().flatten() {
+                Some(attrs) => {
+                    out.push(Event::Html(attrs.apply("<table>").into()));
+                    alignments = Some(aligns);
+                }
+                None => {
+                    alignments = None;
+                    out.push(Event::Start(Tag::Table(aligns)));
+                }
+            },
+            Event::Start(Tag::TableHead) | Event::Start(Tag::TableRow) => {
+                in_head = matches!(event, Event::Start(Tag::TableHead));
+                cell = 0;
+                out.push(event);
+            }
+            Event::Start(Tag::TableCell) if alignments.is_some() => {
+                let tag = if in_head { "th" } else { "td" };
+                let style = match alignments.as_ref().unwrap().get(cell) {
+                    Some(Alignment::Left) => " style=\"text-align: left\"",
+                    Some(Alignment::Center) => " style=\"text-align: center\"",
+                    Some(Alignment::Right) => " style=\"text-align: right\"",
+                    _ => "",
+                };
+                out.push(Event::Html(format!("<{tag}{style}>").into()));
+            }
+            Event::End(TagEnd::TableCell) => {
+                cell += 1;
+                out.push(event);
+            }
+            _ => out.push(event),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_parse_attribute_lists() {
+        let attrs =
+            Attributes::parse("{#intro .wide .dark loading=eager title=\"A <b>title</b>\"}")
+                .unwrap();
+        assert_eq!(
+            attrs.attributes,
+            vec![
+                ("id".to_string(), "intro".to_string()),
+                ("class".to_string(), "wide dark".to_string()),
+                ("loading".to_string(), "eager".to_string()),
+                ("title".to_string(), "A <b>title</b>".to_string()),
+            ]
+        );
+        let mut html = String::new();
+        attrs.write_html(&mut html);
+        assert_eq!(
+            html,
+            " id=\"intro\" class=\"wide dark\" loading=\"eager\" title=\"A &lt;b&gt;title&lt;/b&gt;\""
+        );
+    }
+
+    #[test]
+    fn rejects_things_that_are_not_attribute_lists() {
+        for s in [
+            "{}",
+            "{not an attribute list}",
+            "{.}",
+            "{key=}",
+            "{key=\"unclosed}",
+            "{{.a}}",
+            "{.wide onclick=\"alert(1)\"}",
+            "{onError=x}",
+        ] {
+            assert_eq!(Attributes::parse(s), None, "{s}");
+        }
+    }
+
+    #[test]
+    fn can_apply_attributes_to_tag() {
+        let attrs = Attributes::parse("{.wide rel=me target=_self}").unwrap();
+        assert_eq!(
+            attrs.apply(
+                "<a class=\"external\" rel=\"nofollow\" target=\"_blank\" href=\"https://a.com\">"
+            ),
+            "<a class=\"external wide\" rel=\"nofollow me\" href=\"https://a.com\" target=\"_self\">"
+        );
+        assert_eq!(attrs.apply("<p>"), "<p class=\"wide\" rel=\"me\" target=\"_self\">");
+        assert_eq!(
+            Attributes::parse("{#a}").unwrap().apply("<ol start=\"3\">\n"),
+            "<ol start=\"3\" id=\"a\">\n"
+        );
+    }
+}
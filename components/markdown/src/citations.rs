@@ -0,0 +1,275 @@
+//! Citations of the entries of a BibTeX bibliography, written `[@key]`, `[@key, p. 12]` or
+//! `[@key; @other, chap. 3]` in the markdown, and the reference list appended to the content.
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use config::Config;
+use errors::{Result, anyhow, bail};
+use once_cell::sync::Lazy;
+use pulldown_cmark::Event;
+use pulldown_cmark_escape::escape_html;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// A group of citations between square brackets
+static CITATION_GROUP_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\[(@[^\[\]]+)\]").unwrap());
+
+/// A single citation of a group, with an optional locator after the comma
+static CITATION_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^@(\w[\w:.\-/]*)\s*(?:,\s*(.+?))?\s*$").unwrap());
+
+/// The parsed bibliographies, along with the modification time of their file so they are
+/// parsed again when they change during `zola serve`
+#[allow(clippy::type_complexity)]
+static BIBLIOGRAPHIES: Lazy<Mutex<HashMap<PathBuf, (Option<SystemTime>, Arc<Bibliography>)>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// An entry of the bibliography cited in a page
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Citation {
+    /// The citation key of the entry
+    pub key: String,
+    /// The number of the entry in the reference list, in the order of the first citation
+    pub number: usize,
+    /// The BibTeX type of the entry, eg `article` or `book`
+    pub entry_type: String,
+    /// The fields of the entry with lowercased names, eg `author`, `title` or `year`
+    pub fields: BTreeMap<String, String>,
+}
+
+#[derive(Debug)]
+struct Entry {
+    entry_type: String,
+    fields: BTreeMap<String, String>,
+}
+
+/// The entries of a bibliography by citation key
+#[derive(Debug)]
+pub(crate) struct Bibliography {
+    path: PathBuf,
+    entries: HashMap<String, Entry>,
+}
+
+impl Bibliography {
+    fn parse(path: &Path, content: &str) -> Result<Bibliography> {
+        let bibtex = nom_bibtex::Bibtex::parse(content)
+            .map_err(|e| anyhow!("Failed to parse the bibliography {}: {:?}", path.display(), e))?;
+        let entries = bibtex
+            .bibliographies()
+            .iter()
+            .map(|b| {
+                let fields = b
+                    .tags()
+                    .iter()
+                    .map(|(name, value)| {
+                        // The braces are only there to keep the case of words in BibTeX
+                        (name.to_lowercase(), value.replace(['{', '}'], ""))
+                    })
+                    .collect();
+                let entry = Entry { entry_type: b.entry_type().to_lowercase(), fields };
+                (b.citation_key().to_string(), entry)
+            })
+            .collect();
+
+        Ok(Bibliography { path: path.to_path_buf(), entries })
+    }
+
+    /// Loads the bibliography at that path, re-using the parsed one if the file didn't change
+    pub(crate) fn load(path: &Path) -> Result<Arc<Bibliography>> {
+        let modified = fs::metadata(path).and_then(|m| m.modified()).ok();
+        let mut bibliographies = BIBLIOGRAPHIES.lock().unwrap();
+        if let Some((m, bibliography)) = bibliographies.get(path)
+            && modified.is_some()
+            && *m == modified
+        {
+            return Ok(bibliography.clone());
+        }
+
+        let content = fs::read_to_string(path)
+            .map_err(|e| anyhow!("Failed to read the bibliography {}: {}", path.display(), e))?;
+        let bibliography = Arc::new(Bibliography::parse(path, &content)?);
+        bibliographies.insert(path.to_path_buf(), (modified, bibliography.clone()));
+        Ok(bibliography)
+    }
+}
+
+/// Returns the path of the bibliography used for a page, if any: the one set in its front matter
+/// or the one of the config. Paths starting with `@/` are relative to the `content` directory,
+/// the other ones to the root of the site.
+pub fn bibliography_path(config: &Config, bibliography: Option<&str>) -> Option<PathBuf> {
+    let bibliography = bibliography.or(config.markdown.bibliography.as_deref())?;
+    let base_path = config.markdown.base_path.clone().unwrap_or_default();
+    Some(match bibliography.strip_prefix("@/") {
+        Some(path) => base_path.join("content").join(path),
+        None => base_path.join(bibliography),
+    })
+}
+
+/// Replaces the citations in the text events by links to the reference list and returns the
+/// cited entries in the order of their first citation.
+/// Adjacent text events are merged first since pulldown-cmark splits text on brackets.
+pub(crate) fn render_citations<'a>(
+    events: Vec<Event<'a>>,
+    bibliography: &Bibliography,
+    path: Option<&str>,
+) -> Result<(Vec<Event<'a>>, Vec<Citation>)> {
+    let mut citations = Vec::new();
+    let mut rendered = Vec::with_capacity(events.len());
+    let mut text = String::new();
+
+    for event in events {
+        if let Event::Text(t) = event {
+            text.push_str(&t);
+            continue;
+        }
+        if !text.is_empty() {
+            cite(&text, bibliography, path, &mut citations, &mut rendered)?;
+            text.clear();
+        }
+        rendered.push(event);
+    }
+    if !text.is_empty() {
+        cite(&text, bibliography, path, &mut citations, &mut rendered)?;
+    }
+
+    Ok((rendered, citations))
+}
+
+fn cite(
+    text: &str,
+    bibliography: &Bibliography,
+    path: Option<&str>,
+    citations: &mut Vec<Citation>,
+    events: &mut Vec<Event>,
+) -> Result<()> {
+    let mut last = 0;
+    for group in CITATION_GROUP_RE.captures_iter(text) {
+        // Something like `[@everyone here]` that doesn't only contain citations is left as is
+        let Some(items) = group[1]
+            .split(';')
+            .map(|item| CITATION_RE.captures(item.trim()))
+            .collect::<Option<Vec<_>>>()
+        else {
+            continue;
+        };
+
+        let mut html = String::from("<span class=\"citation\">[");
+        for (i, item) in items.iter().enumerate() {
+            let key = &item[1];
+            let Some(entry) = bibliography.entries.get(key) else {
+                let location =
+                    if let Some(p) = path { format!(" in {p:?}") } else { String::new() };
+                bail!(
+                    "Citation `@{}`{} not found in the bibliography {}",
+                    key,
+                    location,
+                    bibliography.path.display()
+                );
+            };
+            let number = match citations.iter().position(|c| c.key == key) {
+                Some(pos) => pos + 1,
+                None => {
+                    citations.push(Citation {
+                        key: key.to_string(),
+                        number: citations.len() + 1,
+                        entry_type: entry.entry_type.clone(),
+                        fields: entry.fields.clone(),
+                    });
+                    citations.len()
+                }
+            };
+
+            if i > 0 {
+                html.push_str("; ");
+            }
+            html.push_str("<a href=\"#ref-");
+            escape_html(&mut html, key)?;
+            write!(html, "\">{number}</a>")?;
+            if let Some(locator) = item.get(2) {
+                html.push_str(", ");
+                escape_html(&mut html, locator.as_str())?;
+            }
+        }
+        html.push_str("]</span>");
+
+        let whole = group.get(0).unwrap();
+        if whole.start() > last {
+            events.push(Event::Text(text[last..whole.start()].to_string().into()));
+        }
+        events.push(Event::InlineHtml(html.into()));
+        last = whole.end();
+    }
+
+    if last < text.len() {
+        events.push(Event::Text(text[last..].to_string().into()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BIBTEX: &str = r#"
+@book{knuth84,
+    author = {Donald E. Knuth},
+    title = {The {TeX}book},
+    year = 1984,
+}
+@article{turing36,
+    author = "Alan Turing",
+    title = "On Computable Numbers",
+    year = 1936,
+}
+"#;
+
+    fn render(text: &str) -> Result<(String, Vec<Citation>)> {
+        let bibliography = Bibliography::parse(Path::new("refs.bib"), BIBTEX).unwrap();
+        let events = vec![Event::Text(text.into())];
+        let (events, citations) = render_citations(events, &bibliography, Some("post.md"))?;
+        let mut html = String::new();
+        pulldown_cmark::html::push_html(&mut html, events.into_iter());
+        Ok((html, citations))
+    }
+
+    #[test]
+    fn can_parse_bibliography() {
+        let bibliography = Bibliography::parse(Path::new("refs.bib"), BIBTEX).unwrap();
+        let entry = &bibliography.entries["knuth84"];
+        assert_eq!(entry.entry_type, "book");
+        assert_eq!(entry.fields["title"], "The TeXbook");
+        assert_eq!(entry.fields["author"], "Donald E. Knuth");
+    }
+
+    #[test]
+    fn numbers_citations_in_order_of_first_citation() {
+        let (html, citations) =
+            render("As shown [@turing36, p. 12] and [@knuth84; @turing36] too").unwrap();
+        assert_eq!(
+            html,
+            "As shown <span class=\"citation\">[<a href=\"#ref-turing36\">1</a>, p. 12]</span> and <span class=\"citation\">[<a href=\"#ref-knuth84\">2</a>; <a href=\"#ref-turing36\">1</a>]</span> too"
+        );
+        let keys: Vec<_> = citations.iter().map(|c| (c.key.as_str(), c.number)).collect();
+        assert_eq!(keys, vec![("turing36", 1), ("knuth84", 2)]);
+    }
+
+    #[test]
+    fn ignores_brackets_not_containing_citations() {
+        let (html, citations) = render("Ping [@everyone here] or [me]").unwrap();
+        assert_eq!(html, "Ping [@everyone here] or [me]");
+        assert!(citations.is_empty());
+    }
+
+    #[test]
+    fn errors_on_unknown_key() {
+        let err = render("See [@nobody]").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Citation `@nobody` in \"post.md\" not found in the bibliography refs.bib"
+        );
+    }
+}
@@ -0,0 +1,249 @@
+//! Fenced containers like `::: warning "Title"` ... `:::`, enabled with `markdown.containers`.
+//! The content is rendered with the rest of the page, between markers replaced afterwards by the
+//! output of the `containers/{name}.html` template given the HTML between them as `body`.
+//! That way the headings, footnotes and citations of containers are part of the page.
+use std::collections::HashMap;
+use std::ops::Range;
+
+use errors::{Context, Result, bail};
+use once_cell::sync::Lazy;
+use pulldown_cmark::{Event, Parser, Tag};
+use regex::Regex;
+use utils::templates::{check_template_fallbacks, render_template};
+
+use crate::context::RenderContext;
+use crate::markdown::Rendered;
+use crate::render_markdown;
+
+/// `::: name` or `::: name "Title"`, possibly indented to be in a list item
+static OPENING_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"^([ \t]*):{3,}[ \t]*([A-Za-z][\w-]*)(?:[ \t]+"([^"]*)")?[ \t]*$"#).unwrap()
+});
+static CLOSING_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^([ \t]*):{3,}[ \t]*$").unwrap());
+
+/// The ranges of the fenced and indented code blocks, where container markers are ignored
+fn code_block_ranges(source: &str) -> Vec<Range<usize>> {
+    Parser::new(source)
+        .into_offset_iter()
+        .filter_map(|(event, range)| match event {
+            Event::Start(Tag::CodeBlock(_)) => Some(range),
+            _ => None,
+        })
+        .collect()
+}
+
+/// An HTML comment is kept as is by the markdown parser and can interrupt a paragraph
+fn start_marker(index: usize) -> String {
+    format!("<!-- @@ZOLA_CONTAINER_START_{index}@@ -->")
+}
+
+fn end_marker(index: usize) -> String {
+    format!("<!-- @@ZOLA_CONTAINER_END_{index}@@ -->")
+}
+
+#[derive(Debug, PartialEq)]
+struct Container {
+    name: String,
+    title: Option<String>,
+}
+
+/// Replaces the opening and closing lines of the containers by markers, returning the containers
+/// in the order they are opened so a container comes before the ones nested in it.
+fn extract_containers(source: &str, path: Option<&str>) -> Result<(String, Vec<Container>)> {
+    let mut out = String::with_capacity(source.len());
+    let mut containers = Vec::new();
+    let code_blocks = code_block_ranges(source);
+    // The indexes of the containers not closed yet
+    let mut open = Vec::new();
+    let mut line_start = 0;
+
+    for line in source.split_inclusive('\n') {
+        let trimmed = line.trim_end_matches(['\n', '\r']);
+        let line_range = line_start..line_start + trimmed.len();
+        line_start += line.len();
+
+        if code_blocks.iter().any(|r| r.start < line_range.end && line_range.start < r.end) {
+            // Part of a code block
+        } else if let Some(caps) = OPENING_RE.captures(trimmed) {
+            open.push(containers.len());
+            out.push_str(&caps[1]);
+            out.push_str(&start_marker(containers.len()));
+            out.push('\n');
+            containers.push(Container {
+                name: caps[2].to_string(),
+                title: caps.get(3).map(|t| t.as_str().to_string()),
+            });
+            continue;
+        } else if let Some(caps) = CLOSING_RE.captures(trimmed)
+            && let Some(index) = open.pop()
+        {
+            out.push_str(&caps[1]);
+            out.push_str(&end_marker(index));
+            out.push('\n');
+            continue;
+        }
+
+        out.push_str(line);
+    }
+
+    if let Some(index) = open.pop() {
+        let location = if let Some(p) = path { format!(" in {p:?}") } else { String::new() };
+        bail!("The `{}` container{} is never closed with `:::`", containers[index].name, location);
+    }
+
+    Ok((out, containers))
+}
+
+impl Container {
+    fn render(&self, body: &str, nth: usize, context: &RenderContext) -> Result<String> {
+        let template = format!("containers/{}.html", self.name);
+        if check_template_fallbacks(&template, &context.tera, &context.config.theme).is_none() {
+            let location = match context.current_page_path {
+                Some(p) => format!(" in {p:?}"),
+                None => String::new(),
+            };
+            bail!(
+                "Found usage of an unknown container `{}`{}: there is no `{}` template",
+                self.name,
+                location,
+                template
+            );
+        }
+
+        let mut tera_context = tera::Context::new();
+        tera_context.insert("name", &self.name);
+        tera_context.insert("title", &self.title);
+        tera_context.insert("body", body.trim());
+        tera_context.insert("nth", &nth);
+        tera_context.extend(context.tera_context.clone());
+        render_template(&template, &context.tera, tera_context, &context.config.theme)
+            .with_context(|| format!("Failed to render {} container", self.name))
+    }
+}
+
+/// The positions of the start of the start marker and of the end of the end marker of the
+/// container in the HTML, if both are in it
+fn find_markers(html: &str, index: usize) -> Option<(usize, usize, usize, usize)> {
+    let (start, end) = (start_marker(index), end_marker(index));
+    let start_idx = html.find(&start)?;
+    let end_idx = html[start_idx..].find(&end)? + start_idx;
+    Some((start_idx, start_idx + start.len(), end_idx, end_idx + end.len()))
+}
+
+/// Renders the content with the containers, replacing the HTML between the markers of each
+/// container by the output of its template, the nested ones first.
+pub(crate) fn render_with_containers(content: &str, context: &RenderContext) -> Result<Rendered> {
+    let (content, containers) = extract_containers(content, context.current_page_path)?;
+    let mut rendered = render_markdown(&content, context)?;
+
+    // `nth` counts the containers of each name in the order they appear in the page
+    let mut invocations: HashMap<&str, usize> = HashMap::new();
+    let nths: Vec<_> = containers
+        .iter()
+        .map(|container| {
+            let nth = invocations.entry(&container.name).or_insert(0);
+            *nth += 1;
+            *nth
+        })
+        .collect();
+
+    for (index, container) in containers.iter().enumerate().rev() {
+        let Some((start, body_start, body_end, end)) = find_markers(&rendered.body, index) else {
+            let location = match context.current_page_path {
+                Some(p) => format!(" in {p:?}"),
+                None => String::new(),
+            };
+            bail!(
+                "The `{}` container{} isn't a block of its own and can't be rendered",
+                container.name,
+                location
+            );
+        };
+        let html = container.render(&rendered.body[body_start..body_end], nths[index], context)?;
+        rendered.body.replace_range(start..end, &html);
+
+        // The summary can stop in the middle of a container, we then only remove its start marker
+        if let Some(summary) = rendered.summary.as_mut() {
+            match find_markers(summary, index) {
+                Some((start, _, _, end)) => summary.replace_range(start..end, &html),
+                None => *summary = summary.replacen(&start_marker(index), "", 1),
+            }
+        }
+    }
+
+    Ok(rendered)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_extract_nested_containers() {
+        let source = r#"Intro
+::: warning "Be careful"
+Some *text*.
+
+::: note
+Nested
+:::
+
+```md
+:::
+```
+:::
+
+- item
+
+  ::: tip
+  In a list
+  :::
+"#;
+        let (out, containers) = extract_containers(source, None).unwrap();
+        assert_eq!(
+            out,
+            format!(
+                "Intro\n{}\nSome *text*.\n\n{}\nNested\n{}\n\n```md\n:::\n```\n{}\n\n- item\n\n  {}\n  In a list\n  {}\n",
+                start_marker(0),
+                start_marker(1),
+                end_marker(1),
+                end_marker(0),
+                start_marker(2),
+                end_marker(2),
+            )
+        );
+        assert_eq!(
+            containers,
+            vec![
+                Container { name: "warning".to_string(), title: Some("Be careful".to_string()) },
+                Container { name: "note".to_string(), title: None },
+                Container { name: "tip".to_string(), title: None },
+            ]
+        );
+    }
+
+    #[test]
+    fn ignores_markers_in_indented_code_blocks() {
+        let source = "Example:\n\n    ::: note\n    In a code block\n    :::\n";
+        let (out, containers) = extract_containers(source, None).unwrap();
+        assert_eq!(out, source);
+        assert!(containers.is_empty());
+    }
+
+    #[test]
+    fn ignores_lonely_closing_markers() {
+        let (out, containers) = extract_containers("Hello\n:::\n", None).unwrap();
+        assert_eq!(out, "Hello\n:::\n");
+        assert!(containers.is_empty());
+    }
+
+    #[test]
+    fn errors_on_unclosed_container() {
+        let err =
+            extract_containers("Hello\n\n::: note\nNever closed\n", Some("post.md")).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "The `note` container in \"post.md\" is never closed with `:::`"
+        );
+    }
+}
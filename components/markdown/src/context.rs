@@ -1,10 +1,14 @@
 use std::borrow::Cow;
 use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 
-use config::Config;
+use config::{Config, ResponsiveImages};
 use tera::{Context, Tera};
 use utils::templates::ShortcodeDefinition;
-use utils::types::InsertAnchor;
+use utils::types::{ContentFormat, InsertAnchor};
+
+use crate::fences::{FenceCache, FenceRenderer};
+use crate::transforms::MarkdownTransform;
 
 /// All the information from the zola site that is needed to render HTML from markdown
 #[derive(Debug)]
@@ -13,11 +17,32 @@ pub struct RenderContext<'a> {
     pub config: &'a Config,
     pub tera_context: Context,
     pub current_page_path: Option<&'a str>,
+    /// The bibliography set in the front matter of the page, if any
+    pub bibliography: Option<&'a str>,
     pub current_page_permalink: &'a str,
     pub permalinks: Cow<'a, HashMap<String, String>>,
+    /// The markdown paths of the pages `[[wiki links]]` can point to, by lowercased filename,
+    /// slug and title
+    pub wiki_links: Cow<'a, HashMap<String, Vec<String>>>,
+    /// The responsive images options, which pages can override in their front matter
+    pub images: &'a ResponsiveImages,
+    /// Resizes the colocated images when responsive images are enabled
+    pub imageproc: Option<Arc<Mutex<imageproc::Processor>>>,
+    /// Where the output of the fence renderers is kept between builds
+    pub fence_cache: Option<Arc<FenceCache>>,
     pub insert_anchor: InsertAnchor,
+    /// The levels of the headings in the table of contents, which are the ones numbered
+    pub toc_min_level: u32,
+    pub toc_max_level: u32,
+    pub number_headings: bool,
+    /// The markup language of the content, Markdown unless the file is Djot or Org
+    pub format: ContentFormat,
     pub lang: &'a str,
     pub shortcode_definitions: Cow<'a, HashMap<String, ShortcodeDefinition>>,
+    /// Transforms registered in addition to the builtin ones enabled in the config
+    pub transforms: Vec<Arc<dyn MarkdownTransform>>,
+    /// Fence renderers registered in addition to the builtin ones enabled in the config
+    pub fence_renderers: Vec<Arc<dyn FenceRenderer>>,
 }
 
 impl<'a> RenderContext<'a> {
@@ -37,12 +62,23 @@ impl<'a> RenderContext<'a> {
             tera: Cow::Borrowed(tera),
             tera_context,
             current_page_path: None,
+            bibliography: None,
             current_page_permalink,
             permalinks: Cow::Borrowed(permalinks),
+            wiki_links: Cow::Owned(HashMap::new()),
+            images: &config.markdown.images,
+            imageproc: None,
+            fence_cache: None,
             insert_anchor,
+            toc_min_level: config.markdown.toc_min_level,
+            toc_max_level: config.markdown.toc_max_level,
+            number_headings: config.markdown.number_headings,
+            format: ContentFormat::Markdown,
             config,
             lang,
             shortcode_definitions: Cow::Owned(HashMap::new()),
+            transforms: Vec::new(),
+            fence_renderers: Vec::new(),
         }
     }
 
@@ -57,6 +93,63 @@ impl<'a> RenderContext<'a> {
         self.current_page_path = Some(path);
     }
 
+    /// Same as above
+    pub fn set_bibliography(&mut self, bibliography: &'a str) {
+        self.bibliography = Some(bibliography);
+    }
+
+    /// Same as above
+    pub fn set_wiki_links(&mut self, wiki_links: &'a HashMap<String, Vec<String>>) {
+        self.wiki_links = Cow::Borrowed(wiki_links);
+    }
+
+    /// Same as above
+    pub fn set_images(&mut self, images: &'a ResponsiveImages) {
+        self.images = images;
+    }
+
+    /// Overrides the headings options of the config with the ones of the front matter
+    pub fn set_heading_options(
+        &mut self,
+        toc_min_level: Option<u32>,
+        toc_max_level: Option<u32>,
+        number_headings: Option<bool>,
+    ) {
+        self.toc_min_level = toc_min_level.unwrap_or(self.toc_min_level);
+        self.toc_max_level = toc_max_level.unwrap_or(self.toc_max_level);
+        self.number_headings = number_headings.unwrap_or(self.number_headings);
+    }
+
+    /// Same as above
+    pub fn set_format(&mut self, format: ContentFormat) {
+        self.format = format;
+    }
+
+    /// Same as above
+    pub fn set_imageproc(&mut self, imageproc: Arc<Mutex<imageproc::Processor>>) {
+        self.imageproc = Some(imageproc);
+    }
+
+    /// Same as above
+    pub fn set_fence_cache(&mut self, fence_cache: Arc<FenceCache>) {
+        self.fence_cache = Some(fence_cache);
+    }
+
+    /// Registers a transform that will be run on the markdown events after the builtin ones
+    pub fn add_transform(&mut self, transform: impl MarkdownTransform + 'static) {
+        self.transforms.push(Arc::new(transform));
+    }
+
+    /// Same as above, for transforms shared between renders like the ones registered on the site
+    pub fn add_transforms(&mut self, transforms: &[Arc<dyn MarkdownTransform>]) {
+        self.transforms.extend(transforms.iter().cloned());
+    }
+
+    /// Registers a fence renderer that will be tried after the builtin ones
+    pub fn add_fence_renderer(&mut self, renderer: impl FenceRenderer + 'static) {
+        self.fence_renderers.push(Arc::new(renderer));
+    }
+
     // In use in the markdown filter
     // NOTE: This RenderContext is not i18n-aware, see MarkdownFilter::filter for details
     // If this function is ever used outside of MarkdownFilter, take this into consideration
@@ -65,12 +158,23 @@ impl<'a> RenderContext<'a> {
             tera: Cow::Owned(Tera::default()),
             tera_context: Context::new(),
             current_page_path: None,
+            bibliography: None,
             current_page_permalink: "",
             permalinks: Cow::Owned(HashMap::new()),
+            wiki_links: Cow::Owned(HashMap::new()),
+            images: &config.markdown.images,
+            imageproc: None,
+            fence_cache: None,
             insert_anchor: InsertAnchor::None,
+            toc_min_level: config.markdown.toc_min_level,
+            toc_max_level: config.markdown.toc_max_level,
+            number_headings: config.markdown.number_headings,
+            format: ContentFormat::Markdown,
             config,
             lang: &config.default_language,
             shortcode_definitions: Cow::Owned(HashMap::new()),
+            transforms: Vec::new(),
+            fence_renderers: Vec::new(),
         }
     }
 }
@@ -0,0 +1,221 @@
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::fmt::Debug;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use errors::{Result, anyhow, bail};
+use layout::backends::svg::SVGWriter;
+use layout::gv::{DotParser, GraphBuilder};
+use utils::fs::{create_directory, create_file};
+
+use crate::context::RenderContext;
+
+/// Turns the content of a code fence into HTML instead of highlighting it, eg to render
+/// diagrams as inline SVG at build time.
+pub trait FenceRenderer: Debug + Send + Sync {
+    /// The name of the renderer, used to enable builtin renderers from the
+    /// `markdown.fence_renderers` config option
+    fn name(&self) -> &str;
+
+    /// Whether this renderer handles code fences with that language
+    fn handles(&self, lang: &str) -> bool;
+
+    /// Renders the content of the fence to HTML
+    fn render(&self, source: &str) -> Result<String>;
+}
+
+/// Renders Graphviz DOT graphs (`dot` or `graphviz` fences) to SVG
+#[derive(Debug, Default)]
+pub struct DotRenderer;
+
+impl FenceRenderer for DotRenderer {
+    fn name(&self) -> &str {
+        "dot"
+    }
+
+    fn handles(&self, lang: &str) -> bool {
+        lang == "dot" || lang == "graphviz"
+    }
+
+    fn render(&self, source: &str) -> Result<String> {
+        let mut parser = DotParser::new(source);
+        let graph = parser.process().map_err(|e| anyhow!("Invalid DOT graph: {}", e))?;
+        let mut builder = GraphBuilder::new();
+        builder.visit_graph(&graph);
+        let mut visual_graph = builder.get();
+        if visual_graph.num_nodes() == 0 {
+            bail!("Invalid DOT graph: the graph has no nodes");
+        }
+        let mut writer = SVGWriter::new();
+        visual_graph.do_it(false, false, false, &mut writer);
+        let svg = writer.finalize();
+        // We are inlining it in HTML so we don't want the XML declaration
+        match svg.find("<svg") {
+            Some(idx) => Ok(svg[idx..].to_string()),
+            None => Ok(svg),
+        }
+    }
+}
+
+/// Returns the builtin fence renderer with that name
+fn builtin_fence_renderer(name: &str) -> Option<Arc<dyn FenceRenderer>> {
+    match name {
+        "dot" => Some(Arc::new(DotRenderer)),
+        _ => None,
+    }
+}
+
+/// The fence renderers to use for that context: the builtin ones enabled in the config first,
+/// then the ones registered on the context.
+pub(crate) fn get_fence_renderers(context: &RenderContext) -> Result<Vec<Arc<dyn FenceRenderer>>> {
+    let mut renderers = Vec::new();
+    for name in &context.config.markdown.fence_renderers {
+        match builtin_fence_renderer(name) {
+            Some(r) => renderers.push(r),
+            None => bail!("Unknown fence renderer `{}` in `markdown.fence_renderers`", name),
+        }
+    }
+    renderers.extend(context.fence_renderers.iter().cloned());
+    Ok(renderers)
+}
+
+/// Renders the fence, through the cache if there is one
+pub(crate) fn render_fence(
+    renderer: &dyn FenceRenderer,
+    source: &str,
+    cache: Option<&FenceCache>,
+) -> Result<String> {
+    match cache {
+        Some(cache) => cache.render(renderer, source),
+        None => renderer.render(source),
+    }
+}
+
+/// Keeps the rendered fences between builds, like the processed images: they are stored in
+/// files named after the hash of their inputs, the new ones are only written once the site
+/// is built and the ones the build didn't use are pruned at that point.
+#[derive(Debug)]
+pub struct FenceCache {
+    dir: PathBuf,
+    /// The filenames used by this build, with the HTML of the ones that still need to be written
+    used: Mutex<HashMap<String, Option<String>>>,
+}
+
+impl FenceCache {
+    pub fn new(dir: PathBuf) -> FenceCache {
+        FenceCache { dir, used: Mutex::new(HashMap::new()) }
+    }
+
+    /// Renders the fence unless a previous build already did
+    pub fn render(&self, renderer: &dyn FenceRenderer, source: &str) -> Result<String> {
+        let mut hasher = DefaultHasher::new();
+        env!("CARGO_PKG_VERSION").hash(&mut hasher);
+        renderer.name().hash(&mut hasher);
+        source.hash(&mut hasher);
+        let filename = format!("{}-{:016x}.html", renderer.name(), hasher.finish());
+
+        if let Some(Some(html)) = self.used.lock().unwrap().get(&filename) {
+            return Ok(html.clone());
+        }
+        if let Ok(html) = fs::read_to_string(self.dir.join(&filename)) {
+            self.used.lock().unwrap().entry(filename).or_insert(None);
+            return Ok(html);
+        }
+
+        let html = renderer.render(source)?;
+        self.used.lock().unwrap().insert(filename, Some(html.clone()));
+        Ok(html)
+    }
+
+    /// Writes the fences rendered by this build
+    pub fn do_process(&self) -> Result<()> {
+        let used = self.used.lock().unwrap();
+        if used.values().any(Option::is_some) {
+            create_directory(&self.dir)?;
+        }
+        for (filename, html) in used.iter() {
+            if let Some(html) = html {
+                create_file(&self.dir.join(filename), html)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Removes the rendered fences this build didn't use.
+    /// Needs to be called after rendering all the content
+    pub fn prune(&self) -> Result<()> {
+        if !self.dir.exists() {
+            return Ok(());
+        }
+
+        let used = self.used.lock().unwrap();
+        for entry in fs::read_dir(&self.dir)? {
+            let entry_path = entry?.path();
+            if entry_path.is_file()
+                && !used.contains_key(entry_path.file_name().unwrap().to_string_lossy().as_ref())
+            {
+                fs::remove_file(&entry_path)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_render_dot_to_svg() {
+        let svg = DotRenderer.render("digraph { a -> b; }").unwrap();
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.ends_with("</svg>"));
+    }
+
+    #[test]
+    fn errors_on_invalid_dot() {
+        assert!(DotRenderer.render("digraph { a -> ; }").is_err());
+        assert!(DotRenderer.render("digraph { }").is_err());
+    }
+
+    #[test]
+    fn caches_rendered_fences() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let dir = tmp_dir.path().join("fences");
+        let cache = FenceCache::new(dir.clone());
+        let svg = cache.render(&DotRenderer, "digraph { a -> b; }").unwrap();
+        // Nothing is written before the site is built
+        assert!(!dir.exists());
+        cache.do_process().unwrap();
+        let cached: Vec<_> = fs::read_dir(&dir).unwrap().flatten().collect();
+        assert_eq!(cached.len(), 1);
+        assert!(cached[0].file_name().to_string_lossy().starts_with("dot-"));
+
+        // The next builds read from the cache file if it exists
+        fs::write(cached[0].path(), "<svg>cached</svg>").unwrap();
+        let cached_svg = FenceCache::new(dir).render(&DotRenderer, "digraph { a -> b; }").unwrap();
+        assert_ne!(svg, cached_svg);
+        assert_eq!(cached_svg, "<svg>cached</svg>");
+    }
+
+    #[test]
+    fn prunes_unused_rendered_fences() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let cache = FenceCache::new(tmp_dir.path().to_path_buf());
+        cache.render(&DotRenderer, "digraph { a -> b; }").unwrap();
+        cache.render(&DotRenderer, "digraph { a -> c; }").unwrap();
+        cache.prune().unwrap();
+        cache.do_process().unwrap();
+        assert_eq!(fs::read_dir(tmp_dir.path()).unwrap().count(), 2);
+
+        // Only the second graph is still used after an edit
+        let cache = FenceCache::new(tmp_dir.path().to_path_buf());
+        cache.render(&DotRenderer, "digraph { a -> c; }").unwrap();
+        cache.prune().unwrap();
+        cache.do_process().unwrap();
+        assert_eq!(fs::read_dir(tmp_dir.path()).unwrap().count(), 1);
+    }
+}
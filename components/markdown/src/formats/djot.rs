@@ -0,0 +1,221 @@
+//! Djot, rendered with jotdown. The headings without an explicit id get one made from their
+//! title like for Markdown, and the links jotdown made to them, like implicit references
+//! `[My heading][]`, are updated.
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use errors::Result;
+use jotdown::{AttributeKind, Attributes, Container, Event, LinkType};
+use pulldown_cmark_escape::escape_html;
+use utils::net::is_external_link;
+use utils::slugs::slugify_anchors;
+
+use super::{Collector, anchor_links, is_more_divider, link_start_tag};
+use crate::context::RenderContext;
+use crate::markdown::{CONTINUE_READING, find_anchor};
+
+/// Pushes HTML to output as is
+fn push_raw_html<'s>(events: &mut Vec<Event<'s>>, html: String, block: bool) {
+    let container = || {
+        if block {
+            Container::RawBlock { format: "html".into() }
+        } else {
+            Container::RawInline { format: "html".into() }
+        }
+    };
+    events.push(Event::Start(container(), Attributes::new()));
+    events.push(Event::Str(html.into()));
+    events.push(Event::End(container()));
+}
+
+/// The text of the events of a paragraph, if they are only text
+fn paragraph_text(events: &[Event]) -> Option<String> {
+    let mut text = String::new();
+    for event in events {
+        match event {
+            Event::Str(s) => text.push_str(s),
+            // Undo the smart punctuation of `<!-- more -->`
+            Event::EnDash => text.push_str("--"),
+            Event::EmDash => text.push_str("---"),
+            Event::Softbreak => text.push(' '),
+            _ => return None,
+        }
+    }
+    Some(text)
+}
+
+/// The ids to give to the headings instead of the ones jotdown made, by jotdown id.
+/// Explicit ids like `{#faq}` are kept.
+fn heading_ids(parsed: &[Event], context: &RenderContext) -> HashMap<String, String> {
+    let mut ids = HashMap::new();
+    let mut inserted_anchors = Vec::new();
+    // An explicit id ends up on the section jotdown wraps the heading in, if there is one
+    let mut section_attrs = None;
+    for (i, event) in parsed.iter().enumerate() {
+        match event {
+            Event::Start(Container::Section { .. }, attrs) => section_attrs = Some(attrs),
+            Event::Start(Container::Heading { id, .. }, attrs) => {
+                let explicit = attrs.contains_key("id")
+                    || section_attrs.take().is_some_and(|a| a.contains_key("id"));
+                let new_id = if explicit {
+                    id.to_string()
+                } else {
+                    let title: String = parsed[i + 1..]
+                        .iter()
+                        .take_while(|e| !matches!(e, Event::End(Container::Heading { .. })))
+                        .filter_map(|e| match e {
+                            Event::Str(s) => Some(s.as_ref()),
+                            _ => None,
+                        })
+                        .collect();
+                    find_anchor(
+                        &inserted_anchors,
+                        slugify_anchors(&title, context.config.slugify.anchors),
+                        0,
+                    )
+                };
+                inserted_anchors.push(new_id.clone());
+                ids.insert(id.to_string(), new_id);
+            }
+            _ => {}
+        }
+    }
+    ids
+}
+
+/// The opening tag of an external link with the `external_links_*` options and the attributes
+/// of the link
+fn external_link_tag(href: &str, attrs: &Attributes, context: &RenderContext) -> String {
+    let mut tag = link_start_tag(href, href, context);
+    tag.pop();
+    for (key, value) in attrs.unique_pairs() {
+        write!(tag, " {key}=\"").expect("Could not write to buffer");
+        escape_html(&mut tag, &value.to_string()).expect("Could not write to buffer");
+        tag.push('"');
+    }
+    tag.push('>');
+    tag
+}
+
+pub(super) fn render(
+    content: &str,
+    context: &RenderContext,
+    collector: &mut Collector,
+) -> Result<String> {
+    let parsed: Vec<_> = jotdown::Parser::new(content).collect();
+    let heading_ids = heading_ids(&parsed, context);
+    let mut events = Vec::with_capacity(parsed.len());
+    // Containers opened, except the document and the sections, to find top-level paragraphs
+    let mut depth = 0;
+    let mut has_summary = false;
+    // (level, id, text, end of the anchor link) of the heading being rendered
+    let mut heading: Option<(u16, String, String, String)> = None;
+    // Whether the link being rendered was opened with raw HTML for the external links options
+    let mut in_external_link = false;
+
+    let mut i = 0;
+    while i < parsed.len() {
+        let event = parsed[i].clone();
+        i += 1;
+        match event {
+            // The sections jotdown wraps headings in carry their id, we put it on the heading
+            Event::Start(Container::Section { .. }, _) | Event::End(Container::Section { .. }) => {}
+            Event::Start(Container::Document, _) | Event::End(Container::Document) => {
+                events.push(event);
+            }
+            Event::Start(Container::Paragraph, _) if depth == 0 && !has_summary => {
+                let end = parsed[i..]
+                    .iter()
+                    .position(|e| matches!(e, Event::End(Container::Paragraph)))
+                    .map(|p| i + p);
+                if let Some(end) = end
+                    && paragraph_text(&parsed[i..end]).is_some_and(|t| is_more_divider(&t))
+                {
+                    has_summary = true;
+                    push_raw_html(&mut events, CONTINUE_READING.to_string(), true);
+                    i = end + 1;
+                    continue;
+                }
+                depth += 1;
+                events.push(event);
+            }
+            Event::Start(Container::Heading { level, id, .. }, attrs) => {
+                let id: Cow<str> = match heading_ids.get(id.as_ref()) {
+                    Some(new_id) => new_id.clone().into(),
+                    None => id,
+                };
+                let (start, end) = anchor_links(&id, u32::from(level), context)?;
+                heading = Some((level, id.to_string(), String::new(), end));
+                depth += 1;
+                events.push(Event::Start(
+                    Container::Heading { level, has_section: false, id },
+                    attrs,
+                ));
+                if !start.is_empty() {
+                    push_raw_html(&mut events, start, false);
+                }
+            }
+            Event::End(Container::Heading { level, id, .. }) => {
+                let mut id = id;
+                if let Some((level, heading_id, title, end)) = heading.take() {
+                    if !end.is_empty() {
+                        push_raw_html(&mut events, end, false);
+                    }
+                    collector.add_heading(u32::from(level), &heading_id, title, context);
+                    id = heading_id.into();
+                }
+                depth -= 1;
+                events.push(Event::End(Container::Heading { level, has_section: false, id }));
+            }
+            Event::Start(Container::Link(link, link_type), attrs) => {
+                let link = match link.strip_prefix('#').and_then(|id| heading_ids.get(id)) {
+                    Some(id) => Cow::Owned(format!("#{id}")),
+                    None => link,
+                };
+                let is_external = is_external_link(&link);
+                let link = collector.fix_link(&link, link_type == LinkType::Email, context)?;
+                if is_external && context.config.markdown.has_external_link_tweaks() {
+                    in_external_link = true;
+                    push_raw_html(&mut events, external_link_tag(&link, &attrs, context), false);
+                    continue;
+                }
+                depth += 1;
+                events.push(Event::Start(Container::Link(link.into(), link_type), attrs));
+            }
+            Event::End(Container::Link(..)) if in_external_link => {
+                in_external_link = false;
+                push_raw_html(&mut events, "</a>".to_string(), false);
+            }
+            Event::Start(Container::Image(src, link_type), mut attrs)
+                if context.config.markdown.lazy_async_image =>
+            {
+                // They can be overridden by the attributes of the image
+                for (key, value) in [("loading", "lazy"), ("decoding", "async")] {
+                    if !attrs.contains_key(key) {
+                        attrs.push((AttributeKind::Pair { key: key.into() }, value.into()));
+                    }
+                }
+                depth += 1;
+                events.push(Event::Start(Container::Image(src, link_type), attrs));
+            }
+            Event::Start(..) => {
+                depth += 1;
+                events.push(event);
+            }
+            Event::End(..) => {
+                depth -= 1;
+                events.push(event);
+            }
+            Event::Str(ref s) => {
+                if let Some((_, _, title, _)) = heading.as_mut() {
+                    title.push_str(s);
+                }
+                events.push(event);
+            }
+            _ => events.push(event),
+        }
+    }
+
+    Ok(jotdown::html::render_to_string(events.into_iter()))
+}
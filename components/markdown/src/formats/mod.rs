@@ -0,0 +1,115 @@
+//! Rendering of the content written in another markup language than Markdown: Djot and Org.
+//! They go through their own parser but end up in the same `Rendered` as Markdown, with the
+//! headings of the table of contents, the summary and the links to check.
+//! Jupyter notebooks are turned into Markdown instead.
+use errors::Result;
+use pulldown_cmark::LinkType;
+use pulldown_cmark_escape::escape_href;
+use utils::net::is_external_link;
+use utils::table_of_contents::{Heading, make_table_of_contents};
+use utils::types::{ContentFormat, InsertAnchor};
+
+use crate::context::RenderContext;
+use crate::markdown::{
+    A_HTML_TAG, CONTINUE_READING, MORE_DIVIDER_RE, Rendered, fix_link, render_anchor_link,
+};
+
+mod djot;
+pub(crate) mod notebook;
+mod org;
+
+/// What the renderers of each format collect along the way
+#[derive(Debug, Default)]
+pub(crate) struct Collector {
+    headings: Vec<Heading>,
+    internal_links: Vec<(String, Option<String>)>,
+    external_links: Vec<String>,
+}
+
+impl Collector {
+    /// Resolves `@/` links and records the internal and external links, like for Markdown
+    fn fix_link(&mut self, link: &str, is_email: bool, context: &RenderContext) -> Result<String> {
+        let link_type = if is_email { LinkType::Email } else { LinkType::Inline };
+        fix_link(link_type, link, context, &mut self.internal_links, &mut self.external_links)
+    }
+
+    /// Adds the heading to the table of contents if its level is in it
+    fn add_heading(&mut self, level: u32, id: &str, title: String, context: &RenderContext) {
+        if (context.toc_min_level..=context.toc_max_level).contains(&level) {
+            self.headings.push(Heading {
+                level,
+                id: id.to_owned(),
+                permalink: format!("{}#{}", context.current_page_permalink, id),
+                title,
+                number: None,
+                children: Vec::new(),
+            });
+        }
+    }
+}
+
+/// The HTML to insert at the start and at the end of the content of a heading for the
+/// `insert_anchor_links` option
+fn anchor_links(id: &str, level: u32, context: &RenderContext) -> Result<(String, String)> {
+    if context.insert_anchor == InsertAnchor::None {
+        return Ok((String::new(), String::new()));
+    }
+
+    let anchor_link = render_anchor_link(id, level, context)?;
+    Ok(match context.insert_anchor {
+        InsertAnchor::Left => (anchor_link, String::new()),
+        InsertAnchor::Right => (String::new(), anchor_link),
+        InsertAnchor::Heading => match A_HTML_TAG.captures(&anchor_link) {
+            Some(captures) => (captures[1].to_string(), "</a>".to_string()),
+            None => (String::new(), String::new()),
+        },
+        InsertAnchor::None => unreachable!(),
+    })
+}
+
+/// The opening tag of a link, with the `external_links_*` options applied if the link, before
+/// being resolved, was an external one like for Markdown
+fn link_start_tag(href: &str, link: &str, context: &RenderContext) -> String {
+    let mut escaped = String::new();
+    escape_href(&mut escaped, href).expect("Could not write to buffer");
+    let markdown = &context.config.markdown;
+    if is_external_link(link) && markdown.has_external_link_tweaks() {
+        markdown.construct_external_link_tag(&escaped, "")
+    } else {
+        format!("<a href=\"{escaped}\">")
+    }
+}
+
+/// Whether a paragraph is only the `<!-- more -->` summary divider
+fn is_more_divider(text: &str) -> bool {
+    MORE_DIVIDER_RE.find(text.trim()).is_some_and(|m| m.len() == text.trim().len())
+}
+
+/// Renders Djot, Org or notebook content. The summary is everything before a top-level paragraph
+/// containing only `<!-- more -->`.
+pub(crate) fn render_format(content: &str, context: &RenderContext) -> Result<Rendered> {
+    if context.format == ContentFormat::Notebook {
+        return notebook::render(content, context);
+    }
+
+    let mut collector = Collector::default();
+    let body = match context.format {
+        ContentFormat::Djot => djot::render(content, context, &mut collector)?,
+        ContentFormat::Org => org::render(content, context, &mut collector)?,
+        ContentFormat::Markdown | ContentFormat::Notebook => {
+            unreachable!("Markdown is rendered by markdown_to_html")
+        }
+    };
+    let summary = body.find(CONTINUE_READING).map(|idx| body[..idx].trim_end().to_string());
+
+    Ok(Rendered {
+        body,
+        summary,
+        toc: make_table_of_contents(collector.headings),
+        internal_links: collector.internal_links,
+        external_links: collector.external_links,
+        citations: Vec::new(),
+        included_files: Vec::new(),
+        responsive_images: Vec::new(),
+    })
+}
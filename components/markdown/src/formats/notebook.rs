@@ -0,0 +1,211 @@
+//! Jupyter notebooks. They are turned into a Markdown document where the code cells are fenced
+//! code blocks, so they are highlighted like any other, and their stored outputs are placeholders
+//! replaced by their HTML once the Markdown is rendered, like HTML shortcodes.
+use errors::{Context, Result};
+use once_cell::sync::Lazy;
+use pulldown_cmark_escape::escape_html;
+use regex::Regex;
+use serde::Deserialize;
+use tera::{Map, Value};
+
+use crate::context::RenderContext;
+use crate::markdown::{Rendered, markdown_to_html};
+
+const OUTPUT_PLACEHOLDER: &str = "@@ZOLA_NB_OUTPUT@@";
+/// The formats of the outputs we can render, the first one found in an output is used
+const OUTPUT_MIME_TYPES: [&str; 6] =
+    ["text/html", "image/svg+xml", "image/png", "image/jpeg", "image/gif", "text/plain"];
+
+static ANSI_ESCAPE_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\x1b\[[0-9;]*[A-Za-z]").unwrap());
+
+/// A string nbformat stores either as is or as a list of lines
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum MultilineString {
+    One(String),
+    Lines(Vec<String>),
+}
+
+impl MultilineString {
+    fn text(&self) -> String {
+        match self {
+            MultilineString::One(s) => s.clone(),
+            MultilineString::Lines(lines) => lines.concat(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "cell_type", rename_all = "lowercase")]
+enum Cell {
+    Markdown {
+        source: MultilineString,
+    },
+    Code {
+        source: MultilineString,
+        #[serde(default)]
+        outputs: Vec<Output>,
+    },
+    /// Raw cells are only meant for nbconvert
+    #[serde(other)]
+    Raw,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "output_type", rename_all = "snake_case")]
+enum Output {
+    Stream { name: String, text: MultilineString },
+    DisplayData { data: Map<String, Value> },
+    ExecuteResult { data: Map<String, Value> },
+    Error { traceback: Vec<String> },
+}
+
+impl Output {
+    fn to_html(&self) -> String {
+        match self {
+            Output::Stream { name, text } => {
+                format!(
+                    "<pre class=\"notebook-output notebook-{name}\">{}</pre>",
+                    escape(&text.text())
+                )
+            }
+            Output::DisplayData { data } | Output::ExecuteResult { data } => {
+                let Some((mime_type, value)) =
+                    OUTPUT_MIME_TYPES.iter().find_map(|m| data.get(*m).map(|v| (*m, v)))
+                else {
+                    return String::new();
+                };
+                let value = match value {
+                    Value::Array(lines) => lines.iter().filter_map(Value::as_str).collect(),
+                    Value::String(s) => s.clone(),
+                    _ => return String::new(),
+                };
+                let inner = match mime_type {
+                    "text/html" | "image/svg+xml" => value,
+                    "text/plain" => format!("<pre>{}</pre>", escape(&value)),
+                    _ => {
+                        let base64: String = value.split_whitespace().collect();
+                        format!("<img src=\"data:{mime_type};base64,{base64}\" alt=\"\">")
+                    }
+                };
+                format!("<div class=\"notebook-output\">{inner}</div>")
+            }
+            Output::Error { traceback } => {
+                let traceback = ANSI_ESCAPE_RE.replace_all(&traceback.join("\n"), "").into_owned();
+                format!(
+                    "<pre class=\"notebook-output notebook-error\">{}</pre>",
+                    escape(&traceback)
+                )
+            }
+        }
+    }
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    escape_html(&mut escaped, text).unwrap();
+    escaped
+}
+
+/// The parts of a `.ipynb` file Zola uses
+#[derive(Debug, Deserialize)]
+pub struct Notebook {
+    cells: Vec<Cell>,
+    #[serde(default)]
+    metadata: Map<String, Value>,
+}
+
+impl Notebook {
+    pub fn parse(content: &str) -> Result<Notebook> {
+        serde_json::from_str(content).context("Invalid Jupyter notebook")
+    }
+
+    /// The front matter of the page, from the `zola` object of the notebook metadata
+    pub fn front_matter(&self) -> Option<&Value> {
+        self.metadata.get("zola")
+    }
+
+    /// The text of the Markdown cells, which is what gets counted for the reading time
+    pub fn markdown(&self) -> String {
+        let sources: Vec<_> = self
+            .cells
+            .iter()
+            .filter_map(|cell| match cell {
+                Cell::Markdown { source } => Some(source.text()),
+                _ => None,
+            })
+            .collect();
+        sources.join("\n\n")
+    }
+
+    /// The language of the code cells, used to highlight them
+    fn language(&self) -> &str {
+        let language_info = self.metadata.get("language_info").and_then(|i| i.get("name"));
+        let kernel_language = self.metadata.get("kernelspec").and_then(|k| k.get("language"));
+        language_info.or(kernel_language).and_then(Value::as_str).unwrap_or("python")
+    }
+
+    /// The Markdown document of the notebook and the HTML of the outputs of its code cells,
+    /// in the order of their placeholders
+    fn to_markdown(&self) -> (String, Vec<String>) {
+        let language = self.language();
+        let mut markdown = String::new();
+        let mut outputs = Vec::new();
+
+        for cell in &self.cells {
+            match cell {
+                Cell::Markdown { source } => {
+                    markdown.push_str(source.text().trim_end());
+                    markdown.push_str("\n\n");
+                }
+                Cell::Code { source, outputs: cell_outputs } => {
+                    let source = source.text();
+                    if !source.trim().is_empty() {
+                        // The fence needs to be longer than any run of backticks in the code
+                        let longest_run = source.split(|c| c != '`').map(str::len).max();
+                        let fence = "`".repeat(longest_run.unwrap_or(0).max(2) + 1);
+                        markdown.push_str(&format!(
+                            "{fence}{language}\n{}\n{fence}\n\n",
+                            source.trim_end()
+                        ));
+                    }
+                    for output in cell_outputs {
+                        let html = output.to_html();
+                        if !html.is_empty() {
+                            outputs.push(html);
+                            markdown.push_str(OUTPUT_PLACEHOLDER);
+                            markdown.push_str("\n\n");
+                        }
+                    }
+                }
+                Cell::Raw => (),
+            }
+        }
+
+        (markdown, outputs)
+    }
+}
+
+/// Replaces the placeholders of the outputs by their HTML
+fn insert_outputs(html: &str, outputs: &[String]) -> String {
+    let placeholder = format!("<p>{OUTPUT_PLACEHOLDER}</p>");
+    let mut res = String::with_capacity(html.len());
+    let mut outputs = outputs.iter();
+    let mut rest = html;
+    while let Some(idx) = rest.find(&placeholder) {
+        res.push_str(&rest[..idx]);
+        res.push_str(outputs.next().map_or("", String::as_str));
+        rest = &rest[idx + placeholder.len()..];
+    }
+    res.push_str(rest);
+    res
+}
+
+pub(super) fn render(content: &str, context: &RenderContext) -> Result<Rendered> {
+    let notebook = Notebook::parse(content)?;
+    let (markdown, outputs) = notebook.to_markdown();
+    let mut rendered = markdown_to_html(&markdown, context, Vec::new())?;
+    rendered.body = insert_outputs(&rendered.body, &outputs);
+    rendered.summary = rendered.summary.map(|summary| insert_outputs(&summary, &outputs));
+    Ok(rendered)
+}
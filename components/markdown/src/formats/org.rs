@@ -0,0 +1,150 @@
+//! Org-mode, rendered with orgize. The headings get the id of their `CUSTOM_ID` property or
+//! one made from their title like for Markdown. Links without a description to an image are
+//! rendered as images, like the HTML export of Org-mode does.
+use std::io::Write;
+
+use errors::Result;
+use orgize::export::{DefaultHtmlHandler, HtmlEscape, HtmlHandler};
+use orgize::{Element, Event, Org};
+use utils::slugs::slugify_anchors;
+
+use super::{Collector, anchor_links, is_more_divider, link_start_tag};
+use crate::context::RenderContext;
+use crate::markdown::{CONTINUE_READING, find_anchor};
+
+const IMAGE_EXTENSIONS: &[&str] = &["avif", "gif", "jpeg", "jpg", "png", "svg", "webp"];
+
+fn is_image(path: &str) -> bool {
+    path.rsplit_once('.')
+        .is_some_and(|(_, ext)| IMAGE_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+}
+
+/// The text of the events of a paragraph, if they are only text
+fn paragraph_text(events: &[Event]) -> Option<String> {
+    let mut text = String::new();
+    for event in events {
+        match event {
+            Event::Start(Element::Text { value }) => text.push_str(value),
+            Event::End(Element::Text { .. }) => (),
+            _ => return None,
+        }
+    }
+    Some(text)
+}
+
+pub(super) fn render(
+    content: &str,
+    context: &RenderContext,
+    collector: &mut Collector,
+) -> Result<String> {
+    let org = Org::parse(content);
+    let events: Vec<_> = org.iter().collect();
+    let mut handler = DefaultHtmlHandler;
+    let mut html = Vec::with_capacity(content.len());
+    // Containers opened, except the document, headlines and sections, to find top-level paragraphs
+    let mut depth = 0;
+    let mut has_summary = false;
+    let mut inserted_anchors = Vec::new();
+    // The inner HTML and text of the title being rendered, which we need before writing its tag
+    let mut title: Option<Vec<u8>> = None;
+    let mut title_text = String::new();
+    let mut in_title = false;
+
+    let mut i = 0;
+    while i < events.len() {
+        let event = &events[i];
+        i += 1;
+        let out = match title.as_mut() {
+            Some(inner) => inner,
+            None => &mut html,
+        };
+        match event {
+            Event::Start(
+                Element::Document { .. } | Element::Headline { .. } | Element::Section,
+            )
+            | Event::End(Element::Document { .. } | Element::Headline { .. } | Element::Section) => {
+            }
+            Event::Start(element @ Element::Paragraph { .. }) if depth == 0 && !has_summary => {
+                let end = events[i..]
+                    .iter()
+                    .position(|e| matches!(e, Event::End(Element::Paragraph { .. })))
+                    .map(|p| i + p);
+                if let Some(end) = end
+                    && paragraph_text(&events[i..end]).is_some_and(|t| is_more_divider(&t))
+                {
+                    has_summary = true;
+                    write!(out, "{}", CONTINUE_READING)?;
+                    i = end + 1;
+                    continue;
+                }
+                depth += 1;
+                handler.start(out, element)?;
+            }
+            Event::Start(Element::Title(_)) => {
+                depth += 1;
+                title = Some(Vec::new());
+                in_title = true;
+            }
+            Event::End(Element::Title(t)) => {
+                depth -= 1;
+                let inner = title.take().unwrap_or_default();
+                in_title = false;
+                let text = std::mem::take(&mut title_text);
+                let custom_id = t.properties.pairs.iter().find(|(k, _)| k == "CUSTOM_ID");
+                let id = match custom_id {
+                    Some((_, id)) => id.to_string(),
+                    None => find_anchor(
+                        &inserted_anchors,
+                        slugify_anchors(&text, context.config.slugify.anchors),
+                        0,
+                    ),
+                };
+                inserted_anchors.push(id.clone());
+                let level = t.level.min(6) as u32;
+                let (start, end) = anchor_links(&id, level, context)?;
+                write!(html, "<h{level} id=\"{}\">{start}", HtmlEscape(&id))?;
+                html.extend(inner);
+                write!(html, "{end}</h{level}>")?;
+                collector.add_heading(level, &id, text, context);
+            }
+            Event::Start(Element::Link(link)) if link.desc.is_none() && is_image(&link.path) => {
+                let path = link.path.strip_prefix("file:").unwrap_or(&link.path);
+                let src = collector.fix_link(path, false, context)?;
+                write!(out, "<img src=\"{}\" alt=\"{}\"", HtmlEscape(&src), HtmlEscape(path))?;
+                if context.config.markdown.lazy_async_image {
+                    write!(out, " loading=\"lazy\" decoding=\"async\"")?;
+                }
+                write!(out, " />")?;
+            }
+            Event::Start(Element::Link(link)) => {
+                let is_email = link.path.starts_with("mailto:");
+                let href = collector.fix_link(&link.path, is_email, context)?;
+                write!(
+                    out,
+                    "{}{}</a>",
+                    link_start_tag(&href, &link.path, context),
+                    HtmlEscape(link.desc.as_ref().unwrap_or(&link.path)),
+                )?;
+            }
+            Event::Start(element) => {
+                if let Element::Text { value } = element
+                    && in_title
+                {
+                    title_text.push_str(value);
+                }
+                if element.is_container() {
+                    depth += 1;
+                }
+                handler.start(out, element)?;
+            }
+            Event::End(element) => {
+                if element.is_container() {
+                    depth -= 1;
+                }
+                handler.end(out, element)?;
+            }
+        }
+    }
+
+    Ok(String::from_utf8(html)?)
+}
@@ -0,0 +1,226 @@
+//! Responsive versions of the colocated images, enabled with `markdown.images.enabled`.
+//! The images are resized to the configured widths in each configured format and rendered with
+//! a `srcset`, in a `<picture>` with a `<source>` per format when there are several of them.
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+use errors::{Context, Result};
+use imageproc::{ResizeOperation, read_image_metadata};
+use pulldown_cmark_escape::{escape_href, escape_html};
+
+use crate::attributes::Attributes;
+use crate::context::RenderContext;
+
+/// Only raster images are resized, the other ones like SVG or animated GIF are kept as is
+const RESIZABLE_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png", "webp"];
+
+fn mime_type(url: &str) -> &'static str {
+    match url.rsplit('.').next() {
+        Some("avif") => "image/avif",
+        Some("webp") => "image/webp",
+        Some("png") => "image/png",
+        _ => "image/jpeg",
+    }
+}
+
+/// The resized versions of a colocated image
+#[derive(Debug, PartialEq)]
+pub(crate) struct ResponsiveImage {
+    /// The mime type and the URL and width of the versions of each format, the last format
+    /// being the one of the `<img>`
+    srcsets: Vec<(&'static str, Vec<(String, u32)>)>,
+    /// The largest version in the last format
+    src: String,
+    /// The path of the original image
+    path: PathBuf,
+    /// The size of the original image
+    width: u32,
+    height: u32,
+    sizes: Option<String>,
+}
+
+impl ResponsiveImage {
+    /// Enqueues the resized versions of the image at that link, relative to the current page.
+    /// Returns `None` if the image isn't resized: when it is not a colocated raster image or
+    /// when responsive images are disabled.
+    pub(crate) fn new(link: &str, context: &RenderContext) -> Result<Option<ResponsiveImage>> {
+        let images = context.images;
+        let (true, Some(imageproc), Some(page_path)) =
+            (images.enabled, context.imageproc.as_ref(), context.current_page_path)
+        else {
+            return Ok(None);
+        };
+        let extension = Path::new(link).extension().and_then(|e| e.to_str());
+        if !extension.is_some_and(|e| RESIZABLE_EXTENSIONS.contains(&e.to_lowercase().as_str())) {
+            return Ok(None);
+        }
+
+        // The unified path of `resize_image`, so both share the resized images
+        let unified_path = match page_path.rsplit_once('/') {
+            Some((dir, _)) => format!("{dir}/{link}"),
+            None => link.to_string(),
+        };
+        let base_path = context.config.markdown.base_path.clone().unwrap_or_default();
+        let input_path = base_path.join("content").join(&unified_path);
+        if !input_path.is_file() {
+            return Ok(None);
+        }
+
+        let error = || format!("Failed to make the responsive versions of `{link}` in {page_path}");
+        let meta = read_image_metadata(&input_path).with_context(error)?;
+        // Images are never upscaled
+        let mut widths: Vec<_> = images.widths.iter().map(|w| (*w).min(meta.width)).collect();
+        widths.sort_unstable();
+        widths.dedup();
+
+        let mut imageproc = imageproc.lock().expect("Couldn't lock imageproc (responsive images)");
+        let mut srcsets = Vec::with_capacity(images.formats.len());
+        let mut src = String::new();
+        for format in &images.formats {
+            let mut srcset = Vec::with_capacity(widths.len());
+            for width in &widths {
+                let response = imageproc
+                    .enqueue(
+                        ResizeOperation::FitWidth(*width),
+                        unified_path.clone(),
+                        input_path.clone(),
+                        format,
+                        images.quality,
+                        None,
+                    )
+                    .with_context(error)?;
+                src = response.url.clone();
+                srcset.push((response.url, response.width));
+            }
+            srcsets.push((mime_type(&src), srcset));
+        }
+
+        Ok(Some(ResponsiveImage {
+            srcsets,
+            src,
+            path: input_path,
+            width: meta.width,
+            height: meta.height,
+            sizes: images.sizes.clone(),
+        }))
+    }
+
+    /// The URL to use as `src` of the `<img>`
+    pub(crate) fn src(&self) -> &str {
+        &self.src
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn is_picture(&self) -> bool {
+        self.srcsets.len() > 1
+    }
+
+    fn write_srcset(out: &mut String, srcset: &[(String, u32)]) {
+        out.push_str(" srcset=\"");
+        for (i, (url, width)) in srcset.iter().enumerate() {
+            if i > 0 {
+                out.push_str(", ");
+            }
+            escape_href(&mut *out, url).expect("Could not write to buffer");
+            write!(out, " {width}w").expect("Could not write to buffer");
+        }
+        out.push('"');
+    }
+
+    fn write_sizes(&self, out: &mut String) {
+        if let Some(sizes) = &self.sizes {
+            out.push_str(" sizes=\"");
+            escape_html(&mut *out, sizes).expect("Could not write to buffer");
+            out.push('"');
+        }
+    }
+
+    /// Writes what comes before the `<img>`: the opening `<picture>` and the `<source>` of all
+    /// the formats but the last one
+    pub(crate) fn write_start(&self, out: &mut String) {
+        if !self.is_picture() {
+            return;
+        }
+        out.push_str("<picture>");
+        for (mime, srcset) in &self.srcsets[..self.srcsets.len() - 1] {
+            write!(out, "<source type=\"{mime}\"").expect("Could not write to buffer");
+            Self::write_srcset(out, srcset);
+            self.write_sizes(out);
+            out.push_str(" />");
+        }
+    }
+
+    /// Writes the attributes of the `<img>`, leaving out the ones set by an attribute list
+    pub(crate) fn write_img_attributes(&self, out: &mut String, attributes: &Attributes) {
+        if let Some((_, srcset)) = self.srcsets.last() {
+            Self::write_srcset(out, srcset);
+        }
+        if !attributes.contains("sizes") {
+            self.write_sizes(out);
+        }
+        // The intrinsic size avoids layout shifts while the image loads
+        if !attributes.contains("width") && !attributes.contains("height") {
+            write!(out, " width=\"{}\" height=\"{}\"", self.width, self.height)
+                .expect("Could not write to buffer");
+        }
+    }
+
+    /// Writes what comes after the `<img>`
+    pub(crate) fn write_end(&self, out: &mut String) {
+        if self.is_picture() {
+            out.push_str("</picture>");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image(formats: &[&str]) -> ResponsiveImage {
+        ResponsiveImage {
+            srcsets: formats
+                .iter()
+                .map(|f| {
+                    let url = format!("https://example.com/processed_images/a.{f}");
+                    (mime_type(&url), vec![(url, 480)])
+                })
+                .collect(),
+            src: "https://example.com/processed_images/a.jpg".to_string(),
+            path: PathBuf::from("content/a.jpg"),
+            width: 640,
+            height: 480,
+            sizes: Some("(max-width: 600px) 100vw, 50vw".to_string()),
+        }
+    }
+
+    fn render(image: &ResponsiveImage, attributes: &Attributes) -> String {
+        let mut html = String::new();
+        image.write_start(&mut html);
+        write!(html, "<img src=\"{}\" alt=\"\"", image.src()).unwrap();
+        image.write_img_attributes(&mut html, attributes);
+        html.push_str(" />");
+        image.write_end(&mut html);
+        html
+    }
+
+    #[test]
+    fn can_render_picture_with_several_formats() {
+        assert_eq!(
+            render(&image(&["avif", "jpg"]), &Attributes::default()),
+            "<picture><source type=\"image/avif\" srcset=\"https://example.com/processed_images/a.avif 480w\" sizes=\"(max-width: 600px) 100vw, 50vw\" /><img src=\"https://example.com/processed_images/a.jpg\" alt=\"\" srcset=\"https://example.com/processed_images/a.jpg 480w\" sizes=\"(max-width: 600px) 100vw, 50vw\" width=\"640\" height=\"480\" /></picture>"
+        );
+    }
+
+    #[test]
+    fn can_render_img_with_a_single_format() {
+        let attributes = Attributes::parse("{width=320}").unwrap();
+        assert_eq!(
+            render(&image(&["webp"]), &attributes),
+            "<img src=\"https://example.com/processed_images/a.jpg\" alt=\"\" srcset=\"https://example.com/processed_images/a.webp 480w\" sizes=\"(max-width: 600px) 100vw, 50vw\" />"
+        );
+    }
+}
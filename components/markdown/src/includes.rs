@@ -0,0 +1,180 @@
+//! Code blocks showing the content of a file, like ```` ```rust,file=examples/main.rs ````.
+//! The file can be restricted to some lines with `lines=10-30` or to a region between
+//! `ANCHOR: name` and `ANCHOR_END: name` markers with `region=name`.
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use errors::{Context, Result, anyhow, bail};
+use giallo::ParsedFence;
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use crate::context::RenderContext;
+
+/// The markers of the regions, usually in a comment like `// ANCHOR: setup`
+static ANCHOR_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\bANCHOR(_END)?:\s*([\w-]+)").unwrap());
+
+/// Reads the file a code block includes, if any, and removes the include options from its fence.
+/// Returns the canonical path of the file, to track it, and the code to show.
+/// Only the files inside the site directory can be included.
+pub(crate) fn include_file(
+    fence: &mut ParsedFence,
+    context: &RenderContext,
+) -> Result<Option<(PathBuf, String)>> {
+    let Some(file) = fence.rest.remove("file") else {
+        return Ok(None);
+    };
+    let lines = fence.rest.remove("lines");
+    let region = fence.rest.remove("region");
+    let location = match context.current_page_path {
+        Some(p) => format!(" in {p:?}"),
+        None => String::new(),
+    };
+
+    // Same as the bibliography: relative to the site root or to `content` with `@/`
+    let base_path = context.config.markdown.base_path.clone().unwrap_or_default();
+    let path = match file.strip_prefix("@/") {
+        Some(p) => base_path.join("content").join(p),
+        None => base_path.join(&file),
+    };
+    let error = || format!("Failed to include `{file}` in a code block{location}");
+    let root = if base_path.as_os_str().is_empty() { Path::new(".") } else { &base_path };
+    let root = root.canonicalize().with_context(error)?;
+    let path = path.canonicalize().with_context(error)?;
+    if !path.starts_with(&root) {
+        return Err(anyhow!("`{file}` is outside of the site directory")).with_context(error);
+    }
+    let content = fs::read_to_string(&path).with_context(error)?;
+
+    let code = match (lines, region) {
+        (Some(_), Some(_)) => {
+            bail!(
+                "The code block including `{file}`{location} can't have both `lines` and `region`"
+            )
+        }
+        (Some(lines), None) => select_lines(&content, &lines),
+        (None, Some(region)) => select_region(&content, &region),
+        (None, None) => Ok(content),
+    }
+    .with_context(error)?;
+
+    Ok(Some((path, code)))
+}
+
+/// Keeps the lines in a 1-indexed inclusive range like `10-30`, `10` or `10-`
+fn select_lines(content: &str, range: &str) -> Result<String> {
+    let parse = |s: &str| s.trim().parse::<usize>().ok().filter(|n| *n > 0);
+    let (start, end) = match range.split_once('-') {
+        Some((start, "")) => (parse(start), Some(usize::MAX)),
+        Some((start, end)) => (parse(start), parse(end)),
+        None => (parse(range), parse(range)),
+    };
+    let (Some(start), Some(end)) = (start, end) else {
+        bail!("`lines={range}` is not a range of lines like `10-30`");
+    };
+
+    let lines: Vec<_> = content.lines().collect();
+    let end = if end == usize::MAX { lines.len() } else { end };
+    if start > end || end > lines.len() {
+        bail!("`lines={range}` is out of the {} lines of the file", lines.len());
+    }
+    Ok(dedent(&lines[start - 1..end]))
+}
+
+/// Keeps the lines between the `ANCHOR: name` and `ANCHOR_END: name` markers, without the
+/// lines of any marker
+fn select_region(content: &str, name: &str) -> Result<String> {
+    let mut lines = Vec::new();
+    let mut inside = false;
+    for line in content.lines() {
+        match ANCHOR_RE.captures(line) {
+            Some(caps) if &caps[2] == name => {
+                if caps.get(1).is_some() {
+                    if inside {
+                        return Ok(dedent(&lines));
+                    }
+                } else {
+                    inside = true;
+                }
+            }
+            Some(_) => (),
+            None if inside => lines.push(line),
+            None => (),
+        }
+    }
+
+    if inside {
+        bail!("The region `{name}` is never closed with `ANCHOR_END: {name}`");
+    }
+    bail!("There is no `ANCHOR: {name}` marker for the region `{name}`");
+}
+
+/// Removes the indentation common to the lines, since a part of a file is often indented
+fn dedent(lines: &[&str]) -> String {
+    let indent = lines
+        .iter()
+        .filter(|l| !l.trim().is_empty())
+        .map(|l| l.len() - l.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    let mut code = String::new();
+    for line in lines {
+        code.push_str(line.get(indent..).unwrap_or("").trim_end_matches('\r'));
+        code.push('\n');
+    }
+    code
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FILE: &str = "fn main() {
+    // ANCHOR: setup
+    let x = 1;
+    // ANCHOR: inner
+    let y = 2;
+    // ANCHOR_END: inner
+    // ANCHOR_END: setup
+}
+";
+
+    #[test]
+    fn can_select_lines() {
+        assert_eq!(select_lines(FILE, "3").unwrap(), "let x = 1;\n");
+        assert_eq!(
+            select_lines(FILE, "1-3").unwrap(),
+            FILE.lines().take(3).collect::<Vec<_>>().join("\n") + "\n"
+        );
+        assert_eq!(select_lines(FILE, "8-").unwrap(), "}\n");
+    }
+
+    #[test]
+    fn errors_on_invalid_lines() {
+        assert!(select_lines(FILE, "0-2").is_err());
+        assert!(select_lines(FILE, "3-2").is_err());
+        assert!(select_lines(FILE, "a-b").is_err());
+        assert_eq!(
+            select_lines(FILE, "5-20").unwrap_err().to_string(),
+            "`lines=5-20` is out of the 8 lines of the file"
+        );
+    }
+
+    #[test]
+    fn can_select_region() {
+        assert_eq!(select_region(FILE, "setup").unwrap(), "let x = 1;\nlet y = 2;\n");
+        assert_eq!(select_region(FILE, "inner").unwrap(), "let y = 2;\n");
+    }
+
+    #[test]
+    fn errors_on_missing_region() {
+        assert_eq!(
+            select_region(FILE, "nope").unwrap_err().to_string(),
+            "There is no `ANCHOR: nope` marker for the region `nope`"
+        );
+        assert_eq!(
+            select_region("// ANCHOR: open\nhello\n", "open").unwrap_err().to_string(),
+            "The region `open` is never closed with `ANCHOR_END: open`"
+        );
+    }
+}
@@ -1,16 +1,49 @@
+mod attributes;
+mod citations;
+mod containers;
 mod context;
+mod fences;
+mod formats;
+mod images;
+mod includes;
 mod markdown;
+mod math;
 mod shortcode;
+mod transforms;
+mod wiki_links;
 
+use containers::render_with_containers;
 use shortcode::{extract_shortcodes, insert_md_shortcodes};
 
 use errors::Result;
+use utils::types::ContentFormat;
 
 pub use crate::markdown::Rendered;
 use crate::markdown::markdown_to_html;
+pub use citations::{Citation, bibliography_path};
 pub use context::RenderContext;
+pub use fences::{DotRenderer, FenceCache, FenceRenderer};
+pub use formats::notebook::Notebook;
+pub use pulldown_cmark;
+pub use transforms::{MarkdownTransform, WrapTables};
 
 pub fn render_content(content: &str, context: &RenderContext) -> Result<markdown::Rendered> {
+    // Shortcodes and containers are only available in Markdown
+    if context.format != ContentFormat::Markdown {
+        return formats::render_format(content, context);
+    }
+
+    if context.config.markdown.containers && content.contains(":::") {
+        return render_with_containers(content, context);
+    }
+
+    render_markdown(content, context)
+}
+
+pub(crate) fn render_markdown(
+    content: &str,
+    context: &RenderContext,
+) -> Result<markdown::Rendered> {
     // avoid parsing the content if needed
     if !content.contains("{{") && !content.contains("{%") {
         return markdown_to_html(content, context, Vec::new());
@@ -18,7 +51,8 @@ pub fn render_content(content: &str, context: &RenderContext) -> Result<markdown
 
     let definitions = context.shortcode_definitions.as_ref();
     // Extract all the defined shortcodes
-    let (content, shortcodes) = extract_shortcodes(content, definitions)?;
+    let (content, shortcodes) =
+        extract_shortcodes(content, definitions, context.current_page_path, &context.tera_context)?;
 
     // Step 1: we render the MD shortcodes before rendering the markdown so they can get processed
     let (content, html_shortcodes) =
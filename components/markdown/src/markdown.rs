@@ -1,7 +1,10 @@
 use std::collections::HashMap;
 use std::fmt::Write;
+use std::ops::Range;
+use std::path::PathBuf;
 
 use crate::markdown::cmark::CowStr;
+use config::FootnoteStyle;
 use errors::bail;
 use gh_emoji::Replacer as EmojiReplacer;
 use giallo::{HtmlRenderer, ParsedFence, parse_markdown_fence};
@@ -21,15 +24,24 @@ use utils::table_of_contents::{Heading, make_table_of_contents};
 use utils::types::InsertAnchor;
 
 use self::cmark::{Event, LinkType, Options, Parser, Tag, TagEnd};
+use crate::attributes::{Attributes, apply_table_attributes, extract_attributes, tag_to_html};
+use crate::citations::{Bibliography, Citation, bibliography_path, render_citations};
+use crate::fences::{get_fence_renderers, render_fence};
+use crate::images::ResponsiveImage;
+use crate::includes::include_file;
+use crate::math::render_math;
 use crate::shortcode::{SHORTCODE_PLACEHOLDER, Shortcode};
+use crate::transforms::get_transforms;
+use crate::wiki_links::resolve_wiki_link;
 
-const CONTINUE_READING: &str = "<span id=\"continue-reading\"></span>";
+pub(crate) const CONTINUE_READING: &str = "<span id=\"continue-reading\"></span>";
 const SUMMARY_CUTOFF_TEMPLATE: &str = "summary-cutoff.html";
 const ANCHOR_LINK_TEMPLATE: &str = "anchor-link.html";
+const REFERENCES_TEMPLATE: &str = "references.html";
 static EMOJI_REPLACER: Lazy<EmojiReplacer> = Lazy::new(EmojiReplacer::new);
 
 /// Set as a regex to help match some extra cases. This way, spaces and case don't matter.
-static MORE_DIVIDER_RE: Lazy<Regex> = Lazy::new(|| {
+pub(crate) static MORE_DIVIDER_RE: Lazy<Regex> = Lazy::new(|| {
     RegexBuilder::new(r#"<!--\s*more\s*-->"#)
         .case_insensitive(true)
         .dot_matches_new_line(true)
@@ -51,7 +63,8 @@ static STARTS_WITH_SCHEMA_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^[0-9A-Za-
 
 /// Matches a <a>..</a> tag, getting the opening tag in a capture group.
 /// Used only with AnchorInsert::Heading to grab it from the template
-static A_HTML_TAG: Lazy<Regex> = Lazy::new(|| Regex::new(r"(<\s*a[^>]*>).*?<\s*/\s*a>").unwrap());
+pub(crate) static A_HTML_TAG: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(<\s*a[^>]*>).*?<\s*/\s*a>").unwrap());
 
 /// Efficiently insert multiple element in their specified index.
 /// The elements should sorted in ascending order by their index.
@@ -90,6 +103,12 @@ pub struct Rendered {
     pub internal_links: Vec<(String, Option<String>)>,
     /// Outgoing links to external webpages (i.e. HTTP(S) targets).
     pub external_links: Vec<String>,
+    /// The bibliography entries cited, in the order of the reference list
+    pub citations: Vec<Citation>,
+    /// The files included in code blocks with `file=path`
+    pub included_files: Vec<PathBuf>,
+    /// The colocated images resized for responsive images
+    pub responsive_images: Vec<PathBuf>,
 }
 
 /// Tracks a heading in a slice of pulldown-cmark events
@@ -139,7 +158,7 @@ impl HeadingRef {
 // for example an article could have several titles named Example
 // We add a counter after the slug if the slug is already present, which
 // means we will have example, example-1, example-2 etc
-fn find_anchor(anchors: &[String], name: String, level: u16) -> String {
+pub(crate) fn find_anchor(anchors: &[String], name: String, level: u16) -> String {
     if level == 0 && !anchors.contains(&name) {
         return name;
     }
@@ -152,7 +171,7 @@ fn find_anchor(anchors: &[String], name: String, level: u16) -> String {
     find_anchor(anchors, name, level + 1)
 }
 
-fn fix_link(
+pub(crate) fn fix_link(
     link_type: LinkType,
     link: &str,
     context: &RenderContext,
@@ -210,6 +229,17 @@ fn fix_link(
     Ok(result)
 }
 
+/// Renders the `anchor-link.html` template for the heading with that id
+pub(crate) fn render_anchor_link(id: &str, level: u32, context: &RenderContext) -> Result<String> {
+    let mut c = tera::Context::new();
+    c.insert("id", id);
+    c.insert("level", &level);
+    c.insert("lang", &context.lang);
+
+    utils::templates::render_template(ANCHOR_LINK_TEMPLATE, &context.tera, c, &None)
+        .context("Failed to render anchor link template")
+}
+
 /// get only text in a slice of events
 fn get_text(parser_slice: &[Event]) -> String {
     let mut title = String::new();
@@ -400,6 +430,141 @@ fn convert_footnotes_to_github_style(old_events: &mut Vec<Event>) {
     old_events.push(Event::Html("</ol>\n</section>\n".into()));
 }
 
+/// Replaces the `§` of the `[§](#id)` links to a heading of the page by the number of that heading
+fn number_cross_references(
+    events: &mut [Event],
+    heading_numbers: &HashMap<String, String>,
+    context: &RenderContext,
+) -> Result<()> {
+    for i in 1..events.len().saturating_sub(1) {
+        let (Event::Start(Tag::Link { dest_url, .. }), Event::Text(text), Event::End(TagEnd::Link)) =
+            (&events[i - 1], &events[i], &events[i + 1])
+        else {
+            continue;
+        };
+        // Anchor links have been made absolute by `fix_link`
+        let anchor = dest_url.strip_prefix(context.current_page_permalink).unwrap_or(dest_url);
+        let Some(id) = anchor.strip_prefix('#').filter(|_| text.as_ref() == "§") else {
+            continue;
+        };
+        match heading_numbers.get(id) {
+            Some(number) => events[i] = Event::Text(number.clone().into()),
+            None => {
+                let msg = format!(
+                    "Cross-reference `[§](#{id})` in {}: there is no numbered heading with this id",
+                    context.current_page_path.unwrap_or("unknown")
+                );
+                match context.config.link_checker.internal_level {
+                    config::LinkCheckerLevel::Error => bail!(msg),
+                    config::LinkCheckerLevel::Warn => log::warn!("{msg}"),
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Renders each footnote next to its first reference, in a `<span class="sidenote">` that CSS can
+/// move to the margin, with a checkbox toggling it on narrow screens.
+/// The numbers and the ids of the references are the same as with `convert_footnotes_to_github_style`
+fn convert_footnotes_to_sidenotes(old_events: &mut Vec<Event>) {
+    let events = std::mem::take(old_events);
+    // Step 1: extract the footnote definitions, since they can be anywhere in the content and
+    // are usually after their references
+    let mut definitions = HashMap::new();
+    let mut definitions_stack: Vec<(CowStr, Vec<Event>)> = Vec::new();
+    let mut main_events = Vec::with_capacity(events.len());
+    for event in events {
+        match event {
+            Event::Start(Tag::FootnoteDefinition(name)) => {
+                definitions_stack.push((name, Vec::new()));
+            }
+            Event::End(TagEnd::FootnoteDefinition) => {
+                let (name, body) = definitions_stack.pop().unwrap();
+                definitions.entry(name).or_insert(body);
+            }
+            _ => match definitions_stack.last_mut() {
+                Some((_, body)) => body.push(event),
+                None => main_events.push(event),
+            },
+        }
+    }
+
+    // Step 2: replace the references, the definitions never referenced are dropped
+    let mut footnote_numbers = HashMap::new();
+    push_sidenotes(old_events, main_events, &mut definitions, &mut footnote_numbers, false);
+}
+
+/// The sidenotes are rendered inside the paragraph of their first reference, where the blocks
+/// other than paragraphs would make invalid HTML
+fn check_sidenotes(events: &[(Event, Range<usize>)], path: Option<&str>) -> Result<()> {
+    let mut footnote = None;
+    for (event, _) in events {
+        let block = match event {
+            Event::Start(Tag::FootnoteDefinition(name)) => {
+                footnote = Some(name);
+                continue;
+            }
+            Event::End(TagEnd::FootnoteDefinition) => {
+                footnote = None;
+                continue;
+            }
+            Event::Start(Tag::List(_)) => "a list",
+            Event::Start(Tag::CodeBlock(_)) => "a code block",
+            Event::Start(Tag::BlockQuote(_)) => "a blockquote",
+            Event::Start(Tag::Table(_)) => "a table",
+            Event::Start(Tag::Heading { .. }) => "a heading",
+            Event::Start(Tag::HtmlBlock) => "an HTML block",
+            Event::Start(Tag::DefinitionList) => "a definition list",
+            Event::Rule => "a horizontal rule",
+            _ => continue,
+        };
+        if let Some(name) = footnote {
+            let location = if let Some(p) = path { format!(" in {p:?}") } else { String::new() };
+            bail!(
+                "The footnote `{name}`{location} contains {block}: sidenotes can only contain paragraphs, use `footnote_style = \"bottom\"` for such footnotes"
+            );
+        }
+    }
+    Ok(())
+}
+
+fn push_sidenotes<'a>(
+    out: &mut Vec<Event<'a>>,
+    events: Vec<Event<'a>>,
+    definitions: &mut HashMap<CowStr<'a>, Vec<Event<'a>>>,
+    footnote_numbers: &mut HashMap<CowStr<'a>, (usize, usize)>,
+    in_sidenote: bool,
+) {
+    for event in events {
+        match event {
+            Event::FootnoteReference(name) => {
+                let n = footnote_numbers.len() + 1;
+                let (n, nr) = footnote_numbers.entry(name.clone()).or_insert((n, 0usize));
+                *nr += 1;
+                let (n, nr) = (*n, *nr);
+                // Only the first reference gets the sidenote, the other ones link to it
+                let Some(body) = definitions.remove(&name) else {
+                    out.push(Event::Html(format!(r##"<sup class="footnote-reference" id="fr-{name}-{nr}"><a href="#fn-{name}">{n}</a></sup>"##).into()));
+                    continue;
+                };
+                out.push(Event::Html(format!(r##"<sup class="footnote-reference" id="fr-{name}-{nr}"><label for="sn-{name}" class="sidenote-toggle">{n}</label></sup><input type="checkbox" id="sn-{name}" class="sidenote-toggle" /><span class="sidenote" id="fn-{name}" role="note"><span class="sidenote-number">{n}</span> "##).into()));
+                // Footnotes can reference other footnotes
+                push_sidenotes(out, body, definitions, footnote_numbers, true);
+                out.push(Event::Html("</span>".into()));
+            }
+            // The sidenotes are inside paragraphs, so they can't contain any
+            Event::Start(Tag::Paragraph) if in_sidenote => {
+                out.push(Event::Html(r#"<span class="sidenote-paragraph">"#.into()));
+            }
+            Event::End(TagEnd::Paragraph) if in_sidenote => {
+                out.push(Event::Html("</span>".into()));
+            }
+            _ => out.push(event),
+        }
+    }
+}
+
 pub fn markdown_to_html(
     content: &str,
     context: &RenderContext,
@@ -427,8 +592,13 @@ pub fn markdown_to_html(
     let mut headings: Vec<Heading> = vec![];
     let mut internal_links = Vec::new();
     let mut external_links = Vec::new();
+    let mut citations = Vec::new();
+    let mut included_files = Vec::new();
+    let mut responsive_images = Vec::new();
 
     let mut stop_next_end_p = false;
+    // Set when a wiki link couldn't be resolved and is rendered as plain text
+    let mut stop_next_end_link = false;
 
     let lazy_async_image = context.config.markdown.lazy_async_image;
 
@@ -449,11 +619,22 @@ pub fn markdown_to_html(
     if context.config.markdown.github_alerts {
         opts.insert(Options::ENABLE_GFM);
     }
+    if context.config.markdown.math {
+        opts.insert(Options::ENABLE_MATH);
+    }
+    if context.config.markdown.wiki_links {
+        opts.insert(Options::ENABLE_WIKILINKS);
+    }
 
     // we reverse their order so we can pop them easily in order
     let mut html_shortcodes: Vec<_> = html_shortcodes.into_iter().rev().collect();
     let mut next_shortcode = html_shortcodes.pop();
     let contains_shortcode = |txt: &str| -> bool { txt.contains(SHORTCODE_PLACEHOLDER) };
+    let transforms = get_transforms(context)?;
+    let fence_renderers = get_fence_renderers(context)?;
+    let bibliography = bibliography_path(context.config, context.bibliography)
+        .map(|p| Bibliography::load(&p))
+        .transpose()?;
 
     {
         let mut events = Vec::new();
@@ -515,7 +696,22 @@ pub fn markdown_to_html(
             };
         }
 
-        for (event, mut range) in Parser::new_ext(content, opts).into_offset_iter() {
+        let parsed: Vec<_> = Parser::new_ext(content, opts).into_offset_iter().collect();
+        let (parsed, mut attributes) = if context.config.markdown.attribute_lists {
+            extract_attributes(parsed)
+        } else {
+            (parsed, HashMap::new())
+        };
+        if context.config.markdown.footnote_style() == FootnoteStyle::Sidenote {
+            check_sidenotes(&parsed, context.current_page_path)?;
+        }
+        // Applied after the transforms, see `apply_table_attributes`
+        let mut table_attributes = Vec::new();
+        let mut code_block_attributes: Option<Attributes> = None;
+        let mut image_attributes: Option<Attributes> = None;
+        let mut responsive_image: Option<ResponsiveImage> = None;
+
+        for (i, (event, mut range)) in parsed.into_iter().enumerate() {
             match event {
                 Event::Text(text) => {
                     if code_block.is_some() {
@@ -564,6 +760,14 @@ pub fn markdown_to_html(
                         render_shortcodes!(true, text, range);
                     }
                 }
+                Event::InlineMath(ref source) | Event::DisplayMath(ref source) => {
+                    let display = matches!(event, Event::DisplayMath(_));
+                    let location =
+                        if let Some(p) = path { format!(" in {p:?}") } else { String::new() };
+                    let html = render_math(source, display)
+                        .with_context(|| format!("Failed to render math `{source}`{location}"))?;
+                    events.push(Event::InlineHtml(html.into()));
+                }
                 Event::Start(Tag::CodeBlock(ref kind)) => {
                     let fence = match kind {
                         cmark::CodeBlockKind::Fenced(fence_info) => {
@@ -572,10 +776,33 @@ pub fn markdown_to_html(
                         _ => ParsedFence::default(),
                     };
                     code_block = Some(fence);
+                    code_block_attributes = attributes.remove(&i);
                 }
                 Event::End(TagEnd::CodeBlock) => {
-                    let html = if let Some(code) = code_block.take() {
-                        if let Some(hl) = &context.config.markdown.highlighting {
+                    let html = if let Some(mut code) = code_block.take() {
+                        if let Some((path, content)) = include_file(&mut code, context)? {
+                            code_block_content = content;
+                            included_files.push(path);
+                        }
+                        if let Some(renderer) =
+                            fence_renderers.iter().find(|r| r.handles(&code.lang))
+                        {
+                            let location = if let Some(p) = path {
+                                format!(" in {p:?}")
+                            } else {
+                                String::new()
+                            };
+                            let mut html = render_fence(
+                                renderer.as_ref(),
+                                &code_block_content,
+                                context.fence_cache.as_deref(),
+                            )
+                            .with_context(|| {
+                                format!("Failed to render `{}` code block{location}", code.lang)
+                            })?;
+                            html.push('\n');
+                            html
+                        } else if let Some(hl) = &context.config.markdown.highlighting {
                             if !hl.registry.contains_grammar(&code.lang) {
                                 let location = if let Some(p) = path {
                                     format!(" in {p:?}")
@@ -620,42 +847,95 @@ pub fn markdown_to_html(
                             "can we get into a TagEnd::CodeBlock without having seen TagStart?"
                         )
                     };
+                    let html = match code_block_attributes.take() {
+                        Some(attrs) => attrs.apply(&html),
+                        None => html,
+                    };
                     events.push(Event::Html(html.into()));
                     code_block = None;
                     code_block_content.clear();
                 }
                 Event::Start(Tag::Image { link_type, dest_url, title, id }) => {
                     let link = if is_colocated_asset_link(&dest_url) {
+                        match ResponsiveImage::new(&dest_url, context) {
+                            Ok(image) => {
+                                if let Some(image) = &image {
+                                    responsive_images.push(image.path().to_path_buf());
+                                }
+                                responsive_image = image;
+                            }
+                            Err(err) => error = Some(err),
+                        }
                         let link = format!("{}{}", context.current_page_permalink, &*dest_url);
                         link.into()
                     } else {
                         dest_url
                     };
 
-                    events.push(if lazy_async_image {
-                        let mut img_before_alt: String = "<img src=\"".to_string();
-                        cmark_escape::escape_href(&mut img_before_alt, &link)
-                            .expect("Could not write to buffer");
-                        if !title.is_empty() {
-                            img_before_alt
-                                .write_str("\" title=\"")
+                    image_attributes = attributes.remove(&i);
+                    events.push(
+                        if lazy_async_image
+                            || image_attributes.is_some()
+                            || responsive_image.is_some()
+                        {
+                            let mut img_before_alt = String::new();
+                            if let Some(image) = &responsive_image {
+                                image.write_start(&mut img_before_alt);
+                            }
+                            img_before_alt.push_str("<img src=\"");
+                            let src = responsive_image.as_ref().map_or(&*link, |image| image.src());
+                            cmark_escape::escape_href(&mut img_before_alt, src)
                                 .expect("Could not write to buffer");
-                            cmark_escape::escape_href(&mut img_before_alt, &title)
+                            if !title.is_empty() {
+                                img_before_alt
+                                    .write_str("\" title=\"")
+                                    .expect("Could not write to buffer");
+                                cmark_escape::escape_href(&mut img_before_alt, &title)
+                                    .expect("Could not write to buffer");
+                            }
+                            img_before_alt
+                                .write_str("\" alt=\"")
                                 .expect("Could not write to buffer");
-                        }
-                        img_before_alt.write_str("\" alt=\"").expect("Could not write to buffer");
-                        inside_attribute = true;
-                        Event::Html(img_before_alt.into())
-                    } else {
-                        inside_attribute = false;
-                        Event::Start(Tag::Image { link_type, dest_url: link, title, id })
-                    });
+                            inside_attribute = true;
+                            Event::Html(img_before_alt.into())
+                        } else {
+                            inside_attribute = false;
+                            Event::Start(Tag::Image { link_type, dest_url: link, title, id })
+                        },
+                    );
+                }
+                Event::End(TagEnd::Image) => {
+                    events.push(
+                        if lazy_async_image
+                            || image_attributes.is_some()
+                            || responsive_image.is_some()
+                        {
+                            let attrs = image_attributes.take().unwrap_or_default();
+                            let mut img_after_alt = "\"".to_string();
+                            attrs.write_html(&mut img_after_alt);
+                            if let Some(image) = &responsive_image {
+                                image.write_img_attributes(&mut img_after_alt, &attrs);
+                            }
+                            if lazy_async_image {
+                                // They can be overridden by the attribute list
+                                if !attrs.contains("loading") {
+                                    img_after_alt.push_str(" loading=\"lazy\"");
+                                }
+                                if !attrs.contains("decoding") {
+                                    img_after_alt.push_str(" decoding=\"async\"");
+                                }
+                            }
+                            img_after_alt.push_str(" />");
+                            if let Some(image) = responsive_image.take() {
+                                image.write_end(&mut img_after_alt);
+                            }
+                            inside_attribute = false;
+                            Event::Html(img_after_alt.into())
+                        } else {
+                            event
+                        },
+                    )
                 }
-                Event::End(TagEnd::Image) => events.push(if lazy_async_image {
-                    Event::Html("\" loading=\"lazy\" decoding=\"async\" />".into())
-                } else {
-                    event
-                }),
                 Event::Start(Tag::Link { link_type, dest_url, title, id })
                     if dest_url.is_empty() =>
                 {
@@ -668,9 +948,25 @@ pub fn markdown_to_html(
                     }));
                 }
                 Event::Start(Tag::Link { link_type, dest_url, title, id }) => {
+                    let link = match link_type {
+                        LinkType::WikiLink { .. } => match resolve_wiki_link(&dest_url, context) {
+                            Ok(Some(link)) => link,
+                            Ok(None) => {
+                                stop_next_end_link = true;
+                                events.push(Event::Html("".into()));
+                                continue;
+                            }
+                            Err(err) => {
+                                error = Some(err);
+                                events.push(Event::Html("".into()));
+                                continue;
+                            }
+                        },
+                        _ => dest_url.to_string(),
+                    };
                     let fixed_link = match fix_link(
                         link_type,
-                        &dest_url,
+                        &link,
                         context,
                         &mut internal_links,
                         &mut external_links,
@@ -683,6 +979,7 @@ pub fn markdown_to_html(
                         }
                     };
 
+                    let link_attributes = attributes.remove(&i);
                     events.push(
                         if is_external_link(&dest_url)
                             && context.config.markdown.has_external_link_tweaks()
@@ -691,20 +988,21 @@ pub fn markdown_to_html(
                             // write_str can fail but here there are no reasons it should (afaik?)
                             cmark_escape::escape_href(&mut escaped, &dest_url)
                                 .expect("Could not write to buffer");
-                            Event::Html(
-                                context
-                                    .config
-                                    .markdown
-                                    .construct_external_link_tag(&escaped, &title)
-                                    .into(),
-                            )
+                            let tag = context
+                                .config
+                                .markdown
+                                .construct_external_link_tag(&escaped, &title);
+                            match link_attributes {
+                                Some(attrs) => Event::Html(attrs.apply(&tag).into()),
+                                None => Event::Html(tag.into()),
+                            }
                         } else {
-                            Event::Start(Tag::Link {
-                                link_type,
-                                dest_url: fixed_link.into(),
-                                title,
-                                id,
-                            })
+                            let tag =
+                                Tag::Link { link_type, dest_url: fixed_link.into(), title, id };
+                            match link_attributes {
+                                Some(attrs) => Event::Html(attrs.apply(&tag_to_html(tag)).into()),
+                                None => Event::Start(tag),
+                            }
                         },
                     )
                 }
@@ -723,8 +1021,25 @@ pub fn markdown_to_html(
                         continue;
                     }
 
+                    match attributes.remove(&i) {
+                        Some(attrs) => events.push(Event::Html(attrs.apply("<p>").into())),
+                        None => events.push(event),
+                    }
+                }
+                Event::Start(Tag::Table(_)) => {
+                    table_attributes.push(attributes.remove(&i));
                     events.push(event);
                 }
+                Event::Start(tag @ (Tag::List(_) | Tag::BlockQuote(_)))
+                    if attributes.contains_key(&i) =>
+                {
+                    let attrs = attributes.remove(&i).unwrap();
+                    events.push(Event::Html(attrs.apply(&tag_to_html(tag)).into()));
+                }
+                Event::End(TagEnd::Link) if stop_next_end_link => {
+                    stop_next_end_link = false;
+                    events.push(Event::Html("".into()));
+                }
                 Event::End(TagEnd::Paragraph) => {
                     events.push(if stop_next_end_p {
                         stop_next_end_p = false;
@@ -754,8 +1069,33 @@ pub fn markdown_to_html(
             _ => true,
         });
 
+        if let Some(bibliography) = &bibliography {
+            (events, citations) =
+                render_citations(events, bibliography, context.current_page_path)?;
+        }
+
+        for transform in &transforms {
+            events = transform.transform(events, context).with_context(|| {
+                format!("Failed to run the `{}` markdown transform", transform.name())
+            })?;
+        }
+        events = apply_table_attributes(events, table_attributes);
+
+        // The levels of the config are validated when loading it, those can only come from the
+        // front matter
+        let (toc_min_level, toc_max_level) = (context.toc_min_level, context.toc_max_level);
+        if !(1..=6).contains(&toc_min_level) || !(toc_min_level..=6).contains(&toc_max_level) {
+            bail!(
+                "Invalid `toc_min_level = {toc_min_level}` and `toc_max_level = {toc_max_level}`{}: they must be between 1 and 6, the first one not above the second one",
+                context.current_page_path.map(|p| format!(" in {p:?}")).unwrap_or_default()
+            );
+        }
+
         let heading_refs = get_heading_refs(&events);
 
+        // The levels and numbers of the last numbered heading and its ancestors
+        let mut numbers_stack: Vec<(u32, usize)> = vec![];
+        let mut heading_numbers = HashMap::new();
         let mut anchors_to_insert = vec![];
         let mut inserted_anchors = vec![];
         for heading in &heading_refs {
@@ -784,6 +1124,29 @@ pub fn markdown_to_html(
             let html = heading_ref.to_html(id);
             events[start_idx] = Event::Html(html.into());
 
+            let in_toc = (toc_min_level..=toc_max_level).contains(&heading_ref.level);
+            let number = if in_toc && context.number_headings {
+                // Numbered like they are nested by `make_table_of_contents`, even if levels are skipped
+                let mut previous = 0;
+                while let Some(&(level, n)) = numbers_stack.last()
+                    && level >= heading_ref.level
+                {
+                    previous = n;
+                    numbers_stack.pop();
+                }
+                numbers_stack.push((heading_ref.level, previous + 1));
+                let number =
+                    numbers_stack.iter().map(|(_, n)| n.to_string()).collect::<Vec<_>>().join(".");
+                anchors_to_insert.push((
+                    start_idx + 1,
+                    Event::Html(format!(r#"<span class="heading-number">{number}</span> "#).into()),
+                ));
+                heading_numbers.insert(id.to_owned(), number.clone());
+                Some(number)
+            } else {
+                None
+            };
+
             // generate anchors and places to insert them
             if context.insert_anchor != InsertAnchor::None {
                 let anchor_idx = match context.insert_anchor {
@@ -792,18 +1155,7 @@ pub fn markdown_to_html(
                     InsertAnchor::Heading => 0, // modified later to the correct value
                     InsertAnchor::None => unreachable!(),
                 };
-                let mut c = tera::Context::new();
-                c.insert("id", &id);
-                c.insert("level", &heading_ref.level);
-                c.insert("lang", &context.lang);
-
-                let anchor_link = utils::templates::render_template(
-                    ANCHOR_LINK_TEMPLATE,
-                    &context.tera,
-                    c,
-                    &None,
-                )
-                .context("Failed to render anchor link template")?;
+                let anchor_link = render_anchor_link(id, heading_ref.level, context)?;
                 if context.insert_anchor != InsertAnchor::Heading {
                     anchors_to_insert.push((anchor_idx, Event::Html(anchor_link.into())));
                 } else if let Some(captures) = A_HTML_TAG.captures(&anchor_link) {
@@ -814,23 +1166,42 @@ pub fn markdown_to_html(
             }
 
             // record heading to make table of contents
-            let permalink = format!("{}#{}", context.current_page_permalink, id);
-            let h = Heading {
-                level: heading_ref.level,
-                id: id.to_owned(),
-                permalink,
-                title,
-                children: Vec::new(),
-            };
-            headings.push(h);
+            if in_toc {
+                let permalink = format!("{}#{}", context.current_page_permalink, id);
+                let h = Heading {
+                    level: heading_ref.level,
+                    id: id.to_owned(),
+                    permalink,
+                    title,
+                    number,
+                    children: Vec::new(),
+                };
+                headings.push(h);
+            }
         }
 
-        if context.insert_anchor != InsertAnchor::None {
+        if !anchors_to_insert.is_empty() {
             insert_many(&mut events, anchors_to_insert);
         }
 
-        if context.config.markdown.bottom_footnotes {
-            convert_footnotes_to_github_style(&mut events);
+        if context.number_headings {
+            number_cross_references(&mut events, &heading_numbers, context)?;
+        }
+
+        match context.config.markdown.footnote_style() {
+            FootnoteStyle::Plain => (),
+            FootnoteStyle::Bottom => convert_footnotes_to_github_style(&mut events),
+            FootnoteStyle::Sidenote => convert_footnotes_to_sidenotes(&mut events),
+        }
+
+        if !citations.is_empty() {
+            let mut c = tera::Context::new();
+            c.insert("citations", &citations);
+            c.insert("lang", &context.lang);
+            let references =
+                utils::templates::render_template(REFERENCES_TEMPLATE, &context.tera, c, &None)
+                    .context("Failed to render references template")?;
+            events.push(Event::Html(references.into()));
         }
 
         let continue_reading = events
@@ -861,8 +1232,13 @@ pub fn markdown_to_html(
                 &mut summary_html,
                 events.iter().take(continue_reading).cloned(),
             );
-            // remove footnotes
-            let mut summary_html = FOOTNOTES_RE.replace_all(&summary_html, "").into_owned();
+            // remove footnotes, unless they are sidenotes which are complete in the summary
+            let mut summary_html =
+                if context.config.markdown.footnote_style() == FootnoteStyle::Sidenote {
+                    summary_html
+                } else {
+                    FOOTNOTES_RE.replace_all(&summary_html, "").into_owned()
+                };
 
             // truncate trailing whitespace
             summary_html.truncate(summary_html.trim_end().len());
@@ -901,6 +1277,9 @@ pub fn markdown_to_html(
             toc: make_table_of_contents(headings),
             internal_links,
             external_links,
+            citations,
+            included_files,
+            responsive_images,
         })
     }
 }
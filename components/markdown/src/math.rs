@@ -0,0 +1,1008 @@
+//! Converts the LaTeX math found between `$` and `$$` to MathML so it can be displayed without
+//! any JavaScript. Only the commonly used subset of LaTeX math is supported: anything else is an error.
+use errors::{Result, bail};
+
+/// Renders a LaTeX math expression to a MathML `<math>` element
+pub fn render_math(source: &str, display: bool) -> Result<String> {
+    let mut parser = MathParser { chars: source.chars().collect(), pos: 0, display, variant: None };
+    let body = parser.parse_top_level()?;
+    let mut escaped_source = String::new();
+    escape_into(&mut escaped_source, source.trim());
+
+    Ok(format!(
+        "<math{}><semantics>{}<annotation encoding=\"application/x-tex\">{}</annotation></semantics></math>",
+        if display { " display=\"block\"" } else { "" },
+        body,
+        escaped_source
+    ))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Variant {
+    Normal,
+    Bold,
+    Italic,
+    BoldItalic,
+    DoubleStruck,
+    Script,
+    Fraktur,
+    SansSerif,
+    Monospace,
+}
+
+/// Where the scripts of an atom go
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Limits {
+    /// On the side
+    Never,
+    /// Under/over in display mode, on the side otherwise, eg `\sum`
+    Display,
+    /// Always under/over, eg `\underbrace`
+    Always,
+}
+
+#[derive(Debug)]
+struct Atom {
+    html: String,
+    limits: Limits,
+}
+
+impl Atom {
+    fn new(html: String) -> Self {
+        Atom { html, limits: Limits::Never }
+    }
+}
+
+/// Why parsing a list of atoms stopped
+#[derive(Debug, PartialEq)]
+enum Terminator {
+    Eof,
+    Brace,
+    Bracket,
+    Ampersand,
+    NewRow,
+    Right(String),
+    End(String),
+}
+
+struct MathParser {
+    chars: Vec<char>,
+    pos: usize,
+    display: bool,
+    /// Set by `\mathbf` and friends
+    variant: Option<Variant>,
+}
+
+impl MathParser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    fn parse_top_level(&mut self) -> Result<String> {
+        let mut rows = Vec::new();
+        loop {
+            let (atoms, terminator) = self.parse_atoms(false)?;
+            rows.push(render_row(atoms));
+            match terminator {
+                Terminator::Eof => break,
+                Terminator::NewRow => continue,
+                t => bail!("{}", unexpected(&t)),
+            }
+        }
+
+        if rows.len() == 1 {
+            return Ok(rows.pop().unwrap());
+        }
+        // `\\` outside of an environment: one expression per line
+        let mut html = String::from("<mtable>");
+        for row in rows {
+            html.push_str(&format!("<mtr><mtd>{}</mtd></mtr>", row));
+        }
+        html.push_str("</mtable>");
+        Ok(html)
+    }
+
+    /// Parses atoms until the end of the input or something that ends the current group
+    fn parse_atoms(&mut self, in_bracket: bool) -> Result<(Vec<Atom>, Terminator)> {
+        let mut atoms = Vec::new();
+        loop {
+            self.skip_whitespace();
+            let c = match self.peek() {
+                Some(c) => c,
+                None => return Ok((atoms, Terminator::Eof)),
+            };
+            self.pos += 1;
+
+            match c {
+                '}' => return Ok((atoms, Terminator::Brace)),
+                ']' if in_bracket => return Ok((atoms, Terminator::Bracket)),
+                '&' => return Ok((atoms, Terminator::Ampersand)),
+                '^' | '_' => self.attach_scripts(&mut atoms, c)?,
+                '\'' => {
+                    let mut primes = String::from("′");
+                    while self.peek() == Some('\'') {
+                        self.pos += 1;
+                        primes.push('′');
+                    }
+                    let base =
+                        atoms.pop().map(|a| a.html).unwrap_or_else(|| "<mrow></mrow>".into());
+                    atoms.push(Atom::new(format!("<msup>{}<mo>{}</mo></msup>", base, primes)));
+                }
+                '{' => {
+                    let inner = self.parse_group_body()?;
+                    atoms.push(Atom::new(render_row(inner)));
+                }
+                '\\' => {
+                    if self.peek() == Some('\\') {
+                        self.pos += 1;
+                        return Ok((atoms, Terminator::NewRow));
+                    }
+                    let name = self.read_command_name()?;
+                    match name.as_str() {
+                        "right" => return Ok((atoms, Terminator::Right(self.read_delimiter()?))),
+                        "end" => return Ok((atoms, Terminator::End(self.read_braced_text()?))),
+                        _ => {
+                            if let Some(atom) = self.command(&name)? {
+                                atoms.push(atom);
+                            }
+                        }
+                    }
+                }
+                '0'..='9' | '.' if c != '.' || self.peek().is_some_and(|n| n.is_ascii_digit()) => {
+                    let mut number = String::from(c);
+                    while let Some(n) = self.peek() {
+                        let is_decimal_point = n == '.'
+                            && !number.contains('.')
+                            && self.chars.get(self.pos + 1).is_some_and(|n| n.is_ascii_digit());
+                        if !n.is_ascii_digit() && !is_decimal_point {
+                            break;
+                        }
+                        number.push(n);
+                        self.pos += 1;
+                    }
+                    atoms.push(self.number(&number));
+                }
+                '~' => atoms.push(Atom::new("<mspace width=\"0.3333em\"></mspace>".into())),
+                c if c.is_alphabetic() => atoms.push(self.identifier(c)),
+                '-' => atoms.push(Atom::new(operator("−"))),
+                '*' => atoms.push(Atom::new(operator("∗"))),
+                c => atoms.push(Atom::new(operator(&c.to_string()))),
+            }
+        }
+    }
+
+    /// Parses until the closing `}`, the opening one has already been consumed
+    fn parse_group_body(&mut self) -> Result<Vec<Atom>> {
+        let (atoms, terminator) = self.parse_atoms(false)?;
+        match terminator {
+            Terminator::Brace => Ok(atoms),
+            Terminator::Eof => bail!("Missing closing `}}`"),
+            t => bail!("{}", unexpected(&t)),
+        }
+    }
+
+    /// The argument of a command or a script: either a group or a single token
+    fn parse_argument(&mut self, command: &str) -> Result<String> {
+        self.skip_whitespace();
+        match self.peek() {
+            None | Some('}') | Some('&') | Some('^') | Some('_') => {
+                bail!("Missing argument for `{}`", command)
+            }
+            Some('{') => {
+                self.pos += 1;
+                Ok(render_row(self.parse_group_body()?))
+            }
+            Some('\\') => {
+                self.pos += 1;
+                let name = self.read_command_name()?;
+                if name == "\\" || name == "right" || name == "end" {
+                    bail!("Missing argument for `{}`", command);
+                }
+                match self.command(&name)? {
+                    Some(atom) => Ok(atom.html),
+                    None => bail!("Missing argument for `{}`", command),
+                }
+            }
+            Some(c) => {
+                self.pos += 1;
+                Ok(if c.is_ascii_digit() {
+                    self.number(&c.to_string()).html
+                } else if c.is_alphabetic() {
+                    self.identifier(c).html
+                } else {
+                    operator(&c.to_string())
+                })
+            }
+        }
+    }
+
+    fn attach_scripts(&mut self, atoms: &mut Vec<Atom>, first: char) -> Result<()> {
+        let base = atoms.pop().unwrap_or_else(|| Atom::new("<mrow></mrow>".into()));
+        let first_script = self.parse_argument(&first.to_string())?;
+        self.skip_whitespace();
+        let second_script = match self.peek() {
+            Some(c) if c == first => {
+                bail!("Double {}script", if c == '^' { "super" } else { "sub" })
+            }
+            Some(c @ ('^' | '_')) => {
+                self.pos += 1;
+                Some(self.parse_argument(&c.to_string())?)
+            }
+            _ => None,
+        };
+        let (sub, sup) = if first == '_' {
+            (Some(first_script), second_script)
+        } else {
+            (second_script, Some(first_script))
+        };
+
+        let under_over = match base.limits {
+            Limits::Never => false,
+            Limits::Display => self.display,
+            Limits::Always => true,
+        };
+        let (sub_tag, sup_tag, both_tag) = if under_over {
+            ("munder", "mover", "munderover")
+        } else {
+            ("msub", "msup", "msubsup")
+        };
+        let html = match (sub, sup) {
+            (Some(sub), Some(sup)) => {
+                format!("<{both_tag}>{}{}{}</{both_tag}>", base.html, sub, sup)
+            }
+            (Some(sub), None) => format!("<{sub_tag}>{}{}</{sub_tag}>", base.html, sub),
+            (None, Some(sup)) => format!("<{sup_tag}>{}{}</{sup_tag}>", base.html, sup),
+            (None, None) => unreachable!(),
+        };
+        atoms.push(Atom { html, limits: Limits::Never });
+        Ok(())
+    }
+
+    fn read_command_name(&mut self) -> Result<String> {
+        let mut name = String::new();
+        match self.peek() {
+            None => bail!("Missing command name after `\\`"),
+            Some(c) if c.is_ascii_alphabetic() => {
+                while let Some(c) = self.peek() {
+                    if !c.is_ascii_alphabetic() {
+                        break;
+                    }
+                    name.push(c);
+                    self.pos += 1;
+                }
+                if name == "operatorname" && self.peek() == Some('*') {
+                    self.pos += 1;
+                    name.push('*');
+                }
+            }
+            Some(c) => {
+                self.pos += 1;
+                name.push(c);
+            }
+        }
+        Ok(name)
+    }
+
+    /// Reads the content of a `{...}` group as is, eg for `\text`
+    fn read_braced_text(&mut self) -> Result<String> {
+        self.skip_whitespace();
+        if self.peek() != Some('{') {
+            bail!("Expected `{{`");
+        }
+        self.pos += 1;
+        let mut depth = 0;
+        let mut text = String::new();
+        while let Some(c) = self.peek() {
+            self.pos += 1;
+            match c {
+                '{' => depth += 1,
+                '}' if depth == 0 => return Ok(text),
+                '}' => depth -= 1,
+                _ => (),
+            }
+            text.push(c);
+        }
+        bail!("Missing closing `}}`")
+    }
+
+    /// Reads the delimiter following `\left`, `\right`, `\big` etc
+    fn read_delimiter(&mut self) -> Result<String> {
+        self.skip_whitespace();
+        let c = match self.peek() {
+            Some(c) => c,
+            None => bail!("Missing delimiter"),
+        };
+        self.pos += 1;
+        let delimiter = match c {
+            '.' => "",
+            '(' | ')' | '[' | ']' | '|' | '/' => return Ok(c.to_string()),
+            '<' => "⟨",
+            '>' => "⟩",
+            '\\' => match self.read_command_name()?.as_str() {
+                "{" | "lbrace" => "{",
+                "}" | "rbrace" => "}",
+                "|" | "Vert" | "lVert" | "rVert" => "‖",
+                "vert" | "lvert" | "rvert" => "|",
+                "langle" => "⟨",
+                "rangle" => "⟩",
+                "lfloor" => "⌊",
+                "rfloor" => "⌋",
+                "lceil" => "⌈",
+                "rceil" => "⌉",
+                "uparrow" => "↑",
+                "downarrow" => "↓",
+                "backslash" => "\\",
+                name => bail!("Invalid delimiter `\\{}`", name),
+            },
+            c => bail!("Invalid delimiter `{}`", c),
+        };
+        Ok(delimiter.to_string())
+    }
+
+    fn identifier(&self, c: char) -> Atom {
+        let html = match self.variant {
+            Some(Variant::Normal) => {
+                format!("<mi mathvariant=\"normal\">{}</mi>", escape(&c.to_string()))
+            }
+            Some(v) => format!("<mi>{}</mi>", escape(&styled_char(c, v).to_string())),
+            None => format!("<mi>{}</mi>", escape(&c.to_string())),
+        };
+        Atom::new(html)
+    }
+
+    fn number(&self, number: &str) -> Atom {
+        let number: String = match self.variant {
+            Some(v) => number.chars().map(|c| styled_char(c, v)).collect(),
+            None => number.to_string(),
+        };
+        Atom::new(format!("<mn>{}</mn>", number))
+    }
+
+    /// Parses the argument of `\mathbf` & co with the given variant
+    fn styled_argument(&mut self, command: &str, variant: Variant) -> Result<String> {
+        let previous = self.variant.replace(variant);
+        let res = self.parse_argument(command);
+        self.variant = previous;
+        res
+    }
+
+    /// Returns `None` for commands that don't output anything, like `\displaystyle`
+    fn command(&mut self, name: &str) -> Result<Option<Atom>> {
+        if let Some(c) = greek(name) {
+            let html = if c.is_uppercase() {
+                format!("<mi mathvariant=\"normal\">{}</mi>", c)
+            } else {
+                format!("<mi>{}</mi>", c)
+            };
+            return Ok(Some(Atom::new(html)));
+        }
+        if let Some(s) = symbol_operator(name) {
+            return Ok(Some(Atom::new(operator(s))));
+        }
+        if let Some(s) = symbol_identifier(name) {
+            return Ok(Some(Atom::new(format!("<mi>{}</mi>", s))));
+        }
+        if let Some(s) = large_operator(name) {
+            let limits = if name.contains("int") { Limits::Never } else { Limits::Display };
+            return Ok(Some(Atom { html: format!("<mo>{}</mo>", s), limits }));
+        }
+        if let Some(s) = limit_function(name) {
+            return Ok(Some(Atom { html: format!("<mi>{}</mi>", s), limits: Limits::Display }));
+        }
+        if FUNCTIONS.contains(&name) {
+            return Ok(Some(Atom::new(format!("<mi>{}</mi>", name))));
+        }
+        if let Some(width) = space(name) {
+            return Ok(Some(Atom::new(format!("<mspace width=\"{}\"></mspace>", width))));
+        }
+
+        let html = match name {
+            "frac" | "dfrac" | "tfrac" | "cfrac" => {
+                let num = self.parse_argument(name)?;
+                let den = self.parse_argument(name)?;
+                format!("<mfrac>{}{}</mfrac>", num, den)
+            }
+            "binom" | "dbinom" | "tbinom" => {
+                let n = self.parse_argument(name)?;
+                let k = self.parse_argument(name)?;
+                format!(
+                    "<mrow><mo>(</mo><mfrac linethickness=\"0\">{}{}</mfrac><mo>)</mo></mrow>",
+                    n, k
+                )
+            }
+            "sqrt" => {
+                self.skip_whitespace();
+                if self.peek() == Some('[') {
+                    self.pos += 1;
+                    let (index, terminator) = self.parse_atoms(true)?;
+                    if terminator != Terminator::Bracket {
+                        bail!("Missing closing `]` for `\\sqrt`");
+                    }
+                    let radicand = self.parse_argument(name)?;
+                    format!("<mroot>{}{}</mroot>", radicand, render_row(index))
+                } else {
+                    format!("<msqrt>{}</msqrt>", self.parse_argument(name)?)
+                }
+            }
+            "text" | "textrm" | "textnormal" | "textit" | "textbf" | "mbox" | "hbox" => {
+                format!("<mtext>{}</mtext>", escape(&self.read_braced_text()?))
+            }
+            "operatorname" => format!("<mi>{}</mi>", escape(&self.read_braced_text()?)),
+            "operatorname*" => {
+                let html = format!("<mi>{}</mi>", escape(&self.read_braced_text()?));
+                return Ok(Some(Atom { html, limits: Limits::Display }));
+            }
+            "mathrm" => self.styled_argument(name, Variant::Normal)?,
+            "mathbf" => self.styled_argument(name, Variant::Bold)?,
+            "mathit" => self.styled_argument(name, Variant::Italic)?,
+            "boldsymbol" | "bm" => self.styled_argument(name, Variant::BoldItalic)?,
+            "mathbb" => self.styled_argument(name, Variant::DoubleStruck)?,
+            "mathcal" | "mathscr" => self.styled_argument(name, Variant::Script)?,
+            "mathfrak" => self.styled_argument(name, Variant::Fraktur)?,
+            "mathsf" => self.styled_argument(name, Variant::SansSerif)?,
+            "mathtt" => self.styled_argument(name, Variant::Monospace)?,
+            "underline" => {
+                let arg = self.parse_argument(name)?;
+                format!("<munder accentunder=\"true\">{}<mo stretchy=\"true\">_</mo></munder>", arg)
+            }
+            "overbrace" | "underbrace" => {
+                let arg = self.parse_argument(name)?;
+                let html = if name == "overbrace" {
+                    format!("<mover accent=\"true\">{}<mo stretchy=\"true\">⏞</mo></mover>", arg)
+                } else {
+                    format!(
+                        "<munder accentunder=\"true\">{}<mo stretchy=\"true\">⏟</mo></munder>",
+                        arg
+                    )
+                };
+                return Ok(Some(Atom { html, limits: Limits::Always }));
+            }
+            "left" => {
+                let open = self.read_delimiter()?;
+                let (atoms, terminator) = self.parse_atoms(false)?;
+                let close = match terminator {
+                    Terminator::Right(close) => close,
+                    Terminator::Eof => bail!("Missing `\\right` for `\\left`"),
+                    t => bail!("{}", unexpected(&t)),
+                };
+                let mut html = String::from("<mrow>");
+                if !open.is_empty() {
+                    html.push_str(&format!("<mo fence=\"true\">{}</mo>", escape(&open)));
+                }
+                for atom in atoms {
+                    html.push_str(&atom.html);
+                }
+                if !close.is_empty() {
+                    html.push_str(&format!("<mo fence=\"true\">{}</mo>", escape(&close)));
+                }
+                html.push_str("</mrow>");
+                html
+            }
+            "big" | "bigl" | "bigr" | "bigm" | "Big" | "Bigl" | "Bigr" | "Bigm" | "bigg"
+            | "biggl" | "biggr" | "biggm" | "Bigg" | "Biggl" | "Biggr" | "Biggm" => {
+                let size = match name.trim_end_matches(['l', 'r', 'm']) {
+                    "big" => "1.2em",
+                    "Big" => "1.623em",
+                    "bigg" => "2.047em",
+                    _ => "2.470em",
+                };
+                let delimiter = self.read_delimiter()?;
+                format!("<mo minsize=\"{size}\" maxsize=\"{size}\">{}</mo>", escape(&delimiter))
+            }
+            "begin" => {
+                let env = self.read_braced_text()?;
+                self.environment(&env)?
+            }
+            "displaystyle" | "textstyle" | "scriptstyle" | "limits" | "nolimits" | "nonumber"
+            | "notag" => return Ok(None),
+            _ => {
+                if let Some(accent) = accent(name) {
+                    let arg = self.parse_argument(name)?;
+                    let stretchy = if name.starts_with("wide") || name == "overline" {
+                        " stretchy=\"true\""
+                    } else {
+                        ""
+                    };
+                    format!("<mover accent=\"true\">{}<mo{}>{}</mo></mover>", arg, stretchy, accent)
+                } else {
+                    bail!("Unknown command `\\{}`", name)
+                }
+            }
+        };
+        Ok(Some(Atom::new(html)))
+    }
+
+    fn environment(&mut self, env: &str) -> Result<String> {
+        let (open, close, align) = match env {
+            "matrix" | "smallmatrix" | "array" => ("", "", None),
+            "pmatrix" => ("(", ")", None),
+            "bmatrix" => ("[", "]", None),
+            "Bmatrix" => ("{", "}", None),
+            "vmatrix" => ("|", "|", None),
+            "Vmatrix" => ("‖", "‖", None),
+            "cases" => ("{", "", Some("left left")),
+            "aligned" | "align" | "align*" | "split" | "alignat" | "alignat*" => {
+                ("", "", Some("right left"))
+            }
+            "gathered" | "gather" | "gather*" => ("", "", None),
+            _ => bail!("Unknown environment `{}`", env),
+        };
+        if env == "array" || env.starts_with("alignat") {
+            // We don't do anything with the column specification/count
+            self.read_braced_text()?;
+        }
+
+        let mut rows = Vec::new();
+        let mut cells = Vec::new();
+        loop {
+            let (atoms, terminator) = self.parse_atoms(false)?;
+            cells.push(render_row(atoms));
+            match terminator {
+                Terminator::Ampersand => (),
+                Terminator::NewRow => rows.push(std::mem::take(&mut cells)),
+                Terminator::End(name) if name == env => {
+                    rows.push(std::mem::take(&mut cells));
+                    break;
+                }
+                Terminator::End(name) => {
+                    bail!("`\\begin{{{}}}` ended by `\\end{{{}}}`", env, name)
+                }
+                Terminator::Eof => bail!("Missing `\\end{{{}}}`", env),
+                t => bail!("{}", unexpected(&t)),
+            }
+        }
+        // A trailing `\\` doesn't create an empty row
+        if rows.len() > 1 && rows.last().is_some_and(|r| r.len() == 1 && r[0] == "<mrow></mrow>") {
+            rows.pop();
+        }
+
+        let mut html = String::from("<mrow>");
+        if !open.is_empty() {
+            html.push_str(&format!("<mo fence=\"true\">{}</mo>", open));
+        }
+        match align {
+            Some(align) => html.push_str(&format!("<mtable columnalign=\"{}\">", align)),
+            None => html.push_str("<mtable>"),
+        }
+        for row in rows {
+            html.push_str("<mtr>");
+            for cell in row {
+                html.push_str(&format!("<mtd>{}</mtd>", cell));
+            }
+            html.push_str("</mtr>");
+        }
+        html.push_str("</mtable>");
+        if !close.is_empty() {
+            html.push_str(&format!("<mo fence=\"true\">{}</mo>", close));
+        }
+        html.push_str("</mrow>");
+        Ok(html)
+    }
+}
+
+fn unexpected(terminator: &Terminator) -> String {
+    match terminator {
+        Terminator::Brace => "Unexpected `}`".to_string(),
+        Terminator::Bracket => "Unexpected `]`".to_string(),
+        Terminator::Ampersand => "`&` can only be used inside an environment".to_string(),
+        Terminator::NewRow => "Unexpected `\\\\`".to_string(),
+        Terminator::Right(_) => "`\\right` without a matching `\\left`".to_string(),
+        Terminator::End(env) => format!("`\\end{{{}}}` without a matching `\\begin`", env),
+        Terminator::Eof => "Unexpected end of expression".to_string(),
+    }
+}
+
+/// A group of atoms needs to be a single element when used as an argument
+fn render_row(atoms: Vec<Atom>) -> String {
+    if atoms.len() == 1 {
+        return atoms.into_iter().next().unwrap().html;
+    }
+    let mut html = String::from("<mrow>");
+    for atom in atoms {
+        html.push_str(&atom.html);
+    }
+    html.push_str("</mrow>");
+    html
+}
+
+fn operator(s: &str) -> String {
+    format!("<mo>{}</mo>", escape(s))
+}
+
+fn escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    escape_into(&mut out, s);
+    out
+}
+
+fn escape_into(out: &mut String, s: &str) {
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            _ => out.push(c),
+        }
+    }
+}
+
+/// Maps ASCII letters and digits to their Mathematical Alphanumeric Symbols equivalent
+fn styled_char(c: char, variant: Variant) -> char {
+    // Some letters were encoded before that block and are "holes" in it
+    let hole = match (variant, c) {
+        (Variant::Italic, 'h') => Some('ℎ'),
+        (Variant::Script, 'B') => Some('ℬ'),
+        (Variant::Script, 'E') => Some('ℰ'),
+        (Variant::Script, 'F') => Some('ℱ'),
+        (Variant::Script, 'H') => Some('ℋ'),
+        (Variant::Script, 'I') => Some('ℐ'),
+        (Variant::Script, 'L') => Some('ℒ'),
+        (Variant::Script, 'M') => Some('ℳ'),
+        (Variant::Script, 'R') => Some('ℛ'),
+        (Variant::Script, 'e') => Some('ℯ'),
+        (Variant::Script, 'g') => Some('ℊ'),
+        (Variant::Script, 'o') => Some('ℴ'),
+        (Variant::Fraktur, 'C') => Some('ℭ'),
+        (Variant::Fraktur, 'H') => Some('ℌ'),
+        (Variant::Fraktur, 'I') => Some('ℑ'),
+        (Variant::Fraktur, 'R') => Some('ℜ'),
+        (Variant::Fraktur, 'Z') => Some('ℨ'),
+        (Variant::DoubleStruck, 'C') => Some('ℂ'),
+        (Variant::DoubleStruck, 'H') => Some('ℍ'),
+        (Variant::DoubleStruck, 'N') => Some('ℕ'),
+        (Variant::DoubleStruck, 'P') => Some('ℙ'),
+        (Variant::DoubleStruck, 'Q') => Some('ℚ'),
+        (Variant::DoubleStruck, 'R') => Some('ℝ'),
+        (Variant::DoubleStruck, 'Z') => Some('ℤ'),
+        _ => None,
+    };
+    if let Some(h) = hole {
+        return h;
+    }
+
+    let (upper, lower, digit) = match variant {
+        Variant::Normal => return c,
+        Variant::Bold => (0x1D400, 0x1D41A, Some(0x1D7CE)),
+        Variant::Italic => (0x1D434, 0x1D44E, None),
+        Variant::BoldItalic => (0x1D468, 0x1D482, None),
+        Variant::Script => (0x1D49C, 0x1D4B6, None),
+        Variant::Fraktur => (0x1D504, 0x1D51E, None),
+        Variant::DoubleStruck => (0x1D538, 0x1D552, Some(0x1D7D8)),
+        Variant::SansSerif => (0x1D5A0, 0x1D5BA, Some(0x1D7E2)),
+        Variant::Monospace => (0x1D670, 0x1D68A, Some(0x1D7F6)),
+    };
+    let code = match c {
+        'A'..='Z' => upper + (c as u32 - 'A' as u32),
+        'a'..='z' => lower + (c as u32 - 'a' as u32),
+        '0'..='9' => match digit {
+            Some(d) => d + (c as u32 - '0' as u32),
+            None => return c,
+        },
+        _ => return c,
+    };
+    char::from_u32(code).unwrap_or(c)
+}
+
+fn greek(name: &str) -> Option<char> {
+    Some(match name {
+        "alpha" => 'α',
+        "beta" => 'β',
+        "gamma" => 'γ',
+        "delta" => 'δ',
+        "epsilon" => 'ϵ',
+        "varepsilon" => 'ε',
+        "zeta" => 'ζ',
+        "eta" => 'η',
+        "theta" => 'θ',
+        "vartheta" => 'ϑ',
+        "iota" => 'ι',
+        "kappa" => 'κ',
+        "lambda" => 'λ',
+        "mu" => 'μ',
+        "nu" => 'ν',
+        "xi" => 'ξ',
+        "omicron" => 'ο',
+        "pi" => 'π',
+        "varpi" => 'ϖ',
+        "rho" => 'ρ',
+        "varrho" => 'ϱ',
+        "sigma" => 'σ',
+        "varsigma" => 'ς',
+        "tau" => 'τ',
+        "upsilon" => 'υ',
+        "phi" => 'ϕ',
+        "varphi" => 'φ',
+        "chi" => 'χ',
+        "psi" => 'ψ',
+        "omega" => 'ω',
+        "Gamma" => 'Γ',
+        "Delta" => 'Δ',
+        "Theta" => 'Θ',
+        "Lambda" => 'Λ',
+        "Xi" => 'Ξ',
+        "Pi" => 'Π',
+        "Sigma" => 'Σ',
+        "Upsilon" => 'Υ',
+        "Phi" => 'Φ',
+        "Psi" => 'Ψ',
+        "Omega" => 'Ω',
+        _ => return None,
+    })
+}
+
+fn symbol_operator(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "pm" => "±",
+        "mp" => "∓",
+        "times" => "×",
+        "div" => "÷",
+        "cdot" => "⋅",
+        "ast" => "∗",
+        "star" => "⋆",
+        "circ" => "∘",
+        "bullet" => "∙",
+        "oplus" => "⊕",
+        "otimes" => "⊗",
+        "leq" | "le" => "≤",
+        "geq" | "ge" => "≥",
+        "neq" | "ne" => "≠",
+        "ll" => "≪",
+        "gg" => "≫",
+        "approx" => "≈",
+        "equiv" => "≡",
+        "sim" => "∼",
+        "simeq" => "≃",
+        "cong" => "≅",
+        "propto" => "∝",
+        "in" => "∈",
+        "notin" => "∉",
+        "ni" => "∋",
+        "subset" => "⊂",
+        "subseteq" => "⊆",
+        "supset" => "⊃",
+        "supseteq" => "⊇",
+        "cup" => "∪",
+        "cap" => "∩",
+        "setminus" => "∖",
+        "to" | "rightarrow" => "→",
+        "gets" | "leftarrow" => "←",
+        "leftrightarrow" => "↔",
+        "Rightarrow" => "⇒",
+        "Leftarrow" => "⇐",
+        "Leftrightarrow" => "⇔",
+        "implies" => "⟹",
+        "iff" => "⟺",
+        "mapsto" => "↦",
+        "uparrow" => "↑",
+        "downarrow" => "↓",
+        "forall" => "∀",
+        "exists" => "∃",
+        "neg" | "lnot" => "¬",
+        "land" | "wedge" => "∧",
+        "lor" | "vee" => "∨",
+        "perp" => "⊥",
+        "parallel" => "∥",
+        "mid" => "∣",
+        "ldots" | "dots" => "…",
+        "cdots" => "⋯",
+        "vdots" => "⋮",
+        "ddots" => "⋱",
+        "langle" => "⟨",
+        "rangle" => "⟩",
+        "lfloor" => "⌊",
+        "rfloor" => "⌋",
+        "lceil" => "⌈",
+        "rceil" => "⌉",
+        "vert" | "lvert" | "rvert" => "|",
+        "Vert" | "lVert" | "rVert" | "|" => "‖",
+        "colon" => ":",
+        "{" | "lbrace" => "{",
+        "}" | "rbrace" => "}",
+        "backslash" => "\\",
+        "%" => "%",
+        "$" => "$",
+        "&" => "&",
+        "#" => "#",
+        "_" => "_",
+        _ => return None,
+    })
+}
+
+fn symbol_identifier(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "infty" => "∞",
+        "partial" => "∂",
+        "nabla" => "∇",
+        "emptyset" | "varnothing" => "∅",
+        "ell" => "ℓ",
+        "hbar" => "ℏ",
+        "aleph" => "ℵ",
+        "Re" => "ℜ",
+        "Im" => "ℑ",
+        "angle" => "∠",
+        "top" => "⊤",
+        "bot" => "⊥",
+        "prime" => "′",
+        _ => return None,
+    })
+}
+
+fn large_operator(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "sum" => "∑",
+        "prod" => "∏",
+        "coprod" => "∐",
+        "bigcup" => "⋃",
+        "bigcap" => "⋂",
+        "bigoplus" => "⨁",
+        "bigotimes" => "⨂",
+        "bigvee" => "⋁",
+        "bigwedge" => "⋀",
+        "int" => "∫",
+        "iint" => "∬",
+        "iiint" => "∭",
+        "oint" => "∮",
+        _ => return None,
+    })
+}
+
+fn limit_function(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "lim" => "lim",
+        "limsup" => "lim sup",
+        "liminf" => "lim inf",
+        "max" => "max",
+        "min" => "min",
+        "sup" => "sup",
+        "inf" => "inf",
+        "det" => "det",
+        "gcd" => "gcd",
+        "Pr" => "Pr",
+        "argmax" => "arg max",
+        "argmin" => "arg min",
+        _ => return None,
+    })
+}
+
+const FUNCTIONS: &[&str] = &[
+    "sin", "cos", "tan", "cot", "sec", "csc", "arcsin", "arccos", "arctan", "sinh", "cosh", "tanh",
+    "coth", "log", "ln", "lg", "exp", "dim", "ker", "hom", "arg", "deg", "mod", "bmod",
+];
+
+fn space(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "," | "thinspace" => "0.1667em",
+        ":" | ">" | "medspace" => "0.2222em",
+        ";" | "thickspace" => "0.2778em",
+        "!" | "negthinspace" => "-0.1667em",
+        " " => "0.25em",
+        "quad" => "1em",
+        "qquad" => "2em",
+        _ => return None,
+    })
+}
+
+fn accent(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "hat" | "widehat" => "^",
+        "bar" => "¯",
+        "overline" => "‾",
+        "vec" => "→",
+        "dot" => "˙",
+        "ddot" => "¨",
+        "tilde" | "widetilde" => "~",
+        "acute" => "´",
+        "grave" => "`",
+        "breve" => "˘",
+        "check" => "ˇ",
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn body(source: &str) -> String {
+        let html = render_math(source, false).unwrap();
+        let start = "<math><semantics>".len();
+        let end = html.find("<annotation").unwrap();
+        html[start..end].to_string()
+    }
+
+    #[test]
+    fn can_render_basic_expressions() {
+        assert_eq!(body("x"), "<mi>x</mi>");
+        assert_eq!(body("3.14"), "<mn>3.14</mn>");
+        assert_eq!(body("a+b"), "<mrow><mi>a</mi><mo>+</mo><mi>b</mi></mrow>");
+        assert_eq!(body("a-b"), "<mrow><mi>a</mi><mo>−</mo><mi>b</mi></mrow>");
+        assert_eq!(body("a < b"), "<mrow><mi>a</mi><mo>&lt;</mo><mi>b</mi></mrow>");
+        assert_eq!(
+            body("\\alpha \\Gamma"),
+            "<mrow><mi>α</mi><mi mathvariant=\"normal\">Γ</mi></mrow>"
+        );
+    }
+
+    #[test]
+    fn can_render_scripts() {
+        assert_eq!(body("x^2"), "<msup><mi>x</mi><mn>2</mn></msup>");
+        assert_eq!(
+            body("x_i^{n+1}"),
+            "<msubsup><mi>x</mi><mi>i</mi><mrow><mi>n</mi><mo>+</mo><mn>1</mn></mrow></msubsup>"
+        );
+        assert_eq!(body("f'"), "<msup><mi>f</mi><mo>′</mo></msup>");
+        assert_eq!(
+            body("\\sum_{i=0}^n i"),
+            "<mrow><msubsup><mo>∑</mo><mrow><mi>i</mi><mo>=</mo><mn>0</mn></mrow><mi>n</mi></msubsup><mi>i</mi></mrow>"
+        );
+        let display = render_math("\\sum_{i=0}^n i", true).unwrap();
+        assert!(
+            display.starts_with("<math display=\"block\"><semantics><mrow><munderover><mo>∑</mo>")
+        );
+    }
+
+    #[test]
+    fn can_render_commands() {
+        assert_eq!(body("\\frac{1}{2}"), "<mfrac><mn>1</mn><mn>2</mn></mfrac>");
+        assert_eq!(body("\\sqrt[3]{x}"), "<mroot><mi>x</mi><mn>3</mn></mroot>");
+        assert_eq!(body("\\sqrt x"), "<msqrt><mi>x</mi></msqrt>");
+        assert_eq!(body("\\text{if } x"), "<mrow><mtext>if </mtext><mi>x</mi></mrow>");
+        assert_eq!(body("\\mathbb{R}"), "<mi>ℝ</mi>");
+        assert_eq!(body("\\mathbf{v}"), "<mi>𝐯</mi>");
+        assert_eq!(body("\\mathrm{d}"), "<mi mathvariant=\"normal\">d</mi>");
+        assert_eq!(body("\\hat{x}"), "<mover accent=\"true\"><mi>x</mi><mo>^</mo></mover>");
+        assert_eq!(
+            body("\\left( x \\right)"),
+            "<mrow><mo fence=\"true\">(</mo><mi>x</mi><mo fence=\"true\">)</mo></mrow>"
+        );
+        assert_eq!(body("\\sin x"), "<mrow><mi>sin</mi><mi>x</mi></mrow>");
+    }
+
+    #[test]
+    fn can_render_environments() {
+        assert_eq!(
+            body("\\begin{pmatrix} a & b \\\\ c & d \\end{pmatrix}"),
+            "<mrow><mo fence=\"true\">(</mo><mtable><mtr><mtd><mi>a</mi></mtd><mtd><mi>b</mi></mtd></mtr><mtr><mtd><mi>c</mi></mtd><mtd><mi>d</mi></mtd></mtr></mtable><mo fence=\"true\">)</mo></mrow>"
+        );
+        assert_eq!(
+            body("\\begin{cases} 1 & x > 0 \\\\ 0 & \\text{otherwise} \\\\ \\end{cases}")
+                .matches("<mtr>")
+                .count(),
+            2
+        );
+    }
+
+    #[test]
+    fn escapes_the_annotation() {
+        let html = render_math("a<b", false).unwrap();
+        assert!(html.contains("<annotation encoding=\"application/x-tex\">a&lt;b</annotation>"));
+    }
+
+    #[test]
+    fn errors_on_invalid_expressions() {
+        let cases = [
+            ("\\frac{1}", "Missing argument for `frac`"),
+            ("x^", "Missing argument for `^`"),
+            ("x^1^2", "Double superscript"),
+            ("{x", "Missing closing `}`"),
+            ("x}", "Unexpected `}`"),
+            ("\\foo", "Unknown command `\\foo`"),
+            ("\\left( x", "Missing `\\right` for `\\left`"),
+            ("x \\right)", "`\\right` without a matching `\\left`"),
+            ("\\begin{matrix} a", "Missing `\\end{matrix}`"),
+            ("\\begin{foo} a \\end{foo}", "Unknown environment `foo`"),
+            ("a & b", "`&` can only be used inside an environment"),
+        ];
+        for (source, expected) in cases {
+            let err = render_math(source, false).unwrap_err();
+            assert_eq!(err.to_string(), expected, "for {source}");
+        }
+    }
+}
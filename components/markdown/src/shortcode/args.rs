@@ -0,0 +1,123 @@
+//! Checks the arguments of shortcode calls against the arguments the shortcodes declare.
+use errors::{Result, bail};
+use tera::Value;
+use utils::strings::closest_match;
+use utils::templates::ShortcodeSchema;
+
+/// Errors on unknown, missing or mistyped arguments and fills in the defaults of the
+/// arguments not given. `location` is where the shortcode is called, eg ` in "blog/hello.md"`.
+pub(crate) fn check_args(
+    name: &str,
+    args: &mut Value,
+    schema: &ShortcodeSchema,
+    location: &str,
+) -> Result<()> {
+    let Value::Object(args) = args else {
+        return Ok(());
+    };
+
+    for (key, value) in args.iter() {
+        let Some(arg) = schema.args.get(key) else {
+            let hint = match closest_match(key, schema.args.keys().map(String::as_str)) {
+                Some(close) => format!(", did you mean `{close}`?"),
+                None => String::new(),
+            };
+            bail!(
+                "Invalid call to the shortcode `{name}`{location}: unknown argument `{key}`{hint}"
+            );
+        };
+        if !arg.kind.matches(value) {
+            bail!(
+                "Invalid call to the shortcode `{name}`{location}: the argument `{key}` should be {}, got `{value}`",
+                arg.kind
+            );
+        }
+    }
+
+    for (key, arg) in &schema.args {
+        if args.contains_key(key) {
+            continue;
+        }
+        if let Some(default) = &arg.default {
+            args.insert(key.clone(), default.clone());
+        } else if arg.required {
+            bail!(
+                "Invalid call to the shortcode `{name}`{location}: missing required argument `{key}`"
+            );
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tera::{Map, to_value};
+
+    fn args(pairs: &[(&str, Value)]) -> Value {
+        Value::Object(pairs.iter().map(|(k, v)| (k.to_string(), v.clone())).collect::<Map<_, _>>())
+    }
+
+    fn schema() -> ShortcodeSchema {
+        ShortcodeSchema::from_template_source(
+            r#"{#
++++
+[args.id]
+type = "string"
+required = true
+
+[args.width]
+type = "integer"
+default = 560
+
+[args.ratio]
+type = "float"
++++
+#}"#,
+        )
+        .unwrap()
+        .unwrap()
+    }
+
+    #[test]
+    fn can_fill_defaults() {
+        let mut value = args(&[("id", to_value("abc").unwrap()), ("ratio", to_value(2).unwrap())]);
+        check_args("youtube", &mut value, &schema(), "").unwrap();
+        assert_eq!(
+            value,
+            args(&[
+                ("id", to_value("abc").unwrap()),
+                ("ratio", to_value(2).unwrap()),
+                ("width", to_value(560).unwrap()),
+            ])
+        );
+    }
+
+    #[test]
+    fn errors_on_invalid_args() {
+        let cases = [
+            (
+                args(&[("id", to_value("abc").unwrap()), ("widht", to_value(100).unwrap())]),
+                "Invalid call to the shortcode `youtube` in \"my_page.md\": unknown argument `widht`, did you mean `width`?",
+            ),
+            (
+                args(&[("id", to_value("abc").unwrap()), ("autoplay", to_value(true).unwrap())]),
+                "Invalid call to the shortcode `youtube` in \"my_page.md\": unknown argument `autoplay`",
+            ),
+            (
+                args(&[("width", to_value(100).unwrap())]),
+                "Invalid call to the shortcode `youtube` in \"my_page.md\": missing required argument `id`",
+            ),
+            (
+                args(&[("id", to_value(1).unwrap())]),
+                "Invalid call to the shortcode `youtube` in \"my_page.md\": the argument `id` should be a string, got `1`",
+            ),
+        ];
+        for (mut value, error) in cases {
+            let err =
+                check_args("youtube", &mut value, &schema(), " in \"my_page.md\"").unwrap_err();
+            assert_eq!(err.to_string(), error);
+        }
+    }
+}
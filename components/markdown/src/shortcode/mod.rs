@@ -3,20 +3,35 @@ use std::collections::HashMap;
 use errors::Result;
 use utils::templates::{ShortcodeDefinition, ShortcodeFileType, ShortcodeInvocationCounter};
 
+mod args;
 mod parser;
 
 pub(crate) use parser::{SHORTCODE_PLACEHOLDER, Shortcode, parse_for_shortcodes};
 
-/// Extracts the shortcodes present in the source, check if we know them and errors otherwise
+/// Extracts the shortcodes present in the source, check if we know them and errors otherwise.
+/// The arguments of the calls are checked against the declarations of the shortcodes, with
+/// errors mentioning the `page` the shortcodes are in if given. References to values like
+/// `page.title` in the arguments are resolved against `tera_context`.
 pub fn extract_shortcodes(
     source: &str,
     definitions: &HashMap<String, ShortcodeDefinition>,
+    page: Option<&str>,
+    tera_context: &tera::Context,
 ) -> Result<(String, Vec<Shortcode>)> {
-    let (out, mut shortcodes) =
-        parse_for_shortcodes(source, &mut ShortcodeInvocationCounter::new())?;
-
+    let (out, mut shortcodes) = parse_for_shortcodes(
+        source,
+        &mut ShortcodeInvocationCounter::new(),
+        tera_context,
+        definitions,
+    )?;
+
+    let location = match page {
+        Some(p) => format!(" in {p:?}"),
+        None => String::new(),
+    };
     for sc in &mut shortcodes {
         sc.fill_tera_name(definitions)?;
+        sc.check_args(definitions, &location)?;
     }
 
     Ok((out, shortcodes))
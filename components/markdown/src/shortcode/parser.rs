@@ -45,6 +45,22 @@ impl Shortcode {
         Ok(())
     }
 
+    /// Checks the arguments of self and all of self.inner against the declarations of the
+    /// shortcodes, filling in their defaults.
+    pub fn check_args(
+        &mut self,
+        definitions: &HashMap<String, ShortcodeDefinition>,
+        location: &str,
+    ) -> Result<()> {
+        if let Some(schema) = definitions.get(&self.name).and_then(|def| def.schema.as_ref()) {
+            super::args::check_args(&self.name, &mut self.args, schema, location)?;
+        }
+        for inner_sc in self.inner.iter_mut() {
+            inner_sc.check_args(definitions, location)?;
+        }
+        Ok(())
+    }
+
     pub fn file_type(&self) -> ShortcodeFileType {
         if self.tera_name.ends_with("md") {
             ShortcodeFileType::Markdown
@@ -133,48 +149,86 @@ fn replace_string_markers(input: &str) -> String {
     }
 }
 
-fn parse_kwarg_value(pair: Pair<Rule>) -> Value {
-    let mut val = None;
-    for p in pair.into_inner() {
-        match p.as_rule() {
-            Rule::boolean => match p.as_str() {
-                "true" => val = Some(Value::Bool(true)),
-                "false" => val = Some(Value::Bool(false)),
-                _ => unreachable!(),
-            },
-            Rule::string => val = Some(Value::String(replace_string_markers(p.as_str()))),
-            Rule::float => {
-                val = Some(to_value(p.as_str().parse::<f64>().unwrap()).unwrap());
-            }
-            Rule::int => {
-                val = Some(to_value(p.as_str().parse::<i64>().unwrap()).unwrap());
+/// Looks up a dotted path like `page.extra.cover` in the context the shortcodes are rendered with
+fn resolve_reference(reference: &str, context: &Context) -> Result<Value> {
+    let mut parts = reference.split('.');
+    let mut value = context.get(parts.next().unwrap());
+    for part in parts {
+        value = value.and_then(|v| match v {
+            Value::Array(items) => part.parse::<usize>().ok().and_then(|i| items.get(i)),
+            _ => v.get(part),
+        });
+    }
+
+    match value {
+        Some(v) => Ok(v.clone()),
+        None => bail!(
+            "Found a reference to `{}` in the arguments of a shortcode but there is no such value. Make sure it's not a typo and that it exists, eg in the front matter for `page.extra.*`.",
+            reference
+        ),
+    }
+}
+
+fn parse_kwarg_value(pair: Pair<Rule>, context: &Context) -> Result<Value> {
+    let val = match pair.as_rule() {
+        Rule::value | Rule::literal => {
+            return parse_kwarg_value(pair.into_inner().next().unwrap(), context);
+        }
+        Rule::boolean => match pair.as_str() {
+            "true" => Value::Bool(true),
+            "false" => Value::Bool(false),
+            _ => unreachable!(),
+        },
+        Rule::string => Value::String(replace_string_markers(pair.as_str())),
+        Rule::float => to_value(pair.as_str().parse::<f64>().unwrap()).unwrap(),
+        Rule::int => to_value(pair.as_str().parse::<i64>().unwrap()).unwrap(),
+        Rule::array => {
+            let mut vals = vec![];
+            for p in pair.into_inner() {
+                vals.push(parse_kwarg_value(p, context)?);
             }
-            Rule::array => {
-                let mut vals = vec![];
-                for p2 in p.into_inner() {
-                    match p2.as_rule() {
-                        Rule::literal => vals.push(parse_kwarg_value(p2)),
-                        _ => unreachable!("Got something other than literal in an array: {:?}", p2),
-                    }
-                }
-                val = Some(Value::Array(vals));
+            Value::Array(vals)
+        }
+        Rule::inline_table => {
+            let mut table = Map::new();
+            for entry in pair.into_inner() {
+                let mut inner = entry.into_inner();
+                let key = inner.next().unwrap();
+                let key = match key.as_rule() {
+                    Rule::string => replace_string_markers(key.as_str()),
+                    _ => key.as_str().to_string(),
+                };
+                table.insert(key, parse_kwarg_value(inner.next().unwrap(), context)?);
             }
-            _ => unreachable!("Unknown literal: {:?}", p),
-        };
-    }
+            Value::Object(table)
+        }
+        Rule::reference => resolve_reference(pair.as_str(), context)?,
+        _ => unreachable!("Unknown value: {:?}", pair),
+    };
 
-    val.unwrap()
+    Ok(val)
 }
 
-/// Returns (shortcode_name, kwargs)
-fn parse_shortcode_call(pair: Pair<Rule>) -> (String, Value) {
+/// Returns (shortcode_name, kwargs), or `None` if this is a call to an unknown function using
+/// references like `page.title`, which is Tera code to leave as is rather than a shortcode
+fn parse_shortcode_call(
+    pair: Pair<Rule>,
+    context: &Context,
+    definitions: &HashMap<String, ShortcodeDefinition>,
+) -> Result<Option<(String, Value)>> {
+    let has_references =
+        pair.clone().into_inner().flatten().any(|p| p.as_rule() == Rule::reference);
     let mut name = None;
     let mut args = Map::new();
 
     for p in pair.into_inner() {
         match p.as_rule() {
             Rule::ident => {
-                name = Some(p.as_span().as_str().to_string());
+                let ident = p.as_span().as_str();
+                if has_references && !definitions.contains_key(ident) {
+                    return Ok(None);
+                }
+                name = Some(ident.to_string());
             }
             Rule::kwarg => {
                 let mut arg_name = None;
@@ -184,8 +238,8 @@ fn parse_shortcode_call(pair: Pair<Rule>) -> (String, Value) {
                         Rule::ident => {
                             arg_name = Some(p2.as_span().as_str().to_string());
                         }
-                        Rule::literal => {
-                            arg_val = Some(parse_kwarg_value(p2));
+                        Rule::value => {
+                            arg_val = Some(parse_kwarg_value(p2, context)?);
                         }
                         _ => unreachable!("Got something unexpected in a kwarg: {:?}", p2),
                     }
@@ -196,12 +250,17 @@ fn parse_shortcode_call(pair: Pair<Rule>) -> (String, Value) {
             _ => unreachable!("Got something unexpected in a shortcode: {:?}", p),
         }
     }
-    (name.unwrap(), Value::Object(args))
+    Ok(Some((name.unwrap(), Value::Object(args))))
 }
 
+/// Extracts the shortcodes of the content, resolving the references in their arguments, like
+/// `page.title`, against the given context. Calls with references are only shortcodes if they
+/// are in the definitions, otherwise they are left as text.
 pub fn parse_for_shortcodes(
     content: &str,
     invocation_counter: &mut ShortcodeInvocationCounter,
+    context: &Context,
+    definitions: &HashMap<String, ShortcodeDefinition>,
 ) -> Result<(String, Vec<Shortcode>)> {
     let mut shortcodes = Vec::new();
     let mut output = String::with_capacity(content.len());
@@ -214,7 +273,12 @@ pub fn parse_for_shortcodes(
                 Rule::float => "a float".to_string(),
                 Rule::string => "a string".to_string(),
                 Rule::literal => "a literal (int, float, string, bool)".to_string(),
+                Rule::value => "a literal or a reference like `page.title`".to_string(),
+                Rule::reference => "a reference like `page.title`".to_string(),
+                Rule::reference_root => "one of `page`, `section`, `config` or `lang`".to_string(),
                 Rule::array => "an array".to_string(),
+                Rule::inline_table => "an inline table".to_string(),
+                Rule::table_entry => "an entry of an inline table".to_string(),
                 Rule::kwarg => "a keyword argument".to_string(),
                 Rule::ident => "an identifier".to_string(),
                 Rule::inline_shortcode => "an inline shortcode".to_string(),
@@ -250,7 +314,11 @@ pub fn parse_for_shortcodes(
             Rule::text => output.push_str(p.as_span().as_str()),
             Rule::inline_shortcode => {
                 let start = output.len();
-                let (name, args) = parse_shortcode_call(p);
+                let text = p.as_span().as_str();
+                let Some((name, args)) = parse_shortcode_call(p, context, definitions)? else {
+                    output.push_str(text);
+                    continue;
+                };
                 let nth = invocation_counter.get(&name);
                 shortcodes.push(Shortcode {
                     name,
@@ -265,14 +333,22 @@ pub fn parse_for_shortcodes(
             }
             Rule::shortcode_with_body => {
                 let start = output.len();
+                let text = p.as_span().as_str();
                 let mut inner = p.into_inner();
                 // 3 items in inner: call, body, end
                 // we don't care about the closing tag
-                let (name, args) = parse_shortcode_call(inner.next().unwrap());
+                let Some((name, args)) =
+                    parse_shortcode_call(inner.next().unwrap(), context, definitions)?
+                else {
+                    output.push_str(text);
+                    continue;
+                };
                 let nth = invocation_counter.get(&name);
                 let (body, inner) = parse_for_shortcodes(
                     inner.next().unwrap().as_span().as_str().trim(),
                     invocation_counter,
+                    context,
+                    definitions,
                 )?;
                 shortcodes.push(Shortcode {
                     name,
@@ -347,6 +423,8 @@ mod tests {
             "{{ youtube(id=1, autoplay=true, url='hey') }}",
             "{{ youtube(id=1, \nautoplay=true, url='hey', array=[]) }}",
             "{{ youtube(id=1, \nautoplay=true, url='hey', multi_aray=[[]]) }}",
+            "{{ figure(opts={a=1, 'b c'=\"x\", nested={d=[page.title]}}, empty={}) }}",
+            "{{ figure(title=page.title, img=page.extra.images.0, lang=lang) }}",
         ];
         for i in inputs {
             assert_lex_rule!(Rule::inline_shortcode, i);
@@ -455,6 +533,8 @@ mod tests {
         let (out, shortcodes) = parse_for_shortcodes(
             "Inline shortcode: {{ hello(string='hey', int=1, float=2.1, bool=true, array=[true, false]) }} hey",
             &mut ShortcodeInvocationCounter::new(),
+            &Context::new(),
+            &HashMap::new(),
         )
         .unwrap();
         assert_eq!(out, format!("Inline shortcode: {} hey", SHORTCODE_PLACEHOLDER));
@@ -478,6 +558,8 @@ mod tests {
         let (out, shortcodes) = parse_for_shortcodes(
             "Hello World {{/* youtube() */}} hey",
             &mut ShortcodeInvocationCounter::new(),
+            &Context::new(),
+            &HashMap::new(),
         )
         .unwrap();
         assert_eq!(out, "Hello World {{ youtube() }} hey");
@@ -488,7 +570,9 @@ mod tests {
     fn can_extract_shortcode_with_body() {
         let (out, shortcodes) = parse_for_shortcodes(
             "Body shortcode\n {% quote(author='Bobby', array=[[true]]) %}DROP TABLES;{% end %} \n hey",
-            &mut ShortcodeInvocationCounter::new()
+            &mut ShortcodeInvocationCounter::new(),
+            &Context::new(),
+            &HashMap::new(),
         )
         .unwrap();
         assert_eq!(out, format!("Body shortcode\n {} \n hey", SHORTCODE_PLACEHOLDER));
@@ -510,6 +594,8 @@ mod tests {
         let (out, shortcodes) = parse_for_shortcodes(
             "Hello World {%/* youtube() */%} Somebody {%/* end */%} hey",
             &mut ShortcodeInvocationCounter::new(),
+            &Context::new(),
+            &HashMap::new(),
         )
         .unwrap();
         assert_eq!(out, "Hello World {% youtube() %} Somebody {% end %} hey");
@@ -521,6 +607,8 @@ mod tests {
         let (out, shortcodes) = parse_for_shortcodes(
             "Hello World {% youtube() %} Somebody {% end %} {{ hello() }}\n {{hello()}}",
             &mut ShortcodeInvocationCounter::new(),
+            &Context::new(),
+            &HashMap::new(),
         )
         .unwrap();
         assert_eq!(
@@ -541,6 +629,8 @@ mod tests {
         let (out, shortcodes) = parse_for_shortcodes(
             "Hello World {% i_am_gonna_nest() %} Somebody {% i_am_gonna_nest() %} Somebody {% end %} {% end %}!!",
             &mut ShortcodeInvocationCounter::new(),
+            &Context::new(),
+            &HashMap::new(),
         )
         .unwrap();
         assert_eq!(out, format!("Hello World {}!!", SHORTCODE_PLACEHOLDER,));
@@ -561,8 +651,101 @@ mod tests {
         {{ streamable(id="c0ic", n1=true) }}
         {{ gist(url="https://gist.github.com/Keats/32d26f699dcc13ebd41b") }}"#,
             &mut ShortcodeInvocationCounter::new(),
+            &Context::new(),
+            &HashMap::new(),
         )
         .unwrap();
         assert_eq!(shortcodes.len(), 5);
     }
+
+    #[test]
+    fn can_extract_inline_tables() {
+        let (_, shortcodes) = parse_for_shortcodes(
+            "{{ figure(opts={a=1, 'b c'=\"x\", nested={d=[true]}}, empty={}) }}",
+            &mut ShortcodeInvocationCounter::new(),
+            &Context::new(),
+            &HashMap::new(),
+        )
+        .unwrap();
+        assert_eq!(shortcodes[0].args["opts"]["a"], to_value(1).unwrap());
+        assert_eq!(shortcodes[0].args["opts"]["b c"], to_value("x").unwrap());
+        assert_eq!(shortcodes[0].args["opts"]["nested"]["d"], to_value([true]).unwrap());
+        assert_eq!(shortcodes[0].args["empty"], Value::Object(Map::new()));
+    }
+
+    #[test]
+    fn can_resolve_references_in_args() {
+        let mut context = Context::new();
+        context.insert("page", &to_value(test_page()).unwrap());
+        let (_, shortcodes) = parse_for_shortcodes(
+            "{% figure(title=page.title, img=page.extra.images.1, opts={alt=page.extra.alt}) %}{{ nested(pages=page.title) }}{% end %}",
+            &mut ShortcodeInvocationCounter::new(),
+            &context,
+            &definitions(&["figure", "nested"]),
+        )
+        .unwrap();
+        assert_eq!(shortcodes[0].args["title"], to_value("Hello").unwrap());
+        assert_eq!(shortcodes[0].args["img"], to_value("b.png").unwrap());
+        assert_eq!(shortcodes[0].args["opts"]["alt"], to_value("A cat").unwrap());
+        assert_eq!(shortcodes[0].inner[0].args["pages"], to_value("Hello").unwrap());
+    }
+
+    #[test]
+    fn leaves_other_variables_as_text() {
+        let content = "{{ get_url(path=asset) }} {{ get_url(path=pages.title) }} {{ a(b=trueish) }} \
+            {{ get_url(path=page.colocated_path) }} {{ load_data(path=config.extra.data, required=true) }} \
+            {% filter(lang=lang) %}Body{% end %}";
+        let (out, shortcodes) = parse_for_shortcodes(
+            content,
+            &mut ShortcodeInvocationCounter::new(),
+            &Context::new(),
+            &definitions(&["figure"]),
+        )
+        .unwrap();
+        assert_eq!(out, content);
+        assert!(shortcodes.is_empty());
+    }
+
+    #[test]
+    fn errors_on_unresolvable_references() {
+        let mut context = Context::new();
+        context.insert("page", &to_value(test_page()).unwrap());
+        for reference in ["page.extra.cover", "page.extra.images.2", "page.title.nope", "section"] {
+            let err = parse_for_shortcodes(
+                &format!("{{{{ figure(img={reference}) }}}}"),
+                &mut ShortcodeInvocationCounter::new(),
+                &context,
+                &definitions(&["figure"]),
+            )
+            .unwrap_err();
+            assert!(
+                err.to_string().starts_with(&format!(
+                    "Found a reference to `{reference}` in the arguments of a shortcode"
+                )),
+                "{}",
+                err
+            );
+        }
+    }
+
+    fn definitions(names: &[&str]) -> HashMap<String, ShortcodeDefinition> {
+        names
+            .iter()
+            .map(|name| {
+                let def =
+                    ShortcodeDefinition::new(ShortcodeFileType::Html, &format!("{name}.html"));
+                (name.to_string(), def)
+            })
+            .collect()
+    }
+
+    fn test_page() -> HashMap<&'static str, Value> {
+        let mut extra = Map::new();
+        extra.insert("images".to_string(), to_value(["a.png", "b.png"]).unwrap());
+        extra.insert("alt".to_string(), to_value("A cat").unwrap());
+        let mut page = HashMap::new();
+        page.insert("title", to_value("Hello").unwrap());
+        page.insert("extra", Value::Object(extra));
+        page
+    }
 }
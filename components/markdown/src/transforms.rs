@@ -0,0 +1,79 @@
+use std::fmt::Debug;
+use std::sync::Arc;
+
+use errors::{Result, bail};
+use pulldown_cmark::{Event, Tag, TagEnd};
+
+use crate::context::RenderContext;
+
+/// A hook into the markdown rendering: it receives all the events of a document after Zola
+/// processed them (shortcodes rendered, links resolved, code blocks highlighted) and before the
+/// headings, anchors, footnotes and summary are handled and the HTML is generated.
+///
+/// Transforms are run in the order they are registered, each one receiving the output of the previous one.
+pub trait MarkdownTransform: Debug + Send + Sync {
+    /// The name of the transform, used in error messages and to enable builtin
+    /// transforms from the `markdown.transforms` config option
+    fn name(&self) -> &str;
+
+    fn transform<'a>(
+        &self,
+        events: Vec<Event<'a>>,
+        context: &RenderContext,
+    ) -> Result<Vec<Event<'a>>>;
+}
+
+/// Wraps every table in a `<div class="table-wrapper">` so they can be made scrollable
+/// on small screens with CSS
+#[derive(Debug, Default)]
+pub struct WrapTables;
+
+impl MarkdownTransform for WrapTables {
+    fn name(&self) -> &str {
+        "wrap_tables"
+    }
+
+    fn transform<'a>(
+        &self,
+        events: Vec<Event<'a>>,
+        _context: &RenderContext,
+    ) -> Result<Vec<Event<'a>>> {
+        let mut out = Vec::with_capacity(events.len());
+        for event in events {
+            match event {
+                Event::Start(Tag::Table(_)) => {
+                    out.push(Event::Html("<div class=\"table-wrapper\">\n".into()));
+                    out.push(event);
+                }
+                Event::End(TagEnd::Table) => {
+                    out.push(event);
+                    out.push(Event::Html("</div>\n".into()));
+                }
+                _ => out.push(event),
+            }
+        }
+        Ok(out)
+    }
+}
+
+/// Returns the builtin transform with that name
+fn builtin_transform(name: &str) -> Option<Arc<dyn MarkdownTransform>> {
+    match name {
+        "wrap_tables" => Some(Arc::new(WrapTables)),
+        _ => None,
+    }
+}
+
+/// The transforms to run for that context: the builtin ones enabled in the config first,
+/// then the ones registered on the context.
+pub(crate) fn get_transforms(context: &RenderContext) -> Result<Vec<Arc<dyn MarkdownTransform>>> {
+    let mut transforms = Vec::new();
+    for name in &context.config.markdown.transforms {
+        match builtin_transform(name) {
+            Some(t) => transforms.push(t),
+            None => bail!("Unknown markdown transform `{}` in `markdown.transforms`", name),
+        }
+    }
+    transforms.extend(context.transforms.iter().cloned());
+    Ok(transforms)
+}
@@ -0,0 +1,108 @@
+//! Wiki links like `[[Page Title]]`, `[[slug|label]]` or `[[filename#heading]]`, enabled with
+//! `markdown.wiki_links`. Their target is looked up in the filename, slug and title of the pages
+//! and turned into an internal link so they are checked and counted as backlinks like `@/` links.
+use errors::{Result, bail};
+
+use crate::context::RenderContext;
+
+/// Returns the internal link for the target of a wiki link, eg `@/blog/post.md#heading`.
+/// A target that doesn't match exactly one page or section is an error or a warning depending on
+/// `link_checker.internal_level`, in which case `None` is returned and the label is rendered
+/// as plain text.
+pub(crate) fn resolve_wiki_link(target: &str, context: &RenderContext) -> Result<Option<String>> {
+    let (name, anchor) = match target.split_once('#') {
+        Some((name, anchor)) => (name.trim(), Some(anchor)),
+        None => (target.trim(), None),
+    };
+    let anchor = anchor.map(|a| format!("#{a}")).unwrap_or_default();
+    // `[[#heading]]` links to a heading of the current page
+    if name.is_empty() {
+        return Ok(Some(anchor));
+    }
+
+    let page = context.current_page_path.unwrap_or("unknown");
+    let msg = match context.wiki_links.get(&name.to_lowercase()).map(Vec::as_slice) {
+        Some([path]) => return Ok(Some(format!("@/{path}{anchor}"))),
+        Some(paths) if !paths.is_empty() => format!(
+            "Ambiguous wiki link `[[{}]]` in {}: it matches {}",
+            target,
+            page,
+            paths.join(", ")
+        ),
+        _ => format!(
+            "Broken wiki link `[[{}]]` in {}: no page or section has this filename, slug or title",
+            target, page
+        ),
+    };
+    match context.config.link_checker.internal_level {
+        config::LinkCheckerLevel::Error => bail!(msg),
+        config::LinkCheckerLevel::Warn => {
+            log::warn!("{msg}");
+            Ok(None)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use config::Config;
+    use tera::Tera;
+    use utils::types::InsertAnchor;
+
+    use super::*;
+
+    fn resolve_with_config(target: &str, config: Config) -> Result<Option<String>> {
+        let tera = Tera::default();
+        let permalinks = HashMap::new();
+        let wiki_links = HashMap::from([
+            ("hello".to_string(), vec!["blog/hello.md".to_string()]),
+            ("hello world".to_string(), vec!["blog/hello.md".to_string()]),
+            ("notes".to_string(), vec!["a/notes.md".to_string(), "b/notes.md".to_string()]),
+        ]);
+        let mut context =
+            RenderContext::new(&tera, &config, "en", "", &permalinks, InsertAnchor::None);
+        context.set_current_page_path("post.md");
+        context.set_wiki_links(&wiki_links);
+        resolve_wiki_link(target, &context)
+    }
+
+    fn resolve(target: &str) -> Result<Option<String>> {
+        resolve_with_config(target, Config::default_for_test())
+    }
+
+    #[test]
+    fn can_resolve_wiki_links() {
+        assert_eq!(resolve("hello").unwrap().unwrap(), "@/blog/hello.md");
+        assert_eq!(resolve("Hello World").unwrap().unwrap(), "@/blog/hello.md");
+        assert_eq!(resolve("hello#some-heading").unwrap().unwrap(), "@/blog/hello.md#some-heading");
+        assert_eq!(resolve("#some-heading").unwrap().unwrap(), "#some-heading");
+    }
+
+    #[test]
+    fn errors_on_ambiguous_wiki_links() {
+        let err = resolve("Notes").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Ambiguous wiki link `[[Notes]]` in post.md: it matches a/notes.md, b/notes.md"
+        );
+    }
+
+    #[test]
+    fn errors_on_broken_wiki_links() {
+        let err = resolve("Nowhere").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Broken wiki link `[[Nowhere]]` in post.md: no page or section has this filename, slug or title"
+        );
+    }
+
+    #[test]
+    fn does_not_link_broken_wiki_links_when_warning() {
+        let mut config = Config::default_for_test();
+        config.link_checker.internal_level = config::LinkCheckerLevel::Warn;
+        assert_eq!(resolve_with_config("Nowhere", config.clone()).unwrap(), None);
+        assert_eq!(resolve_with_config("Notes", config).unwrap(), None);
+    }
+}
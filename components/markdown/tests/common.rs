@@ -1,6 +1,7 @@
 #![allow(dead_code)]
 
 use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 
 use tera::Tera;
 
@@ -8,12 +9,25 @@ use config::Config;
 use errors::Result;
 use markdown::{RenderContext, Rendered, render_content};
 use templates::ZOLA_TERA;
-use utils::types::InsertAnchor;
+use utils::types::{ContentFormat, InsertAnchor};
 
-fn configurable_render(
+const VIDEO_SHORTCODE: &str = r#"{#
++++
+[args.id]
+type = "string"
+required = true
+
+[args.width]
+type = "integer"
+default = 560
++++
+#}<iframe src="https://example.com/{{ id }}" width="{{ width }}"></iframe>"#;
+
+pub fn configurable_render(
     content: &str,
     config: Config,
     insert_anchor: InsertAnchor,
+    format: ContentFormat,
 ) -> Result<Rendered> {
     let mut tera = Tera::default();
     tera.extend(&ZOLA_TERA).unwrap();
@@ -54,13 +68,29 @@ fn configurable_render(
     .unwrap();
     tera.add_raw_template("shortcodes/md_passthrough.md", "{{body}}").unwrap();
     tera.add_raw_template("shortcodes/nth.html", "{{ nth }}").unwrap();
+    tera.add_raw_template(
+        "containers/note.html",
+        r#"<aside class="note" id="note-{{ nth }}">{% if title %}<p class="title">{{ title }}</p>{% endif %}{{ body | safe }}</aside>"#,
+    )
+    .unwrap();
 
     let mut permalinks = HashMap::new();
     permalinks.insert("pages/about.md".to_owned(), "https://getzola.org/about/".to_owned());
 
+    // Templates added from strings have no file to read their argument declaration from
+    tera.add_raw_template("shortcodes/video.html", VIDEO_SHORTCODE).unwrap();
+    let mut shortcode_def = utils::templates::get_shortcodes(&tera).unwrap();
+    shortcode_def.get_mut("video").unwrap().schema =
+        utils::templates::ShortcodeSchema::from_template_source(VIDEO_SHORTCODE).unwrap();
+
     tera.register_filter(
         "markdown",
-        templates::filters::MarkdownFilter::new(config.clone(), permalinks.clone(), tera.clone()),
+        templates::filters::MarkdownFilter::new(
+            config.clone(),
+            permalinks.clone(),
+            tera.clone(),
+            shortcode_def.clone(),
+        ),
     );
     let mut context = RenderContext::new(
         &tera,
@@ -70,21 +100,45 @@ fn configurable_render(
         &permalinks,
         insert_anchor,
     );
-    let shortcode_def = utils::templates::get_shortcodes(&tera);
     context.set_shortcode_definitions(&shortcode_def);
     context.set_current_page_path("my_page.md");
+    context.set_format(format);
+    let mut wiki_links = HashMap::new();
+    wiki_links.insert("about".to_owned(), vec!["pages/about.md".to_owned()]);
+    wiki_links.insert("about us".to_owned(), vec!["pages/about.md".to_owned()]);
+    wiki_links
+        .insert("notes".to_owned(), vec!["pages/notes.md".to_owned(), "posts/notes.md".to_owned()]);
+    context.set_wiki_links(&wiki_links);
+    if config.markdown.images.enabled {
+        let base_path = config.markdown.base_path.clone().unwrap_or_default();
+        let imageproc = imageproc::Processor::new(base_path, &config);
+        context.set_imageproc(Arc::new(Mutex::new(imageproc)));
+    }
 
     render_content(content, &context)
 }
 
 pub fn render(content: &str) -> Result<Rendered> {
-    configurable_render(content, Config::default_for_test(), InsertAnchor::None)
+    configurable_render(
+        content,
+        Config::default_for_test(),
+        InsertAnchor::None,
+        ContentFormat::Markdown,
+    )
 }
 
 pub fn render_with_config(content: &str, config: Config) -> Result<Rendered> {
-    configurable_render(content, config, InsertAnchor::None)
+    configurable_render(content, config, InsertAnchor::None, ContentFormat::Markdown)
 }
 
 pub fn render_with_insert_anchor(content: &str, insert_anchor: InsertAnchor) -> Result<Rendered> {
-    configurable_render(content, Config::default_for_test(), insert_anchor)
+    configurable_render(content, Config::default_for_test(), insert_anchor, ContentFormat::Markdown)
+}
+
+pub fn render_with_format(
+    content: &str,
+    format: ContentFormat,
+    insert_anchor: InsertAnchor,
+) -> Result<Rendered> {
+    configurable_render(content, Config::default_for_test(), insert_anchor, format)
 }
@@ -0,0 +1,189 @@
+use config::Config;
+use utils::types::{ContentFormat, InsertAnchor};
+
+mod common;
+
+#[test]
+fn can_render_djot() {
+    let content = r#"# Getting started
+
+A _Djot_ page, see [about](@/pages/about.md#team) and [the setup][Setting up].
+
+<!-- more -->
+
+## Setting up
+
+Install [Zola](https://www.getzola.org) then read [the FAQ](#faq).
+
+{#faq}
+## FAQ
+
+- A list
+"#;
+    let res = common::render_with_format(content, ContentFormat::Djot, InsertAnchor::None).unwrap();
+    insta::assert_snapshot!(res.body);
+
+    assert_eq!(
+        res.summary.as_deref(),
+        Some(
+            "<h1 id=\"getting-started\">Getting started</h1>\n<p>A <em>Djot</em> page, see <a href=\"https://getzola.org/about/#team\">about</a> and <a href=\"https://www.getzola.org/test/#setting-up\">the setup</a>.</p>"
+        )
+    );
+    assert_eq!(res.toc.len(), 1);
+    assert_eq!(res.toc[0].title, "Getting started");
+    let children: Vec<_> = res.toc[0].children.iter().map(|h| h.id.as_str()).collect();
+    assert_eq!(children, ["setting-up", "faq"]);
+    assert_eq!(
+        res.internal_links,
+        [
+            ("pages/about.md".to_string(), Some("team".to_string())),
+            ("my_page.md".to_string(), Some("setting-up".to_string())),
+            ("my_page.md".to_string(), Some("faq".to_string())),
+        ]
+    );
+    assert_eq!(res.external_links, ["https://www.getzola.org"]);
+}
+
+#[test]
+fn can_render_org() {
+    let content = r#"* Getting started
+
+An /Org/ page, see [[@/pages/about.md#team][about]].
+
+<!-- more -->
+
+** Setting up
+:PROPERTIES:
+:CUSTOM_ID: setup
+:END:
+
+Install [[https://www.getzola.org][Zola]] then read [[#faq][the FAQ]].
+
+** FAQ
+
+- A list
+"#;
+    let res = common::render_with_format(content, ContentFormat::Org, InsertAnchor::None).unwrap();
+    insta::assert_snapshot!(res.body);
+
+    assert_eq!(
+        res.summary.as_deref(),
+        Some(
+            "<h1 id=\"getting-started\">Getting started</h1><p>An <i>Org</i> page, see <a href=\"https://getzola.org/about/#team\">about</a>.</p>"
+        )
+    );
+    assert_eq!(res.toc.len(), 1);
+    let children: Vec<_> = res.toc[0].children.iter().map(|h| h.id.as_str()).collect();
+    assert_eq!(children, ["setup", "faq"]);
+    assert_eq!(
+        res.internal_links,
+        [
+            ("pages/about.md".to_string(), Some("team".to_string())),
+            ("my_page.md".to_string(), Some("faq".to_string())),
+        ]
+    );
+    assert_eq!(res.external_links, ["https://www.getzola.org"]);
+}
+
+#[test]
+fn can_insert_anchors_in_other_formats() {
+    let res =
+        common::render_with_format("# Hello", ContentFormat::Djot, InsertAnchor::Left).unwrap();
+    insta::assert_snapshot!("djot_anchor", res.body);
+    let res =
+        common::render_with_format("* Hello", ContentFormat::Org, InsertAnchor::Heading).unwrap();
+    insta::assert_snapshot!("org_anchor", res.body);
+}
+
+#[test]
+fn can_apply_link_and_image_options_in_other_formats() {
+    let mut config = Config::default_for_test();
+    config.markdown.external_links_target_blank = true;
+    config.markdown.external_links_class = Some("external".to_owned());
+    config.markdown.lazy_async_image = true;
+
+    let res = common::configurable_render(
+        "[Zola](https://www.getzola.org){lang=en} ![A cat](cat.jpg)",
+        config.clone(),
+        InsertAnchor::None,
+        ContentFormat::Djot,
+    )
+    .unwrap();
+    assert_eq!(
+        res.body,
+        "<p><a class=\"external\" rel=\"noopener external\" target=\"_blank\" href=\"https://www.getzola.org\" lang=\"en\">Zola</a> <img loading=\"lazy\" decoding=\"async\" alt=\"A cat\" src=\"cat.jpg\"></p>\n"
+    );
+
+    let res = common::configurable_render(
+        "[[https://www.getzola.org][Zola]] [[file:cat.jpg]]",
+        config,
+        InsertAnchor::None,
+        ContentFormat::Org,
+    )
+    .unwrap();
+    assert_eq!(
+        res.body,
+        "<p><a class=\"external\" rel=\"noopener external\" target=\"_blank\" href=\"https://www.getzola.org\">Zola</a> <img src=\"https://www.getzola.org/test/cat.jpg\" alt=\"cat.jpg\" loading=\"lazy\" decoding=\"async\" /></p>"
+    );
+}
+
+#[test]
+fn errors_on_broken_internal_links_in_other_formats() {
+    let res = common::render_with_format(
+        "[nope](@/pages/nope.md)",
+        ContentFormat::Djot,
+        InsertAnchor::None,
+    );
+    assert_eq!(
+        res.unwrap_err().to_string(),
+        "Broken relative link `@/pages/nope.md` in my_page.md"
+    );
+    let res = common::render_with_format(
+        "[[@/pages/nope.md][nope]]",
+        ContentFormat::Org,
+        InsertAnchor::None,
+    );
+    assert_eq!(
+        res.unwrap_err().to_string(),
+        "Broken relative link `@/pages/nope.md` in my_page.md"
+    );
+}
+
+#[test]
+fn can_render_notebook() {
+    let content = r###"{
+  "metadata": {
+    "zola": {"title": "Analysis"},
+    "kernelspec": {"language": "python", "name": "python3"}
+  },
+  "cells": [
+    {"cell_type": "markdown", "metadata": {}, "source": ["# Analysis\n", "\n", "Some *data*, see [about](@/pages/about.md).\n", "\n", "<!-- more -->"]},
+    {"cell_type": "code", "metadata": {}, "execution_count": 1, "source": "print(1 < 2)\n3 + 4", "outputs": [
+      {"output_type": "stream", "name": "stdout", "text": ["True\n"]},
+      {"output_type": "execute_result", "execution_count": 1, "metadata": {}, "data": {"text/plain": ["7"]}}
+    ]},
+    {"cell_type": "raw", "metadata": {}, "source": "Only for nbconvert"},
+    {"cell_type": "code", "metadata": {}, "execution_count": 2, "source": ["plot()"], "outputs": [
+      {"output_type": "display_data", "metadata": {}, "data": {"image/png": "iVBORw0KGgo=\n", "text/plain": ["<Figure>"]}},
+      {"output_type": "display_data", "metadata": {}, "data": {"text/html": ["<table>\n", "\n", "<tr><td>1</td></tr>\n", "</table>"]}},
+      {"output_type": "error", "ename": "ValueError", "evalue": "nope", "traceback": ["\u001b[0;31mValueError\u001b[0m: nope"]}
+    ]},
+    {"cell_type": "markdown", "metadata": {}, "source": "## Conclusion"}
+  ],
+  "nbformat": 4,
+  "nbformat_minor": 5
+}"###;
+    let res =
+        common::render_with_format(content, ContentFormat::Notebook, InsertAnchor::None).unwrap();
+    insta::assert_snapshot!(res.body);
+
+    assert_eq!(
+        res.summary.as_deref(),
+        Some(
+            "<h1 id=\"analysis\">Analysis</h1>\n<p>Some <em>data</em>, see <a href=\"https://getzola.org/about/\">about</a>.</p>"
+        )
+    );
+    let children: Vec<_> = res.toc[0].children.iter().map(|h| h.id.as_str()).collect();
+    assert_eq!(children, ["conclusion"]);
+    assert_eq!(res.internal_links, [("pages/about.md".to_string(), None)]);
+}
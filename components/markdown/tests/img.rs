@@ -31,3 +31,37 @@ fn can_add_lazy_loading_and_async_decoding() {
     let body = common::render_with_config(&cases.join("\n"), config).unwrap().body;
     insta::assert_snapshot!(body);
 }
+
+#[test]
+fn can_render_responsive_images() {
+    let dir = tempfile::tempdir().unwrap();
+    let content = dir.path().join("content");
+    std::fs::create_dir(&content).unwrap();
+    let image = concat!(env!("CARGO_MANIFEST_DIR"), "/../imageproc/tests/test_imgs/png.png");
+    std::fs::copy(image, content.join("png.png")).unwrap();
+    let mut config = Config::default_for_test();
+    config.markdown.base_path = Some(dir.path().to_path_buf());
+    config.markdown.lazy_async_image = true;
+    config.markdown.images.enabled = true;
+    // The image is 300px wide so it is not upscaled to 480px
+    config.markdown.images.widths = vec![150, 480];
+    config.markdown.images.sizes = Some("(min-width: 40em) 50vw, 100vw".to_owned());
+
+    let cases = vec![
+        "![A PNG](png.png)",
+        // Remote, missing and vector images are kept as they are
+        "![remote](https://example.com/abc.jpg)",
+        "![missing](missing.png)",
+        "![vector](diagram.svg)",
+    ];
+    let rendered = common::render_with_config(&cases.join("\n\n"), config.clone()).unwrap();
+    insta::assert_snapshot!(rendered.body);
+    assert_eq!(rendered.responsive_images, vec![content.join("png.png")]);
+
+    // With a single format there is no `<picture>`
+    config.markdown.images.formats = vec!["webp".to_owned()];
+    let body = common::render_with_config("![A PNG](png.png)", config).unwrap().body;
+    assert!(body.starts_with("<p><img src=\"http://a-website.com/processed_images/png."));
+    assert!(body.contains(".webp 150w, "));
+    assert!(body.contains(".webp 300w\" sizes=\"(min-width: 40em) 50vw, 100vw\" width=\"300\" height=\"380\" loading=\"lazy\""));
+}
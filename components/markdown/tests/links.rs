@@ -1,5 +1,7 @@
 mod common;
 
+use config::Config;
+
 #[test]
 fn can_detect_links() {
     // no links
@@ -53,3 +55,48 @@ Email: <foo@bar.baz>",
     assert!(res.is_err());
     assert_eq!(res.unwrap_err().to_string(), "There is a link that is missing a URL");
 }
+
+#[test]
+fn can_resolve_wiki_links() {
+    let mut config = Config::default_for_test();
+    config.markdown.wiki_links = true;
+    let rendered = common::render_with_config(
+        "See [[About]], [[about us|the team]] and [[about#contact]] or [[#intro]].",
+        config.clone(),
+    )
+    .unwrap();
+    assert_eq!(
+        rendered.body,
+        "<p>See <a href=\"https://getzola.org/about/\">About</a>, <a href=\"https://getzola.org/about/\">the team</a> and <a href=\"https://getzola.org/about/#contact\">about#contact</a> or <a href=\"https://www.getzola.org/test/#intro\">#intro</a>.</p>\n"
+    );
+    assert_eq!(
+        rendered.internal_links,
+        vec![
+            ("pages/about.md".to_owned(), None),
+            ("pages/about.md".to_owned(), None),
+            ("pages/about.md".to_owned(), Some("contact".to_owned())),
+            ("my_page.md".to_owned(), Some("intro".to_owned())),
+        ]
+    );
+
+    let err = common::render_with_config("See [[Notes]]", config.clone()).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Ambiguous wiki link `[[Notes]]` in my_page.md: it matches pages/notes.md, posts/notes.md"
+    );
+    assert!(common::render_with_config("See [[Nowhere]]", config.clone()).is_err());
+
+    // Broken wiki links are left as text when only warning about them
+    config.link_checker.internal_level = config::LinkCheckerLevel::Warn;
+    let rendered =
+        common::render_with_config("See [[Nowhere|the void]] and [[Notes]].", config).unwrap();
+    assert_eq!(rendered.body, "<p>See the void and Notes.</p>\n");
+    assert!(rendered.internal_links.is_empty());
+}
+
+#[test]
+fn wiki_links_are_opt_in() {
+    let rendered = common::render("See [[About]]").unwrap();
+    assert_eq!(rendered.body, "<p>See [[About]]</p>\n");
+    assert!(rendered.internal_links.is_empty());
+}
@@ -2,8 +2,9 @@ use std::collections::HashMap;
 
 use tera::Tera;
 
-use config::Config;
-use markdown::{RenderContext, render_content};
+use config::{Config, FootnoteStyle};
+use markdown::pulldown_cmark::Event;
+use markdown::{MarkdownTransform, RenderContext, render_content};
 use templates::ZOLA_TERA;
 use utils::slugs::SlugifyStrategy;
 use utils::types::InsertAnchor;
@@ -458,6 +459,54 @@ Footnotes can also be referenced with identifiers[^first].
     insta::assert_snapshot!(body);
 }
 
+#[test]
+fn sidenote_footnotes() {
+    let mut config = Config::default_for_test();
+    config.markdown.footnote_style = FootnoteStyle::Sidenote;
+
+    let markdown = r#"This text has a footnote[^1] and the same one again[^1].
+
+[^1]: But it is meaningless.
+
+    And has a second paragraph.
+
+This text has a footnote[^2]
+
+[^2]: But the footnote has another footnote[^3].
+
+[^3]: That's it.
+
+[^4]: Never referenced.
+"#;
+
+    let body = common::render_with_config(markdown, config).unwrap().body;
+    insta::assert_snapshot!(body);
+}
+
+#[test]
+fn errors_on_sidenotes_with_blocks() {
+    let mut config = Config::default_for_test();
+    config.markdown.footnote_style = FootnoteStyle::Sidenote;
+
+    let markdown = r#"This text has a footnote[^1].
+
+[^1]: It has a list:
+
+    - one
+    - two
+
+    ```
+    and some code
+    ```
+"#;
+
+    let err = common::render_with_config(markdown, config).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "The footnote `1` in \"my_page.md\" contains a list: sidenotes can only contain paragraphs, use `footnote_style = \"bottom\"` for such footnotes"
+    );
+}
+
 #[test]
 fn github_alerts() {
     let mut config = Config::default_for_test();
@@ -483,3 +532,395 @@ fn github_alerts() {
     let body = common::render_with_config(&markdown, config).unwrap().body;
     insta::assert_snapshot!(body);
 }
+
+#[test]
+fn can_wrap_tables() {
+    let mut config = Config::default_for_test();
+    config.markdown.transforms = vec!["wrap_tables".to_string()];
+
+    let markdown = r#"
+| a | b |
+|---|---|
+| 1 | 2 |
+"#;
+
+    let body = common::render_with_config(markdown, config).unwrap().body;
+    insta::assert_snapshot!(body);
+}
+
+#[test]
+fn errors_on_unknown_transform() {
+    let mut config = Config::default_for_test();
+    config.markdown.transforms = vec!["unknown".to_string()];
+    let err = common::render_with_config("Hello", config).unwrap_err();
+    assert!(format!("{err:?}").contains("Unknown markdown transform `unknown`"));
+}
+
+#[derive(Debug)]
+struct Shout;
+
+impl MarkdownTransform for Shout {
+    fn name(&self) -> &str {
+        "shout"
+    }
+
+    fn transform<'a>(
+        &self,
+        events: Vec<Event<'a>>,
+        _context: &RenderContext,
+    ) -> errors::Result<Vec<Event<'a>>> {
+        Ok(events
+            .into_iter()
+            .map(|e| match e {
+                Event::Text(t) => Event::Text(t.to_uppercase().into()),
+                _ => e,
+            })
+            .collect())
+    }
+}
+
+#[test]
+fn can_register_custom_transform() {
+    let mut tera = Tera::default();
+    tera.extend(&ZOLA_TERA).unwrap();
+    let permalinks_ctx = HashMap::new();
+    let config = Config::default_for_test();
+    let mut context = RenderContext::new(
+        &tera,
+        &config,
+        &config.default_language,
+        "",
+        &permalinks_ctx,
+        InsertAnchor::Right,
+    );
+    context.add_transform(Shout);
+    let rendered = render_content("# Hello\n\nworld", &context).unwrap();
+    // The transforms run before the headings are handled
+    assert_eq!(rendered.toc[0].title, "HELLO");
+    assert!(rendered.body.contains("<p>WORLD</p>"));
+}
+
+#[test]
+fn can_render_dot_fences_to_svg() {
+    let mut config = Config::default_for_test();
+    config.markdown.fence_renderers = vec!["dot".to_string()];
+
+    let markdown = r#"
+```dot
+digraph { a -> b; }
+```
+
+```rust
+fn main() {}
+```
+"#;
+
+    let body = common::render_with_config(markdown, config).unwrap().body;
+    assert!(body.starts_with("<svg"));
+    assert!(!body.contains("<?xml"));
+    assert!(body.contains("<pre><code data-lang=\"rust\">fn main() {}"));
+}
+
+#[test]
+fn errors_on_invalid_dot_fence() {
+    let mut config = Config::default_for_test();
+    config.markdown.fence_renderers = vec!["dot".to_string()];
+    let err = common::render_with_config("```dot\ndigraph { a -> }\n```", config).unwrap_err();
+    assert!(format!("{err:?}").contains("Failed to render `dot` code block"));
+}
+
+#[test]
+fn can_include_files_in_code_blocks() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir_all(dir.path().join("examples")).unwrap();
+    std::fs::write(
+        dir.path().join("examples/main.rs"),
+        "use std::env;\n\nfn main() {\n    // ANCHOR: setup\n    let args = env::args();\n    // ANCHOR_END: setup\n    println!(\"{args:?}\");\n}\n",
+    )
+    .unwrap();
+    let mut config = Config::default_for_test();
+    config.markdown.base_path = Some(dir.path().to_path_buf());
+
+    let markdown = r#"
+```rust,file=examples/main.rs,lines=3-
+```
+
+```rust,region=setup,file=examples/main.rs
+this is replaced
+```
+"#;
+
+    let rendered = common::render_with_config(markdown, config).unwrap();
+    assert!(rendered.body.contains(
+        "<pre><code data-lang=\"rust\">fn main() {\n    // ANCHOR: setup\n    let args = env::args();"
+    ));
+    assert!(
+        rendered
+            .body
+            .contains("<pre><code data-lang=\"rust\">let args = env::args();\n</code></pre>")
+    );
+    assert!(!rendered.body.contains("this is replaced"));
+    let path = dir.path().join("examples/main.rs").canonicalize().unwrap();
+    assert_eq!(rendered.included_files, vec![path; 2]);
+}
+
+#[test]
+fn errors_on_included_file_outside_of_the_site() {
+    let dir = tempfile::tempdir().unwrap();
+    let site = dir.path().join("site");
+    std::fs::create_dir(&site).unwrap();
+    std::fs::write(dir.path().join("secret.txt"), "hunter2").unwrap();
+    let mut config = Config::default_for_test();
+    config.markdown.base_path = Some(site);
+
+    let absolute = dir.path().join("secret.txt");
+    for file in ["../secret.txt".to_owned(), absolute.display().to_string()] {
+        let markdown = format!("```txt,file={file}\n```");
+        let err = common::render_with_config(&markdown, config.clone()).unwrap_err();
+        assert!(
+            format!("{err:?}").contains(&format!("`{file}` is outside of the site directory")),
+            "{err:?}"
+        );
+    }
+}
+
+#[test]
+fn errors_on_missing_included_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut config = Config::default_for_test();
+    config.markdown.base_path = Some(dir.path().to_path_buf());
+    let err = common::render_with_config(
+        "```rust,file=nope.rs
+```",
+        config,
+    )
+    .unwrap_err();
+    assert!(
+        format!("{err:?}")
+            .contains("Failed to include `nope.rs` in a code block in \"my_page.md\"")
+    );
+}
+
+#[test]
+fn can_render_math_to_mathml() {
+    let mut config = Config::default_for_test();
+    config.markdown.math = true;
+
+    let markdown = r#"
+Euler's identity: $e^{i\pi} + 1 = 0$.
+
+$$
+\sum_{k=1}^n k = \frac{n(n+1)}{2}
+$$
+"#;
+
+    let body = common::render_with_config(markdown, config).unwrap().body;
+    insta::assert_snapshot!(body);
+}
+
+#[test]
+fn does_not_render_math_by_default() {
+    let body =
+        common::render_with_config("It costs $5 or $10.", Config::default_for_test()).unwrap().body;
+    assert_eq!(body, "<p>It costs $5 or $10.</p>\n");
+}
+
+#[test]
+fn errors_on_invalid_math() {
+    let mut config = Config::default_for_test();
+    config.markdown.math = true;
+    let err = common::render_with_config(r"Hello $\frac{1}$", config).unwrap_err();
+    let err = format!("{err:?}");
+    assert!(err.contains("Failed to render math `\\frac{1}`"));
+    assert!(err.contains("Missing argument for `frac`"));
+}
+
+#[test]
+fn can_render_citations_and_references() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("refs.bib"),
+        r#"@article{lamport94,
+    author = {Leslie Lamport and Someone Else},
+    title = {{LaTeX}: A Document Preparation System},
+    journal = {Addison-Wesley},
+    year = 1994,
+    doi = {10.1000/182},
+}
+@book{knuth84,
+    author = {Donald E. Knuth},
+    title = {The TeXbook},
+    publisher = {Addison-Wesley},
+    year = 1984,
+}"#,
+    )
+    .unwrap();
+    let mut config = Config::default_for_test();
+    config.markdown.bibliography = Some("refs.bib".to_owned());
+    config.markdown.base_path = Some(dir.path().to_path_buf());
+
+    let rendered = common::render_with_config(
+        "As described [@knuth84, p. 12] and *[@lamport94; @knuth84]*.\n\n```\n[@notacitation]\n```",
+        config,
+    )
+    .unwrap();
+    insta::assert_snapshot!(rendered.body);
+    let keys: Vec<_> = rendered.citations.iter().map(|c| (c.key.as_str(), c.number)).collect();
+    assert_eq!(keys, vec![("knuth84", 1), ("lamport94", 2)]);
+}
+
+#[test]
+fn does_not_render_citations_without_bibliography() {
+    let body = common::render("Ping [@someone]").unwrap().body;
+    assert_eq!(body, "<p>Ping [@someone]</p>\n");
+}
+
+#[test]
+fn errors_on_unknown_citation() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("refs.bib"), "@book{knuth84, title = {The TeXbook}}").unwrap();
+    let mut config = Config::default_for_test();
+    config.markdown.bibliography = Some("refs.bib".to_owned());
+    config.markdown.base_path = Some(dir.path().to_path_buf());
+
+    let err = common::render_with_config("See [@nobody]", config).unwrap_err();
+    assert!(format!("{err:?}").contains("Citation `@nobody` in \"my_page.md\" not found"));
+}
+
+#[test]
+fn can_use_attribute_lists() {
+    let mut config = Config::default_for_test();
+    config.markdown.attribute_lists = true;
+    let markdown = r#"A paragraph with ![a diagram](a.png){.wide loading=eager} and [a link](https://example.com){#link .button title="Tom & Jerry"}.
+{.lead data-x='1 2'}
+
+| Left | Right |
+|:-----|------:|
+| a    | b     |
+
+{.striped}
+
+1. one
+2. two
+
+{start=3 .steps}
+
+> quoted
+
+{.quote}
+
+```rust
+let x = 1;
+```
+
+{#snippet}
+
+Not {an attribute list} and {.not} at the end of a line.
+
+{.orphan}"#;
+    let body = common::render_with_config(markdown, config).unwrap().body;
+    insta::assert_snapshot!(body);
+}
+
+#[test]
+fn attribute_lists_work_with_lazy_images_and_external_links() {
+    let mut config = Config::default_for_test();
+    config.markdown.attribute_lists = true;
+    config.markdown.lazy_async_image = true;
+    config.markdown.external_links_target_blank = true;
+    config.markdown.external_links_class = Some("external".to_owned());
+    let markdown =
+        "![cover](cover.png){.hero loading=eager} [site](https://example.com){.cta rel=me}";
+    let body = common::render_with_config(markdown, config).unwrap().body;
+    assert_eq!(
+        body,
+        "<p><img src=\"https://www.getzola.org/test/cover.png\" alt=\"cover\" class=\"hero\" loading=\"eager\" decoding=\"async\" /> <a class=\"external cta\" rel=\"noopener external me\" target=\"_blank\" href=\"https://example.com\">site</a></p>\n"
+    );
+}
+
+#[test]
+fn attribute_lists_are_opt_in() {
+    let body = common::render("![a](a.png){.wide}").unwrap().body;
+    assert_eq!(
+        body,
+        "<p><img src=\"https://www.getzola.org/test/a.png\" alt=\"a\" />{.wide}</p>\n"
+    );
+}
+
+#[test]
+fn can_render_containers() {
+    let mut config = Config::default_for_test();
+    config.markdown.containers = true;
+    let markdown = r#"Before the note.
+
+::: note "Read *this*"
+Some **markdown** with a [link](https://example.com) and {{ ex1() }}.
+
+::: note
+Nested
+:::
+
+```
+:::
+```
+:::
+
+- In a list:
+
+  ::: note
+  Indented
+  :::
+"#;
+    let rendered = common::render_with_config(markdown, config).unwrap();
+    insta::assert_snapshot!(rendered.body);
+    assert_eq!(rendered.external_links, vec!["https://example.com".to_owned()]);
+}
+
+#[test]
+fn keeps_headings_and_citations_of_containers() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("refs.bib"),
+        "@book{knuth84, author = {Donald E. Knuth}, title = {The TeXbook}, year = 1984}\n\
+         @book{lamport94, author = {Leslie Lamport}, title = {LaTeX}, year = 1994}",
+    )
+    .unwrap();
+    let mut config = Config::default_for_test();
+    config.markdown.containers = true;
+    config.markdown.bibliography = Some("refs.bib".to_owned());
+    config.markdown.base_path = Some(dir.path().to_path_buf());
+    let markdown = r#"# Intro
+
+As described [@knuth84].
+
+::: note
+## Details
+
+See [@lamport94] and [@knuth84].
+:::
+"#;
+    let rendered = common::render_with_config(markdown, config).unwrap();
+    insta::assert_snapshot!(rendered.body);
+    assert_eq!(rendered.toc.len(), 1);
+    assert_eq!(rendered.toc[0].children.len(), 1);
+    assert_eq!(rendered.toc[0].children[0].id, "details");
+    let keys: Vec<_> = rendered.citations.iter().map(|c| (c.key.as_str(), c.number)).collect();
+    assert_eq!(keys, vec![("knuth84", 1), ("lamport94", 2)]);
+}
+
+#[test]
+fn errors_on_unknown_container() {
+    let mut config = Config::default_for_test();
+    config.markdown.containers = true;
+    let err = common::render_with_config("::: danger\nHello\n:::", config).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Found usage of an unknown container `danger` in \"my_page.md\": there is no `containers/danger.html` template"
+    );
+}
+
+#[test]
+fn containers_are_opt_in() {
+    let body = common::render("::: note\nHello\n:::").unwrap().body;
+    assert_eq!(body, "<p>::: note\nHello\n:::</p>\n");
+}
@@ -410,3 +410,29 @@ fn can_render_nested_shortcodes_with_bodies_with_nth() {
     .body;
     insta::assert_snapshot!(body);
 }
+
+#[test]
+fn can_fill_default_shortcode_arguments() {
+    let body = common::render(r#"{{ video(id="abc") }}"#).unwrap().body;
+    assert_eq!(body, "<iframe src=\"https://example.com/abc\" width=\"560\"></iframe>");
+}
+
+#[test]
+fn errors_on_unknown_shortcode_argument() {
+    let err = common::render(r#"{{ video(id="abc", widht=300) }}"#).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Invalid call to the shortcode `video` in \"my_page.md\": unknown argument `widht`, did you mean `width`?"
+    );
+}
+
+#[test]
+fn errors_on_invalid_shortcode_arguments_in_bodies() {
+    let err = common::render("{% render_md() %}\n{{ video(id=1) }}\n{% end %}").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Invalid call to the shortcode `video` in \"my_page.md\": the argument `id` should be a string, got `1`"
+    );
+    let err = common::render(r#"{{ video(width=300) }}"#).unwrap_err();
+    assert!(err.to_string().contains("missing required argument `id`"));
+}
@@ -1,3 +1,5 @@
+use config::{Config, FootnoteStyle};
+
 mod common;
 
 fn get_summary(content: &str) -> String {
@@ -75,3 +77,23 @@ Good bye.
     );
     insta::assert_snapshot!(body);
 }
+
+#[test]
+fn summary_with_sidenotes() {
+    let mut config = Config::default_for_test();
+    config.markdown.footnote_style = FootnoteStyle::Sidenote;
+    let rendered = common::render_with_config(
+        r#"
+Hello world[^1]!
+
+<!-- more -->
+
+And some content after
+
+[^1]: A sidenote.
+"#,
+        config,
+    )
+    .unwrap();
+    insta::assert_snapshot!(rendered.summary.expect("had no summary"));
+}
@@ -1,3 +1,5 @@
+use config::Config;
+
 mod common;
 
 #[test]
@@ -75,3 +77,77 @@ fn can_make_toc_all_levels() {
     assert_eq!(toc[0].children[1].children[0].children.len(), 1);
     assert_eq!(toc[0].children[1].children[0].children[0].children.len(), 1);
 }
+
+#[test]
+fn can_limit_toc_levels() {
+    let mut config = Config::default_for_test();
+    config.markdown.toc_min_level = 2;
+    config.markdown.toc_max_level = 3;
+    let res =
+        common::render_with_config("# Title\n\n## A\n\n### A.1\n\n#### Too deep\n\n## B", config)
+            .unwrap();
+
+    let toc = res.toc;
+    assert_eq!(toc.len(), 2);
+    assert_eq!(toc[0].title, "A");
+    assert_eq!(toc[0].children.len(), 1);
+    assert!(toc[0].children[0].children.is_empty());
+    assert_eq!(toc[1].title, "B");
+}
+
+#[test]
+fn can_number_headings() {
+    let mut config = Config::default_for_test();
+    config.markdown.toc_min_level = 2;
+    config.markdown.number_headings = true;
+    let res = common::render_with_config(
+        r#"
+# Title
+
+## Intro
+
+## Install {#install}
+
+### From source
+
+#### Skipping a level
+
+### With cargo
+
+## Usage
+
+See [§](#install) and [the sources](#from-source).
+"#,
+        config,
+    )
+    .unwrap();
+
+    insta::assert_snapshot!(res.body);
+    let numbers: Vec<_> = res.toc.iter().map(|h| h.number.as_deref().unwrap()).collect();
+    assert_eq!(numbers, vec!["1", "2", "3"]);
+    let install = &res.toc[1];
+    assert_eq!(install.title, "Install");
+    assert_eq!(install.children[0].number.as_deref(), Some("2.1"));
+    assert_eq!(install.children[0].children[0].number.as_deref(), Some("2.1.1"));
+    assert_eq!(install.children[1].number.as_deref(), Some("2.2"));
+}
+
+#[test]
+fn errors_on_cross_reference_to_unnumbered_heading() {
+    let mut config = Config::default_for_test();
+    config.markdown.number_headings = true;
+    let err = common::render_with_config("# Title\n\nSee [§](#nowhere)", config).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Cross-reference `[§](#nowhere)` in my_page.md: there is no numbered heading with this id"
+    );
+}
+
+#[test]
+fn errors_on_invalid_toc_levels() {
+    let mut config = Config::default_for_test();
+    config.markdown.toc_min_level = 3;
+    config.markdown.toc_max_level = 2;
+    let err = common::render_with_config("# Title", config).unwrap_err();
+    assert!(err.to_string().starts_with("Invalid `toc_min_level = 3` and `toc_max_level = 2`"));
+}
@@ -0,0 +1,479 @@
+//! The on-disk cache used by `zola build --incremental`.
+//!
+//! Rendering the markdown of the pages and the templates is by far the most expensive part of a
+//! build so we keep the results between builds and re-use them when nothing they depend on
+//! changed. A cached result is only re-used if:
+//!
+//! - the fingerprint of the site (Zola version, config, templates, build flags) is the same
+//! - the key of the render (the page for the markdown, the template context for the templates) is the same
+//! - the files it depends on were not modified since the previous build
+//! - every other dependency recorded by the global functions while rendering it has the same hash
+//!
+//! The cache also knows which files the previous build wrote in the output directory, so the
+//! output doesn't need to be deleted before an incremental build.
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+use tera::{Context as TeraContext, Tera, Value, to_value};
+
+use content::{Library, Page, Taxonomy};
+use errors::{Context, Result};
+use markdown::Citation;
+use templates::dependencies::{Dependency, track};
+use utils::fs::{create_directory, create_file, get_file_time, read_file};
+use utils::table_of_contents::Heading;
+use utils::types::InsertAnchor;
+
+const MANIFEST_FILENAME: &str = "build.json";
+const RENDERED_DIR: &str = "rendered";
+
+/// What we need to compute the hash of a dependency.
+/// The library is not available while rendering markdown as it is being mutated at that time.
+pub struct DependencyContext<'a> {
+    pub library: Option<&'a Library>,
+    pub taxonomies: &'a [Taxonomy],
+    pub permalinks: &'a HashMap<String, String>,
+    /// The pages wiki links can point to, only known when rendering markdown
+    pub wiki_links: Option<&'a HashMap<String, HashMap<String, Vec<String>>>>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CachedMarkdown {
+    content: String,
+    summary: Option<String>,
+    toc: Vec<Heading>,
+    internal_links: Vec<(String, Option<String>)>,
+    external_links: Vec<String>,
+    #[serde(default)]
+    citations: Vec<Citation>,
+    #[serde(default)]
+    included_files: Vec<PathBuf>,
+    #[serde(default)]
+    responsive_images: Vec<PathBuf>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct MarkdownEntry {
+    key: String,
+    dependencies: Vec<(Dependency, String)>,
+    rendered: CachedMarkdown,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct RenderedEntry {
+    key: String,
+    dependencies: Vec<(Dependency, String)>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Manifest {
+    fingerprint: String,
+    /// Keyed by the path of the page relative to the `content` directory
+    markdown: HashMap<String, MarkdownEntry>,
+    /// Keyed by the path of the rendered file relative to the output directory
+    rendered: HashMap<String, RenderedEntry>,
+    /// Every file written in the output directory, relative to it
+    outputs: BTreeSet<String>,
+}
+
+#[derive(Debug)]
+pub struct BuildCache {
+    /// The directory where the manifest and the rendered templates are stored
+    path: PathBuf,
+    fingerprint: String,
+    /// What the previous build left, only read from
+    previous: Manifest,
+    /// When the previous build finished
+    previous_time: Option<SystemTime>,
+    /// What this build is going to leave for the next one
+    current: Mutex<Manifest>,
+    /// The hashes of the dependencies, computed at most once per build
+    hashes: Mutex<HashMap<Dependency, String>>,
+    reused: AtomicUsize,
+}
+
+impl BuildCache {
+    /// Loads the cache found in the given directory if there is one and it was created
+    /// with the same fingerprint, otherwise starts from an empty cache.
+    pub fn load(path: &Path, fingerprint: String) -> Self {
+        let manifest_path = path.join(MANIFEST_FILENAME);
+        let previous_time = get_file_time(&manifest_path);
+        let previous = if manifest_path.exists() {
+            match read_file(&manifest_path)
+                .map_err(|e| e.to_string())
+                .and_then(|s| serde_json::from_str::<Manifest>(&s).map_err(|e| e.to_string()))
+            {
+                Ok(m) if m.fingerprint == fingerprint => m,
+                Ok(_) => {
+                    log::debug!(
+                        "Build cache was created with another config/templates, ignoring it"
+                    );
+                    Manifest::default()
+                }
+                Err(e) => {
+                    log::warn!("Ignoring invalid build cache {}: {}", manifest_path.display(), e);
+                    Manifest::default()
+                }
+            }
+        } else {
+            Manifest::default()
+        };
+        // A build failing halfway leaves the output in an unknown state so the manifest is only
+        // there again once this build succeeds
+        if manifest_path.exists()
+            && let Err(e) = fs::remove_file(&manifest_path)
+        {
+            log::warn!("Failed to remove build cache {}: {}", manifest_path.display(), e);
+        }
+
+        BuildCache {
+            path: path.to_path_buf(),
+            current: Mutex::new(Manifest {
+                fingerprint: fingerprint.clone(),
+                ..Default::default()
+            }),
+            fingerprint,
+            previous,
+            previous_time,
+            hashes: Mutex::new(HashMap::new()),
+            reused: AtomicUsize::new(0),
+        }
+    }
+
+    /// How many markdown and template renders were re-used from the previous build
+    pub fn reused(&self) -> usize {
+        self.reused.load(Ordering::Relaxed)
+    }
+
+    /// Whether a previous build with the same fingerprint left its output, in which case the
+    /// output directory doesn't need to be cleaned
+    pub fn has_previous_build(&self) -> bool {
+        !self.previous.outputs.is_empty()
+    }
+
+    /// Keeps track of a file written in the output directory, given relative to it
+    pub fn record_output(&self, path: &Path) {
+        let path = path.to_string_lossy().replace('\\', "/");
+        self.current.lock().unwrap().outputs.insert(path);
+    }
+
+    /// Files are fresh if they were not modified after the previous build wrote its manifest
+    fn is_file_fresh(&self, path: &Path) -> bool {
+        match (get_file_time(path), self.previous_time) {
+            (Some(modified), Some(previous)) => modified <= previous,
+            _ => false,
+        }
+    }
+
+    /// Renders the markdown of the given page with `render` unless a previous build
+    /// already rendered the exact same thing.
+    pub fn render_markdown(
+        &self,
+        page: &mut Page,
+        insert_anchor: InsertAnchor,
+        ctx: &DependencyContext,
+        render: impl FnOnce(&mut Page) -> Result<()>,
+    ) -> Result<()> {
+        let key = self.markdown_key(page, insert_anchor);
+
+        if let Some(entry) = self.previous.markdown.get(&page.file.relative)
+            && entry.key == key
+            && self.is_file_fresh(&page.file.path)
+            && self.are_fresh(&entry.dependencies, ctx)
+        {
+            let cached = &entry.rendered;
+            page.content = cached.content.clone();
+            page.summary = cached.summary.clone();
+            page.toc = cached.toc.clone();
+            page.internal_links = cached.internal_links.clone();
+            page.external_links = cached.external_links.clone();
+            page.citations = cached.citations.clone();
+            page.included_files = cached.included_files.clone();
+            page.responsive_images = cached.responsive_images.clone();
+            self.reused.fetch_add(1, Ordering::Relaxed);
+            self.remember_markdown(page, key, entry.dependencies.clone());
+            return Ok(());
+        }
+
+        let (res, mut dependencies) = track(|| render(page));
+        res?;
+        // Internal links in the markdown itself are resolved without going through a global function
+        if !page.internal_links.is_empty() && !dependencies.contains(&Dependency::Permalinks) {
+            dependencies.push(Dependency::Permalinks);
+        }
+        if let Some(dependencies) = self.hash_dependencies(&dependencies, ctx) {
+            self.remember_markdown(page, key, dependencies);
+        }
+        Ok(())
+    }
+
+    /// Keeps the rendered markdown of that page for the next build
+    fn remember_markdown(&self, page: &Page, key: String, dependencies: Vec<(Dependency, String)>) {
+        let rendered = CachedMarkdown {
+            content: page.content.clone(),
+            summary: page.summary.clone(),
+            toc: page.toc.clone(),
+            internal_links: page.internal_links.clone(),
+            external_links: page.external_links.clone(),
+            citations: page.citations.clone(),
+            included_files: page.included_files.clone(),
+            responsive_images: page.responsive_images.clone(),
+        };
+        self.current
+            .lock()
+            .unwrap()
+            .markdown
+            .insert(page.file.relative.clone(), MarkdownEntry { key, dependencies, rendered });
+    }
+
+    /// Returns the output of `template` for the given context, either from the previous build
+    /// if nothing it depends on changed or by calling `render`. `output` is the path of the
+    /// rendered file relative to the output directory.
+    pub fn render_template(
+        &self,
+        output: &str,
+        template: &str,
+        context: &TeraContext,
+        ctx: &DependencyContext,
+        render: impl FnOnce() -> Result<String>,
+    ) -> Result<String> {
+        let key = hash_str(&format!(
+            "{}{}{}{}",
+            self.fingerprint,
+            output,
+            template,
+            hash_value(&context.clone().into_json())
+        ));
+        let rendered_path = self.path.join(RENDERED_DIR).join(&key);
+
+        if let Some(entry) = self.previous.rendered.get(output)
+            && entry.key == key
+            && self.are_fresh(&entry.dependencies, ctx)
+            && let Ok(rendered) = read_file(&rendered_path)
+        {
+            self.reused.fetch_add(1, Ordering::Relaxed);
+            self.current.lock().unwrap().rendered.insert(
+                output.to_string(),
+                RenderedEntry { key, dependencies: entry.dependencies.clone() },
+            );
+            return Ok(rendered);
+        }
+
+        let (res, dependencies) = track(render);
+        let rendered = res?;
+        if let Some(dependencies) = self.hash_dependencies(&dependencies, ctx) {
+            create_file(&rendered_path, &rendered)?;
+            self.current
+                .lock()
+                .unwrap()
+                .rendered
+                .insert(output.to_string(), RenderedEntry { key, dependencies });
+        }
+        Ok(rendered)
+    }
+
+    /// Removes the files the previous build wrote in the output directory that this one didn't,
+    /// along with the directories left empty
+    pub fn remove_stale_outputs(&self, output_path: &Path) -> Result<()> {
+        let current = self.current.lock().unwrap();
+        for stale in self.previous.outputs.difference(&current.outputs) {
+            let path = output_path.join(stale);
+            if !path.is_file() {
+                continue;
+            }
+            fs::remove_file(&path)
+                .with_context(|| format!("Failed to remove stale output {}", path.display()))?;
+            let mut dir = path.parent();
+            while let Some(d) = dir.filter(|d| *d != output_path) {
+                // Only empty directories can be removed
+                if fs::remove_dir(d).is_err() {
+                    break;
+                }
+                dir = d.parent();
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the manifest for the next build and removes the renders that are not used anymore
+    pub fn save(&self) -> Result<()> {
+        let current = self.current.lock().unwrap();
+        let rendered_dir = self.path.join(RENDERED_DIR);
+        create_directory(&rendered_dir)?;
+
+        let in_use: HashSet<_> = current.rendered.values().map(|entry| &entry.key).collect();
+        for entry in fs::read_dir(&rendered_dir)
+            .with_context(|| format!("Failed to read build cache {}", rendered_dir.display()))?
+            .flatten()
+        {
+            if !in_use.contains(&entry.file_name().to_string_lossy().to_string()) {
+                fs::remove_file(entry.path()).with_context(|| {
+                    format!("Failed to remove {} from the build cache", entry.path().display())
+                })?;
+            }
+        }
+
+        let manifest = serde_json::to_string(&*current)?;
+        create_file(&self.path.join(MANIFEST_FILENAME), &manifest)
+    }
+
+    /// The file itself is checked with `is_file_fresh`
+    fn markdown_key(&self, page: &Page, insert_anchor: InsertAnchor) -> String {
+        let mut hasher = DefaultHasher::new();
+        self.fingerprint.hash(&mut hasher);
+        page.file.relative.hash(&mut hasher);
+        page.permalink.hash(&mut hasher);
+        page.lang.hash(&mut hasher);
+        page.ancestors.hash(&mut hasher);
+        page.serialized_assets.hash(&mut hasher);
+        format!("{:?}", insert_anchor).hash(&mut hasher);
+        format!("{:016x}", hasher.finish())
+    }
+
+    fn are_fresh(&self, dependencies: &[(Dependency, String)], ctx: &DependencyContext) -> bool {
+        dependencies.iter().all(|(dep, hash)| match dep {
+            Dependency::File(path) => self.is_file_fresh(path),
+            _ => self.hash_dependency(dep, ctx).as_ref() == Some(hash),
+        })
+    }
+
+    /// Returns `None` if one of the dependencies cannot be hashed, meaning the result
+    /// cannot be cached.
+    fn hash_dependencies(
+        &self,
+        dependencies: &[Dependency],
+        ctx: &DependencyContext,
+    ) -> Option<Vec<(Dependency, String)>> {
+        dependencies
+            .iter()
+            .map(|dep| self.hash_dependency(dep, ctx).map(|hash| (dep.clone(), hash)))
+            .collect()
+    }
+
+    fn hash_dependency(&self, dep: &Dependency, ctx: &DependencyContext) -> Option<String> {
+        if let Some(hash) = self.hashes.lock().unwrap().get(dep) {
+            return Some(hash.clone());
+        }
+
+        let hash = match dep {
+            Dependency::Volatile => None,
+            // Their freshness is checked with their modification time
+            Dependency::File(path) => path.exists().then(String::new),
+            Dependency::Permalinks => {
+                let mut permalinks: Vec<_> = ctx.permalinks.iter().collect();
+                permalinks.sort();
+                let mut hasher = DefaultHasher::new();
+                permalinks.hash(&mut hasher);
+                Some(format!("{:016x}", hasher.finish()))
+            }
+            Dependency::WikiLinks => {
+                let wiki_links: BTreeMap<_, BTreeMap<_, _>> = ctx
+                    .wiki_links?
+                    .iter()
+                    .map(|(lang, names)| (lang, names.iter().collect()))
+                    .collect();
+                let mut hasher = DefaultHasher::new();
+                wiki_links.hash(&mut hasher);
+                Some(format!("{:016x}", hasher.finish()))
+            }
+            Dependency::Page(path) => {
+                let library = ctx.library?;
+                // A missing page is an error when rendering so it cannot be in the cache
+                let page = library.pages.get(path)?;
+                to_value(page.serialize(library)).ok().map(|v| hash_value(&v))
+            }
+            Dependency::Section(path) => {
+                let library = ctx.library?;
+                let section = library.sections.get(path)?;
+                to_value(section.serialize(library)).ok().map(|v| hash_value(&v))
+            }
+            Dependency::Taxonomy(key) => {
+                let library = ctx.library?;
+                match ctx.taxonomies.iter().find(|t| format!("{}-{}", t.kind.name, t.lang) == *key)
+                {
+                    Some(t) => to_value(t.to_serialized(library)).ok().map(|v| hash_value(&v)),
+                    // Taxonomies are allowed to be missing with `required=false`
+                    None => Some(String::new()),
+                }
+            }
+        }?;
+
+        self.hashes.lock().unwrap().insert(dep.clone(), hash.clone());
+        Some(hash)
+    }
+}
+
+/// Computes the fingerprint of everything that affects the rendering of every page
+pub fn fingerprint(parts: &[&str], tera: &Tera) -> String {
+    let mut hasher = DefaultHasher::new();
+    env!("CARGO_PKG_VERSION").hash(&mut hasher);
+    parts.hash(&mut hasher);
+    let mut templates: Vec<_> = tera.templates.values().collect();
+    templates.sort_by(|a, b| a.name.cmp(&b.name));
+    for tpl in templates {
+        tpl.name.hash(&mut hasher);
+        // The builtin templates don't have a path but are covered by the version
+        if let Some(content) = tpl.path.as_ref().and_then(|p| fs::read(p).ok()) {
+            content.hash(&mut hasher);
+        }
+    }
+    format!("{:016x}", hasher.finish())
+}
+
+fn hash_str(s: &str) -> String {
+    let mut hasher = DefaultHasher::new();
+    s.hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
+
+/// Hashes a JSON value with the keys of objects sorted since some of them come from hashmaps
+fn hash_value(value: &Value) -> String {
+    fn visit(value: &Value, hasher: &mut DefaultHasher) {
+        match value {
+            Value::Null => 0u8.hash(hasher),
+            Value::Bool(b) => b.hash(hasher),
+            Value::Number(n) => n.to_string().hash(hasher),
+            Value::String(s) => s.hash(hasher),
+            Value::Array(items) => {
+                items.len().hash(hasher);
+                for item in items {
+                    visit(item, hasher);
+                }
+            }
+            Value::Object(map) => {
+                let mut keys: Vec<_> = map.keys().collect();
+                keys.sort();
+                keys.len().hash(hasher);
+                for key in keys {
+                    key.hash(hasher);
+                    visit(&map[key], hasher);
+                }
+            }
+        }
+    }
+
+    let mut hasher = DefaultHasher::new();
+    visit(value, &mut hasher);
+    format!("{:016x}", hasher.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hash_value_ignores_key_order() {
+        let a: Value = serde_json::from_str(r#"{"a": 1, "b": {"c": [1, 2], "d": null}}"#).unwrap();
+        let b: Value = serde_json::from_str(r#"{"b": {"d": null, "c": [1, 2]}, "a": 1}"#).unwrap();
+        let c: Value = serde_json::from_str(r#"{"b": {"d": null, "c": [2, 1]}, "a": 1}"#).unwrap();
+        assert_eq!(hash_value(&a), hash_value(&b));
+        assert_ne!(hash_value(&a), hash_value(&c));
+    }
+}
@@ -67,16 +67,21 @@ pub fn render_feeds(
     for feed_filename in &site.config.languages[lang].feed_filenames {
         let mut context = context.clone();
 
-        let feed_url = if let Some(base) = base_path {
-            site.config
-                .make_permalink(&base.join(feed_filename).to_string_lossy().replace('\\', "/"))
-        } else {
-            site.config.make_permalink(feed_filename)
+        let output = match base_path {
+            Some(base) => base.join(feed_filename).to_string_lossy().replace('\\', "/"),
+            None => feed_filename.clone(),
         };
+        let feed_url = site.config.make_permalink(&output);
 
         context.insert("feed_url", &feed_url);
         context = additional_context_fn(context);
-        feeds.push(render_template(feed_filename, &site.tera, context, &site.config.theme)?);
+        feeds.push(site.render_cached(
+            &library,
+            &output,
+            feed_filename,
+            || context.clone(),
+            || render_template(feed_filename, &site.tera, context.clone(), &site.config.theme),
+        )?);
     }
 
     Ok(Some(feeds))
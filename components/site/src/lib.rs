@@ -1,3 +1,4 @@
+pub mod cache;
 pub mod feeds;
 pub mod link_checking;
 mod minify;
@@ -15,20 +16,26 @@ use log;
 use once_cell::sync::Lazy;
 use rayon::prelude::*;
 use tera::{Context, Tera};
+use time::OffsetDateTime;
 use walkdir::{DirEntry, WalkDir};
 
+use cache::{BuildCache, DependencyContext};
 use config::{Config, IndexFormat, get_config};
-use content::{Library, Page, Paginator, Section, Taxonomy};
+use content::{Library, Page, Paginator, PublishState, Section, Taxonomy};
 use errors::{Result, anyhow, bail};
+use link_checking::LinkIssue;
+use markdown::{FenceCache, MarkdownTransform};
 use relative_path::RelativePathBuf;
 use std::time::Instant;
+use templates::dependencies::{self, Dependency};
 use templates::{load_tera, render_redirect_template};
 use utils::fs::{
     clean_site_output_folder, copy_directory, copy_file_if_needed, create_directory, create_file,
+    read_file,
 };
 use utils::net::{get_available_port, is_external_link};
 use utils::templates::{ShortcodeDefinition, render_template};
-use utils::types::InsertAnchor;
+use utils::types::{ContentFormat, InsertAnchor};
 
 pub static SITE_CONTENT: Lazy<Arc<RwLock<HashMap<RelativePathBuf, String>>>> =
     Lazy::new(|| Arc::new(RwLock::new(HashMap::new())));
@@ -67,10 +74,35 @@ pub struct Site {
     pub library: Arc<RwLock<Library>>,
     /// Whether to load draft pages
     include_drafts: bool,
+    /// Whether to load the content whose `publish_date` is in the future
+    include_future: bool,
+    /// Whether to load the content whose `expiry_date` has passed
+    include_expired: bool,
     build_mode: BuildMode,
     shortcode_definitions: HashMap<String, ShortcodeDefinition>,
     /// Whether to check external links
     check_external_links: bool,
+    /// Whether to check again the external links kept in the cache by the previous runs
+    refresh_links: bool,
+    /// The path to the config file, needed to know whether the build cache is still valid
+    config_path: PathBuf,
+    /// Whether to re-use the pages rendered by the previous build, only for `zola build`
+    incremental: bool,
+    pub cache_path: PathBuf,
+    build_cache: Option<BuildCache>,
+    /// Only there when some fence renderers are enabled
+    fence_cache: Option<Arc<FenceCache>>,
+    /// Transforms run on the markdown of every page and section after the builtin ones
+    markdown_transforms: Vec<Arc<dyn MarkdownTransform>>,
+    /// The broken links found when loading the site, external ones are only checked in `zola check`
+    pub link_issues: Vec<LinkIssue>,
+}
+
+/// The error listing all the pages not matching their `page_schema`
+fn schema_errors_message(errors: &[String]) -> String {
+    let messages: Vec<String> =
+        errors.iter().enumerate().map(|(i, msg)| format!("  {}. {}", i + 1, msg)).collect();
+    format!("Found {} front matter error(s)\n{}", messages.len(), messages.join("\n"))
 }
 
 impl Site {
@@ -87,7 +119,7 @@ impl Site {
         }
 
         let tera = load_tera(path, &config)?;
-        let shortcode_definitions = utils::templates::get_shortcodes(&tera);
+        let shortcode_definitions = utils::templates::get_shortcodes(&tera)?;
 
         let content_path = path.join("content");
         let sass_path = path.join("sass");
@@ -95,6 +127,9 @@ impl Site {
         let templates_path = path.join("templates");
         let imageproc = imageproc::Processor::new(path.to_path_buf(), &config);
         let output_path = path.join(config.output_dir.clone());
+        let cache_path = path.join(config.cache_dir.clone());
+        let fence_cache = fence_cache(&config, &cache_path);
+        config.markdown.base_path = Some(path.to_path_buf());
 
         let site = Site {
             base_path: path.to_path_buf(),
@@ -110,11 +145,21 @@ impl Site {
             taxonomies: Vec::new(),
             permalinks: HashMap::new(),
             include_drafts: false,
+            include_future: false,
+            include_expired: false,
             // We will allocate it properly later on
             library: Arc::new(RwLock::new(Library::default())),
             build_mode: BuildMode::Disk,
             shortcode_definitions,
             check_external_links: true,
+            refresh_links: false,
+            config_path: path.join(config_file),
+            incremental: false,
+            cache_path,
+            build_cache: None,
+            fence_cache,
+            markdown_transforms: Vec::new(),
+            link_issues: Vec::new(),
         };
 
         Ok(site)
@@ -127,17 +172,97 @@ impl Site {
         self.build_mode = build_mode;
     }
 
+    /// Registers a transform run on the markdown of every page and section, after the builtin
+    /// ones enabled in `markdown.transforms`.
+    /// Needs to be called before loading the site
+    pub fn add_markdown_transform(&mut self, transform: impl MarkdownTransform + 'static) {
+        self.markdown_transforms.push(Arc::new(transform));
+    }
+
     /// Set the site to load the drafts.
     /// Needs to be called before loading it
     pub fn include_drafts(&mut self) {
         self.include_drafts = true;
     }
 
+    /// Set the site to load the content whose `publish_date` is in the future.
+    /// Needs to be called before loading it
+    pub fn include_future(&mut self) {
+        self.include_future = true;
+    }
+
+    /// Set the site to load the content whose `expiry_date` has passed.
+    /// Needs to be called before loading it
+    pub fn include_expired(&mut self) {
+        self.include_expired = true;
+    }
+
+    /// Whether to load content in that state, logging the scheduled content in `zola serve`
+    fn should_load(&self, state: PublishState, path: &Path) -> bool {
+        let (included, reason) = match state {
+            PublishState::Published => return true,
+            PublishState::Future => (self.include_future, "its `publish_date` is in the future"),
+            PublishState::Expired => (self.include_expired, "its `expiry_date` has passed"),
+        };
+        if self.config.is_in_serve_mode() {
+            let path = path.strip_prefix(&self.base_path).unwrap_or(path);
+            let action = if included { "Showing" } else { "Hiding" };
+            log::info!("{action} {}: {reason}", path.display());
+        }
+        included
+    }
+
     /// Set the site checker to skip external links check.
     pub fn skip_external_links_check(&mut self) {
         self.check_external_links = false;
     }
 
+    /// Set the site checker to ignore the external link checks cached by the previous runs.
+    pub fn refresh_external_links(&mut self) {
+        self.refresh_links = true;
+    }
+
+    /// Re-use the pages rendered by the previous `zola build` when possible.
+    /// Needs to be called before loading the site
+    pub fn enable_build_cache(&mut self) {
+        self.incremental = true;
+    }
+
+    pub fn set_cache_path<P: AsRef<Path>>(&mut self, path: P) {
+        self.cache_path = path.as_ref().to_path_buf();
+        self.fence_cache = fence_cache(&self.config, &self.cache_path);
+    }
+
+    /// The cache used for this build, if the site was built with `enable_build_cache`
+    pub fn build_cache(&self) -> Option<&BuildCache> {
+        self.build_cache.as_ref()
+    }
+
+    /// Everything that can change the output of every page: if any of it changes,
+    /// the build cache is thrown away
+    fn build_fingerprint(&self) -> Result<String> {
+        let config_content = read_file(&self.config_path)?;
+        let theme_content = match self.config.theme {
+            Some(ref theme) => {
+                read_file(&self.base_path.join("themes").join(theme).join("theme.toml"))?
+            }
+            None => String::new(),
+        };
+        Ok(cache::fingerprint(
+            &[
+                &config_content,
+                &theme_content,
+                &self.config.base_url,
+                &self.config.minify_html.to_string(),
+                &self.include_drafts.to_string(),
+                &self.include_future.to_string(),
+                &self.include_expired.to_string(),
+                &self.output_path.to_string_lossy(),
+            ],
+            &self.tera,
+        ))
+    }
+
     /// The index sections are ALWAYS at those paths
     /// There are one index section for the default language + 1 per language
     fn index_section_paths(&self) -> Vec<(PathBuf, Option<&str>)> {
@@ -163,6 +288,7 @@ impl Site {
     /// Reloads the templates and rebuild the site without re-markdown the Markdown.
     pub fn reload_templates(&mut self) -> Result<()> {
         self.tera.full_reload()?;
+        self.shortcode_definitions = utils::templates::get_shortcodes(&self.tera)?;
         // TODO: be smarter than that, no need to recompile sass for example
         self.build()
     }
@@ -185,7 +311,12 @@ impl Site {
     /// out of them
     pub fn load(&mut self) -> Result<()> {
         self.library = Arc::new(RwLock::new(Library::new(&self.config)));
+        if self.incremental && self.build_mode == BuildMode::Disk {
+            self.build_cache = Some(BuildCache::load(&self.cache_path, self.build_fingerprint()?));
+        }
         let mut pages_insert_anchors = HashMap::new();
+        // Scheduled content is published relative to the build time
+        let now = OffsetDateTime::now_utc();
 
         // not the most elegant loop, but this is necessary to use skip_current_dir
         // which we can only decide to use after we've deserialised the section
@@ -232,8 +363,10 @@ impl Site {
                 continue;
             }
 
-            // skip hidden files and non md files
-            if !path.is_dir() && (!file_name.ends_with(".md") || file_name.starts_with('.')) {
+            // skip hidden files and files in none of the content formats
+            if !path.is_dir()
+                && (self.config.content_format(path).is_none() || file_name.starts_with('.'))
+            {
                 continue;
             }
 
@@ -273,6 +406,12 @@ impl Site {
                         continue;
                     }
 
+                    // same for sections not published yet or anymore
+                    if !self.should_load(section.meta.publish_state(now), &section.file.path) {
+                        dir_walker.skip_current_dir();
+                        continue;
+                    }
+
                     self.add_section(section, false)?;
                 }
             } else {
@@ -282,15 +421,23 @@ impl Site {
         }
         self.create_default_index_sections()?;
 
-        for page in pages {
+        let mut schema_errors = Vec::new();
+        for mut page in pages {
             // should we skip drafts?
             if page.meta.draft && !self.include_drafts {
                 continue;
             }
 
+            if !self.should_load(page.meta.publish_state(now), &page.file.path) {
+                continue;
+            }
+
             // We are only checking it on load and not in add_page since we have access to
             // all the components there.
-            if page.file.filename == "index.md" {
+            if ContentFormat::EXTENSIONS
+                .iter()
+                .any(|ext| page.file.filename == format!("index.{ext}"))
+            {
                 let is_invalid = match page.components.last() {
                     Some(_) => sections.contains(&page.components.join("/")),
                     // content/index.md is always invalid, but content/colocated/index.md is ok
@@ -299,7 +446,8 @@ impl Site {
 
                 if is_invalid {
                     bail!(
-                        "We can't have a page called `index.md` in the same folder as an index section in {:?}",
+                        "We can't have a page called `{}` in the same folder as an index section in {:?}",
+                        page.file.filename,
                         page.file.parent
                     );
                 }
@@ -309,7 +457,17 @@ impl Site {
                 page.file.path.clone(),
                 self.find_parent_section_insert_anchor(&page.file.parent.clone(), &page.lang),
             );
-            self.add_page(page, false)?;
+            // We report the front matter errors of all the pages at once, without inserting the
+            // invalid pages in the library
+            let errors = self.apply_ancestor_front_matter(&mut page);
+            if errors.is_empty() {
+                self.insert_page(page, false)?;
+            } else {
+                schema_errors.extend(errors);
+            }
+        }
+        if !schema_errors.is_empty() {
+            bail!(schema_errors_message(&schema_errors));
         }
 
         {
@@ -330,19 +488,24 @@ impl Site {
         self.populate_taxonomies()?;
         tpls::register_early_global_fns(self)?;
         self.populate_sections();
+        self.populate_paginate_sources()?;
         self.render_markdown()?;
         {
             let mut lib = self.library.write().unwrap();
             lib.fill_backlinks();
+            lib.populate_related(&self.config);
         }
         tpls::register_tera_global_fns(self);
 
         // Needs to be done after rendering markdown as we only get the anchors at that point
-        let internal_link_messages = link_checking::check_internal_links_with_anchors(self);
+        let internal_link_issues = link_checking::check_internal_links_with_anchors(self);
+        self.link_issues = internal_link_issues.clone();
+        // We only error out once all the links are checked so `zola check` reports all of them
+        let mut link_errors = Vec::new();
 
         // log any broken internal links and error out if needed
-        if !internal_link_messages.is_empty() {
-            let messages: Vec<String> = internal_link_messages
+        if !internal_link_issues.is_empty() {
+            let messages: Vec<String> = internal_link_issues
                 .iter()
                 .enumerate()
                 .map(|(i, msg)| format!("  {}. {}", i + 1, msg))
@@ -354,15 +517,16 @@ impl Site {
             );
             match self.config.link_checker.internal_level {
                 config::LinkCheckerLevel::Warn => log::warn!("{msg}"),
-                config::LinkCheckerLevel::Error => return Err(anyhow!(msg)),
+                config::LinkCheckerLevel::Error => link_errors.push(msg),
             }
         }
 
         // check external links, log the results, and error out if needed
         if self.config.is_in_check_mode() && self.check_external_links {
-            let external_link_messages = link_checking::check_external_links(self);
-            if !external_link_messages.is_empty() {
-                let messages: Vec<String> = external_link_messages
+            let external_link_issues = link_checking::check_external_links(self)?;
+            self.link_issues.extend(external_link_issues.iter().cloned());
+            if !external_link_issues.is_empty() {
+                let messages: Vec<String> = external_link_issues
                     .iter()
                     .enumerate()
                     .map(|(i, msg)| format!("  {}. {}", i + 1, msg))
@@ -374,11 +538,15 @@ impl Site {
                 );
                 match self.config.link_checker.external_level {
                     config::LinkCheckerLevel::Warn => log::warn!("{msg}"),
-                    config::LinkCheckerLevel::Error => return Err(anyhow!(msg)),
+                    config::LinkCheckerLevel::Error => link_errors.push(msg),
                 }
             }
         }
 
+        if !link_errors.is_empty() {
+            return Err(anyhow!(link_errors.join("\n")));
+        }
+
         Ok(())
     }
 
@@ -448,6 +616,21 @@ impl Site {
             );
         }
 
+        let wiki_links = if config.markdown.wiki_links {
+            self.library.read().unwrap().find_wiki_link_targets()
+        } else {
+            HashMap::new()
+        };
+        let no_wiki_links = HashMap::new();
+
+        let build_cache = self.build_cache.as_ref();
+        let dependency_context = DependencyContext {
+            library: None,
+            taxonomies: &self.taxonomies,
+            permalinks,
+            wiki_links: Some(&wiki_links),
+        };
+
         let mut library = self.library.write().expect("Get lock for render_markdown");
         library
             .pages
@@ -456,13 +639,45 @@ impl Site {
             .par_iter_mut()
             .map(|page| {
                 let insert_anchor = pages_insert_anchors[&page.file.path];
-                page.render_markdown(
-                    permalinks,
-                    tera,
-                    config,
-                    insert_anchor,
-                    &self.shortcode_definitions,
-                )
+                let render = |page: &mut Page| {
+                    page.render_markdown(
+                        permalinks,
+                        tera,
+                        config,
+                        insert_anchor,
+                        &self.shortcode_definitions,
+                        wiki_links.get(&page.lang).unwrap_or(&no_wiki_links),
+                        Some(&self.imageproc),
+                        self.fence_cache.as_ref(),
+                        &self.markdown_transforms,
+                    )?;
+                    // Wiki links can point to any page depending on their filename, slug and title
+                    if config.markdown.wiki_links && page.raw_content.contains("[[") {
+                        dependencies::record(Dependency::WikiLinks);
+                    }
+                    // The resized versions only change with the images, the processed images
+                    // of the pages re-used from the cache being kept by `process_images`
+                    for path in &page.responsive_images {
+                        dependencies::record(Dependency::File(path.clone()));
+                    }
+                    // The bibliography is read without going through a global function
+                    if !page.citations.is_empty()
+                        && let Some(path) = page.bibliography_path(config)
+                    {
+                        dependencies::record(Dependency::File(path));
+                    }
+                    // Same for the files included in code blocks
+                    for path in &page.included_files {
+                        dependencies::record(Dependency::File(path.clone()));
+                    }
+                    Ok(())
+                };
+                match build_cache {
+                    Some(cache) => {
+                        cache.render_markdown(page, insert_anchor, &dependency_context, render)
+                    }
+                    None => render(page),
+                }
             })
             .collect::<Result<()>>()?;
 
@@ -472,36 +687,93 @@ impl Site {
             .collect::<Vec<_>>()
             .par_iter_mut()
             .map(|section| {
-                section.render_markdown(permalinks, tera, config, &self.shortcode_definitions)
+                section.render_markdown(
+                    permalinks,
+                    tera,
+                    config,
+                    &self.shortcode_definitions,
+                    wiki_links.get(&section.lang).unwrap_or(&no_wiki_links),
+                    Some(&self.imageproc),
+                    self.fence_cache.as_ref(),
+                    &self.markdown_transforms,
+                )
             })
             .collect::<Result<()>>()?;
 
         Ok(())
     }
 
+    /// The pages wiki links of that language can point to, when they are enabled
+    fn find_wiki_link_targets(&self, lang: &str) -> HashMap<String, Vec<String>> {
+        if !self.config.markdown.wiki_links {
+            return HashMap::new();
+        }
+        self.library.read().unwrap().find_wiki_link_targets().remove(lang).unwrap_or_default()
+    }
+
+    /// Applies the `[cascade]` of the sections above the page and checks it against the closest
+    /// `page_schema` and the taxonomies of the config, returning all the problems found
+    fn apply_ancestor_front_matter(&self, page: &mut Page) -> Vec<String> {
+        let library = self.library.read().unwrap();
+        // The values of the page win over the ones of the `[cascade]` of its sections
+        for cascade in
+            library.cascades_for(&page.file.path, &page.file.parent, &page.lang, &self.config)
+        {
+            page.meta.apply_cascade(cascade);
+        }
+
+        let mut errors: Vec<_> =
+            match library.page_schema_for(&page.file.parent, &page.lang, &self.config) {
+                Some(schema) => schema
+                    .check(&mut page.meta.extra, &mut page.meta.taxonomies)
+                    .into_iter()
+                    .map(|error| {
+                        format!("Invalid front matter in `{}`: {}", page.file.relative, error)
+                    })
+                    .collect(),
+                None => Vec::new(),
+            };
+
+        let mut taxonomies: Vec<_> = page.meta.taxonomies.keys().collect();
+        taxonomies.sort();
+        for taxa_name in taxonomies {
+            if !self.config.has_taxonomy(taxa_name, &page.lang) {
+                errors.push(format!(
+                    "Invalid front matter in `{}`: taxonomy `{}` is not defined in config.toml",
+                    page.file.relative, taxa_name
+                ));
+            }
+        }
+        errors
+    }
+
     /// Add a page to the site
     /// The `render` parameter is used in the serve command with --fast, when rebuilding a page.
     pub fn add_page(&mut self, mut page: Page, render_md: bool) -> Result<()> {
-        for taxa_name in page.meta.taxonomies.keys() {
-            if !self.config.has_taxonomy(taxa_name, &page.lang) {
-                bail!(
-                    "Page `{}` has taxonomy `{}` which is not defined in config.toml",
-                    page.file.path.display(),
-                    taxa_name
-                );
-            }
+        let schema_errors = self.apply_ancestor_front_matter(&mut page);
+        if !schema_errors.is_empty() {
+            bail!(schema_errors_message(&schema_errors));
         }
+        self.insert_page(page, render_md)
+    }
 
+    /// Adds a page whose `[cascade]`, `page_schema` and taxonomies were already checked
+    fn insert_page(&mut self, mut page: Page, render_md: bool) -> Result<()> {
         self.permalinks.insert(page.file.relative.clone(), page.permalink.clone());
         if render_md {
             let insert_anchor =
                 self.find_parent_section_insert_anchor(&page.file.parent, &page.lang);
+            let wiki_links = self.find_wiki_link_targets(&page.lang);
             page.render_markdown(
                 &self.permalinks,
                 &self.tera,
                 &self.config,
                 insert_anchor,
                 &self.shortcode_definitions,
+                &wiki_links,
+                Some(&self.imageproc),
+                self.fence_cache.as_ref(),
+                &self.markdown_transforms,
             )?;
         }
 
@@ -519,6 +791,8 @@ impl Site {
         self.add_page(page, true)?;
         self.populate_sections();
         self.populate_taxonomies()?;
+        self.populate_paginate_sources()?;
+        self.library.write().unwrap().populate_related(&self.config);
         let library = self.library.read().unwrap();
         let page = library.pages.get(path).unwrap();
         self.render_page(page)
@@ -527,13 +801,29 @@ impl Site {
     /// Add a section to the site
     /// The `render` parameter is used in the serve command with --fast, when rebuilding a page.
     pub fn add_section(&mut self, mut section: Section, render_md: bool) -> Result<()> {
+        // The sections above are always added before their subsections
+        if let Some(ref grand_parent) = section.file.grand_parent {
+            for cascade in self.library.read().unwrap().cascades_for(
+                &section.file.path,
+                grand_parent,
+                &section.lang,
+                &self.config,
+            ) {
+                section.meta.apply_cascade(cascade);
+            }
+        }
         self.permalinks.insert(section.file.relative.clone(), section.permalink.clone());
         if render_md {
+            let wiki_links = self.find_wiki_link_targets(&section.lang);
             section.render_markdown(
                 &self.permalinks,
                 &self.tera,
                 &self.config,
                 &self.shortcode_definitions,
+                &wiki_links,
+                Some(&self.imageproc),
+                self.fence_cache.as_ref(),
+                &self.markdown_transforms,
             )?;
         }
         let mut library = self.library.write().expect("Get lock for add_section");
@@ -549,6 +839,7 @@ impl Site {
         let section = Section::from_file(path, &self.config, &self.base_path)?;
         self.add_section(section, true)?;
         self.populate_sections();
+        self.populate_paginate_sources()?;
         let library = self.library.read().unwrap();
         let section = library.sections.get(path).unwrap();
         self.render_section(section, true)
@@ -582,6 +873,13 @@ impl Site {
         library.populate_sections(&self.config, &self.content_path);
     }
 
+    /// Find the pages of the sections using `paginate_source`, needs the sections and taxonomies
+    /// to be populated first
+    pub fn populate_paginate_sources(&mut self) -> Result<()> {
+        let mut library = self.library.write().expect("Get lock for populate_paginate_sources");
+        library.populate_paginate_sources(&self.taxonomies, &self.content_path)
+    }
+
     /// Find all the tags and categories if it's asked in the config
     pub fn populate_taxonomies(&mut self) -> Result<()> {
         self.taxonomies = self.library.read().unwrap().find_taxonomies(&self.config);
@@ -606,36 +904,53 @@ impl Site {
     /// Copy the main `static` folder and the theme `static` folder if a theme is used
     pub fn copy_static_directories(&self) -> Result<()> {
         // The user files will overwrite the theme files
+        let mut copied = Vec::new();
         if let Some(ref theme) = self.config.theme {
-            copy_directory(
+            copied.extend(copy_directory(
                 &self.base_path.join("themes").join(theme).join("static"),
                 &self.output_path,
                 false,
                 None,
-            )?;
+            )?);
         }
         // We're fine with missing static folders
         if self.static_path.exists() {
             if let Some(gs) = &self.config.ignored_static_globset {
-                copy_directory(
+                copied.extend(copy_directory(
                     &self.static_path,
                     &self.output_path,
                     self.config.hard_link_static,
                     Some(gs),
-                )?;
+                )?);
             } else {
-                copy_directory(
+                copied.extend(copy_directory(
                     &self.static_path,
                     &self.output_path,
                     self.config.hard_link_static,
                     None,
-                )?;
+                )?);
             }
         }
+        for path in copied {
+            self.record_output(&self.output_path.join(path));
+        }
 
         Ok(())
     }
 
+    /// The canonical paths of the files included in code blocks of the content, which `zola serve`
+    /// compares to the paths of file system events, even once the files are deleted
+    pub fn included_files(&self) -> HashSet<PathBuf> {
+        let library = self.library.read().expect("Get lock for included_files");
+        library
+            .pages
+            .values()
+            .flat_map(|p| &p.included_files)
+            .chain(library.sections.values().flat_map(|s| &s.included_files))
+            .cloned()
+            .collect()
+    }
+
     pub fn num_img_ops(&self) -> usize {
         let imageproc = self.imageproc.lock().expect("Couldn't lock imageproc (num_img_ops)");
         imageproc.num_img_ops()
@@ -644,10 +959,27 @@ impl Site {
     pub fn process_images(&self) -> Result<()> {
         let mut imageproc =
             self.imageproc.lock().expect("Couldn't lock imageproc (process_images)");
-        imageproc.prune()?;
+        // The pages re-used from the build cache don't enqueue their responsive images so we
+        // can only know which processed images are stale on full builds
+        if self.build_cache.is_none() {
+            imageproc.prune()?;
+        }
         imageproc.do_process()
     }
 
+    /// Writes the fences rendered by the fence renderers in the cache and removes the stale ones
+    pub fn process_fences(&self) -> Result<()> {
+        let Some(ref fence_cache) = self.fence_cache else {
+            return Ok(());
+        };
+        // The pages re-used from the build cache don't render their fences so we can only
+        // know which ones are stale on full builds
+        if self.build_cache.is_none() {
+            fence_cache.prune()?;
+        }
+        fence_cache.do_process()
+    }
+
     /// Deletes the `public` directory if it exists and the `preserve_dotfiles_in_output` option is set to false,
     /// or if set to true: its contents except for the dotfiles at the root level.
     pub fn clean(&self) -> Result<()> {
@@ -682,6 +1014,7 @@ impl Site {
             BuildMode::Disk | BuildMode::Both => {
                 let end_path = current_path.join(filename);
                 create_file(&end_path, &final_content)?;
+                self.record_output(&end_path);
             }
             _ => (),
         }
@@ -698,16 +1031,47 @@ impl Site {
         Ok(current_path)
     }
 
+    /// Keeps track of a file written in the output directory so the next incremental build
+    /// can remove it if it isn't generated anymore
+    fn record_output(&self, path: &Path) {
+        if let Some(ref cache) = self.build_cache
+            && let Ok(relative) = path.strip_prefix(&self.output_path)
+        {
+            cache.record_output(relative);
+        }
+    }
+
+    /// Renders a template with `render`, re-using the output of the previous build instead
+    /// if the build cache is enabled and nothing it depends on changed.
+    /// `output` is the path of the rendered file relative to the output directory.
+    fn render_cached(
+        &self,
+        library: &Library,
+        output: &str,
+        template: &str,
+        context: impl FnOnce() -> Context,
+        render: impl FnOnce() -> Result<String>,
+    ) -> Result<String> {
+        let Some(ref cache) = self.build_cache else {
+            return render();
+        };
+        let dependency_context = DependencyContext {
+            library: Some(library),
+            taxonomies: &self.taxonomies,
+            permalinks: &self.permalinks,
+            wiki_links: None,
+        };
+        cache.render_template(output, template, &context(), &dependency_context, render)
+    }
+
     fn copy_assets(&self, parent: &Path, assets: &[impl AsRef<Path>], dest: &Path) -> Result<()> {
         for asset in assets {
             let asset_path = asset.as_ref();
-            copy_file_if_needed(
-                asset_path,
-                &dest.join(
-                    asset_path.strip_prefix(parent).expect("Couldn't get filename from page asset"),
-                ),
-                self.config.hard_link_static,
-            )?;
+            let target = dest.join(
+                asset_path.strip_prefix(parent).expect("Couldn't get filename from page asset"),
+            );
+            copy_file_if_needed(asset_path, &target, self.config.hard_link_static)?;
+            self.record_output(&target);
         }
 
         Ok(())
@@ -719,10 +1083,24 @@ impl Site {
             return Ok(());
         }
 
-        let output = page.render_html(&self.tera, &self.config, &self.library.read().unwrap())?;
-        let content = self.inject_livereload(output);
+        let library = self.library.read().unwrap();
         let components: Vec<&str> = page.path.split('/').collect();
+        let output = self.render_cached(
+            &library,
+            &output_name(&components, "index.html"),
+            page.template_name(),
+            || page.template_context(&self.config, &library),
+            || page.render_html(&self.tera, &self.config, &library),
+        )?;
+        let content = self.inject_livereload(output);
         let current_path = self.write_content(&components, "index.html", content)?;
+        for format in page.output_formats(&self.config) {
+            if let Some(output) =
+                page.render_output_format(format, &self.tera, &self.config, &library)?
+            {
+                self.write_content(&components, &format!("index.{}", format), output)?;
+            }
+        }
 
         // Copy any asset we found previously into the same directory as the index.html
         self.copy_assets(page.file.path.parent().unwrap(), &page.assets, &current_path)?;
@@ -733,8 +1111,10 @@ impl Site {
     /// Deletes the `public` directory (only for `zola build`) and builds the site
     pub fn build(&self) -> Result<()> {
         let mut start = Instant::now();
-        // Do not clean on `zola serve` otherwise we end up copying assets all the time
-        if self.build_mode == BuildMode::Disk {
+        // Do not clean on `zola serve` otherwise we end up copying assets all the time.
+        // Incremental builds only remove what is not generated anymore at the end instead.
+        let keep_output = self.build_cache.as_ref().is_some_and(|c| c.has_previous_build());
+        if self.build_mode == BuildMode::Disk && !keep_output {
             self.clean()?;
         }
         start = log_time(start, "Cleaned folder");
@@ -743,13 +1123,17 @@ impl Site {
         if let Some(ref theme) = self.config.theme {
             let theme_path = self.base_path.join("themes").join(theme);
             if theme_path.join("sass").exists() {
-                sass::compile_sass(&theme_path, &self.output_path)?;
+                for path in sass::compile_sass(&theme_path, &self.output_path)? {
+                    self.record_output(&path);
+                }
                 start = log_time(start, "Compiled theme Sass");
             }
         }
 
         if self.config.compile_sass {
-            sass::compile_sass(&self.base_path, &self.output_path)?;
+            for path in sass::compile_sass(&self.base_path, &self.output_path)? {
+                self.record_output(&path);
+            }
             start = log_time(start, "Compiled own Sass");
         }
 
@@ -804,9 +1188,15 @@ impl Site {
         // or from templates
         self.process_images()?;
         start = log_time(start, "Processed images");
+        self.process_fences()?;
         // Processed images will be in static so the last step is to copy it
         self.copy_static_directories()?;
-        log_time(start, "Copied static dir");
+        start = log_time(start, "Copied static dir");
+        if let Some(ref cache) = self.build_cache {
+            cache.remove_stale_outputs(&self.output_path)?;
+            cache.save()?;
+            log_time(start, "Saved build cache");
+        }
 
         Ok(())
     }
@@ -853,7 +1243,9 @@ impl Site {
                     format!("window.searchIndex = {}", content)
                 }
             },
-        )
+        )?;
+        self.record_output(path);
+        Ok(())
     }
 
     pub fn build_search_index(&self) -> Result<()> {
@@ -872,7 +1264,9 @@ impl Site {
         match self.config.search.index_format {
             IndexFormat::ElasticlunrJavascript | IndexFormat::ElasticlunrJson => {
                 // then elasticlunr.min.js
-                create_file(&self.output_path.join("elasticlunr.min.js"), search::ELASTICLUNR_JS)?;
+                let path = self.output_path.join("elasticlunr.min.js");
+                create_file(&path, search::ELASTICLUNR_JS)?;
+                self.record_output(&path);
             }
             _ => {}
         }
@@ -963,12 +1357,17 @@ impl Site {
 
         components.push(taxonomy.slug.as_ref());
 
-        let list_output =
-            taxonomy.render_all_terms(&self.tera, &self.config, &self.library.read().unwrap())?;
+        let library = self.library.read().unwrap();
+        let list_output = self.render_cached(
+            &library,
+            &output_name(&components, "index.html"),
+            "taxonomy_list.html",
+            || taxonomy.list_context(&self.config, &library),
+            || taxonomy.render_all_terms(&self.tera, &self.config, &library),
+        )?;
         let content = self.inject_livereload(list_output);
         self.write_content(&components, "index.html", content)?;
 
-        let library = self.library.read().unwrap();
         taxonomy
             .items
             .par_iter()
@@ -988,8 +1387,13 @@ impl Site {
                         ),
                     )?;
                 } else {
-                    let single_output =
-                        taxonomy.render_term(item, &self.tera, &self.config, &library)?;
+                    let single_output = self.render_cached(
+                        &library,
+                        &output_name(&comp, "index.html"),
+                        "taxonomy_single.html",
+                        || taxonomy.term_context(item, &self.config, &library),
+                        || taxonomy.render_term(item, &self.tera, &self.config, &library),
+                    )?;
                     let content = self.inject_livereload(single_output);
                     self.write_content(&comp, "index.html", content)?;
                 }
@@ -1179,15 +1583,51 @@ impl Site {
         }
 
         if section.meta.is_paginated() {
-            self.render_paginated(
-                components,
-                &Paginator::from_section(section, &self.library.read().unwrap()),
-            )?;
-        } else {
-            let output =
-                section.render_html(&self.tera, &self.config, &self.library.read().unwrap())?;
-            let content = self.inject_livereload(output);
-            self.write_content(&components, "index.html", content)?;
+            let library = self.library.read().unwrap();
+            let paginator = Paginator::from_section(section, &library);
+            self.render_paginated(components.clone(), &paginator)?;
+            // Every pager is rendered to the other formats as well, the first one next to
+            // the HTML of the section as there is no redirect for them
+            for format in section.output_formats(&self.config) {
+                for pager in &paginator.pagers {
+                    let Some(output) = paginator.render_pager_output_format(
+                        pager,
+                        format,
+                        &self.config,
+                        &self.tera,
+                        &library,
+                    )?
+                    else {
+                        break;
+                    };
+                    let pager_path = format!("{}", pager.index);
+                    let mut pager_components = components.clone();
+                    if pager.index > 1 {
+                        pager_components.push(&paginator.paginate_path);
+                        pager_components.push(&pager_path);
+                    }
+                    self.write_content(&pager_components, &format!("index.{}", format), output)?;
+                }
+            }
+            return Ok(());
+        }
+
+        let library = self.library.read().unwrap();
+        let output = self.render_cached(
+            &library,
+            &output_name(&components, "index.html"),
+            section.get_template_name(),
+            || section.template_context(&self.config, &library),
+            || section.render_html(&self.tera, &self.config, &library),
+        )?;
+        let content = self.inject_livereload(output);
+        self.write_content(&components, "index.html", content)?;
+        for format in section.output_formats(&self.config) {
+            if let Some(output) =
+                section.render_output_format(format, &self.tera, &self.config, &library)?
+            {
+                self.write_content(&components, &format!("index.{}", format), output)?;
+            }
         }
 
         Ok(())
@@ -1230,11 +1670,13 @@ impl Site {
                 pager_components.push(&paginator.paginate_path);
                 let pager_path = format!("{}", pager.index);
                 pager_components.push(&pager_path);
-                let output = paginator.render_pager(
-                    pager,
-                    &self.config,
-                    &self.tera,
-                    &self.library.read().unwrap(),
+                let library = self.library.read().unwrap();
+                let output = self.render_cached(
+                    &library,
+                    &output_name(&pager_components, "index.html"),
+                    &paginator.template,
+                    || paginator.pager_context(pager, &self.config, &library),
+                    || paginator.render_pager(pager, &self.config, &self.tera, &library),
                 )?;
                 let content = self.inject_livereload(output);
 
@@ -1255,6 +1697,25 @@ impl Site {
     }
 }
 
+/// Where the output of the fence renderers enabled in the config is kept between builds
+fn fence_cache(config: &Config, cache_path: &Path) -> Option<Arc<FenceCache>> {
+    if config.markdown.fence_renderers.is_empty() {
+        return None;
+    }
+    Some(Arc::new(FenceCache::new(cache_path.join("fences"))))
+}
+
+/// The path of a file written with `write_content`, relative to the output directory
+fn output_name(components: &[&str], filename: &str) -> String {
+    components
+        .iter()
+        .chain(std::iter::once(&filename))
+        .filter(|c| !c.is_empty())
+        .copied()
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
 fn log_time(start: Instant, message: &str) -> Instant {
     let now = Instant::now();
     log::debug!("{} took {}ms", message, now.duration_since(start).as_millis());
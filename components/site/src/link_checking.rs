@@ -1,4 +1,5 @@
 use core::time;
+use std::fmt;
 use std::path::{Path, PathBuf};
 use std::{cmp, collections::HashMap, collections::HashSet, iter::FromIterator, thread};
 
@@ -6,12 +7,105 @@ use config::LinkCheckerLevel;
 use globset::GlobSet;
 use log;
 use rayon::prelude::*;
+use serde::Serialize;
+use serde_json::json;
 
 use crate::Site;
-use errors::{Result, bail};
+use errors::{Result, anyhow, bail};
 use rayon;
 use url::Url;
 use utils::anchors::is_special_anchor;
+use utils::fs::read_file;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LinkKind {
+    /// A link to an anchor of a page or section of the site
+    Internal,
+    /// A link to another website
+    External,
+}
+
+/// A broken link found while checking the site
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct LinkIssue {
+    pub kind: LinkKind,
+    /// The markdown file containing the link
+    pub file: PathBuf,
+    /// Where the link is in the file, 1-indexed. `None` if it couldn't be found in the file,
+    /// eg if it comes from a shortcode
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    /// The link as written in the markdown file
+    pub target: String,
+    /// The HTTP status code returned for external links, if we got a response
+    pub status: Option<u16>,
+    /// What is wrong with the link
+    pub message: String,
+    pub severity: LinkCheckerLevel,
+}
+
+impl fmt::Display for LinkIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            LinkKind::Internal => write!(
+                f,
+                "The anchor in the link `{}` in {} does not exist.",
+                self.target,
+                self.file.to_string_lossy()
+            ),
+            LinkKind::External => write!(
+                f,
+                "Broken link in {} to {} : {}",
+                self.file.to_string_lossy(),
+                self.target,
+                self.message
+            ),
+        }
+    }
+}
+
+fn sort_issues(issues: &mut [LinkIssue]) {
+    issues.sort_by(|a, b| {
+        (&a.file, a.line, a.column, &a.target).cmp(&(&b.file, b.line, b.column, &b.target))
+    });
+}
+
+/// Finds where links are in the markdown files.
+/// Looking up the same link several times in a file returns its successive occurrences.
+#[derive(Default)]
+struct LinkLocator {
+    files: HashMap<PathBuf, Option<String>>,
+    cursors: HashMap<(PathBuf, String), usize>,
+}
+
+impl LinkLocator {
+    /// Returns the 1-indexed line and column of the first of the `needles` found in the file
+    fn locate(&mut self, file_path: &Path, needles: &[&str]) -> (Option<usize>, Option<usize>) {
+        let content = match self
+            .files
+            .entry(file_path.to_path_buf())
+            .or_insert_with(|| read_file(file_path).ok())
+        {
+            Some(c) => c,
+            None => return (None, None),
+        };
+
+        for needle in needles {
+            let cursor =
+                self.cursors.entry((file_path.to_path_buf(), needle.to_string())).or_insert(0);
+            if let Some(pos) = content[*cursor..].find(needle) {
+                let offset = *cursor + pos;
+                *cursor = offset + needle.len();
+                let before = &content[..offset];
+                let line = before.matches('\n').count() + 1;
+                let column = before.rsplit('\n').next().unwrap_or_default().chars().count() + 1;
+                return (Some(line), Some(column));
+            }
+        }
+        (None, None)
+    }
+}
 
 /// Check whether all internal links pointing to explicit anchor fragments are valid.
 ///
@@ -19,7 +113,7 @@ use utils::anchors::is_special_anchor;
 /// is always performed (while external ones only conditionally in `zola check`).  If broken links
 /// are encountered, the `internal_level` setting in config.toml will determine whether they are
 /// treated as warnings or errors.
-pub fn check_internal_links_with_anchors(site: &Site) -> Vec<String> {
+pub fn check_internal_links_with_anchors(site: &Site) -> Vec<LinkIssue> {
     log::info!("Checking all internal links with anchors.");
     let library = site.library.write().expect("Get lock for check_internal_links_with_anchors");
 
@@ -80,29 +174,38 @@ pub fn check_internal_links_with_anchors(site: &Site) -> Vec<String> {
         }
     });
 
-    // Format faulty entries into error messages, and collect them.
-    let messages = missing_targets
+    // Turn faulty entries into issues, and collect them.
+    let mut locator = LinkLocator::default();
+    let mut issues = missing_targets
         .map(|(page_path, md_path, anchor)| {
-            format!(
-                "The anchor in the link `@/{}#{}` in {} does not exist.",
-                md_path,
-                anchor,
-                page_path.to_string_lossy(),
-            )
+            let target = format!("@/{}#{}", md_path, anchor);
+            // Links to an anchor of the same file can be written as just the anchor
+            let (line, column) = locator.locate(&page_path, &[&target, &format!("#{}", anchor)]);
+            LinkIssue {
+                kind: LinkKind::Internal,
+                file: page_path,
+                line,
+                column,
+                target,
+                status: None,
+                message: "The anchor does not exist".to_string(),
+                severity: site.config.link_checker.internal_level.clone(),
+            }
         })
         .collect::<Vec<_>>();
+    sort_issues(&mut issues);
 
     // Finally emit a summary, and return overall anchors-checking result.
-    if messages.is_empty() {
+    if issues.is_empty() {
         log::info!("> Successfully checked {} internal link(s) with anchors.", anchors_total);
     } else {
         log::warn!(
             "> Checked {} internal link(s) with anchors: {} target(s) missing.",
             anchors_total,
-            messages.len(),
+            issues.len(),
         );
     }
-    messages
+    issues
 }
 
 fn should_skip_by_prefix(link: &str, skip_prefixes: &[String]) -> bool {
@@ -123,9 +226,9 @@ fn get_link_domain(link: &str) -> Result<String> {
     }
 }
 
-/// Checks all external links and returns all the errors that were encountered.
+/// Checks all external links and returns all the broken ones.
 /// Empty vec == all good
-pub fn check_external_links(site: &Site) -> Vec<String> {
+pub fn check_external_links(site: &Site) -> Result<Vec<LinkIssue>> {
     let library = site.library.write().expect("Get lock for check_external_links");
 
     struct LinkDef {
@@ -144,7 +247,22 @@ pub fn check_external_links(site: &Site) -> Vec<String> {
         }
     }
 
-    let mut messages: Vec<String> = vec![];
+    let severity = site.config.link_checker.external_level.clone();
+    let mut locator = LinkLocator::default();
+    let mut issues: Vec<LinkIssue> = vec![];
+    let mut add_issue = |file_path: &Path, link: &str, status: Option<u16>, message: String| {
+        let (line, column) = locator.locate(file_path, &[link]);
+        issues.push(LinkIssue {
+            kind: LinkKind::External,
+            file: file_path.to_path_buf(),
+            line,
+            column,
+            target: link.to_string(),
+            status,
+            message,
+            severity: severity.clone(),
+        });
+    };
     let mut external_links = Vec::new();
     for p in library.pages.values() {
         external_links.push((&p.file.path, &p.external_links));
@@ -171,10 +289,10 @@ pub fn check_external_links(site: &Site) -> Vec<String> {
                         checked_links.push(LinkDef::new(file_path, link, domain));
                     }
                     Err(err) => {
-                        // We could use the messages.len() to keep track of them for below
+                        // We could use the issues.len() to keep track of them for below
                         // but it's more explicit this way
                         invalid_url_links += 1;
-                        messages.push(err.to_string());
+                        add_issue(file_path, link, None, err.to_string());
                     }
                 }
             }
@@ -199,12 +317,19 @@ pub fn check_external_links(site: &Site) -> Vec<String> {
     );
 
     if checked_links.is_empty() {
-        return Vec::new();
+        sort_issues(&mut issues);
+        return Ok(issues);
+    }
+
+    let use_cache = site.config.link_checker.uses_cache();
+    if use_cache {
+        link_checker::load_cache(&site.cache_path.join("links.json"), site.refresh_links)?;
     }
 
     // error out if we're in error mode and any external URLs couldn't be parsed
-    if site.config.link_checker.external_level == LinkCheckerLevel::Error && !messages.is_empty() {
-        return messages;
+    if site.config.link_checker.external_level == LinkCheckerLevel::Error && invalid_url_links > 0 {
+        sort_issues(&mut issues);
+        return Ok(issues);
     }
 
     let mut links_by_domain: HashMap<&str, Vec<&LinkDef>> = HashMap::new();
@@ -282,16 +407,166 @@ pub fn check_external_links(site: &Site) -> Vec<String> {
             );
 
             for (page_path, link, check_res) in errors {
-                messages.push(format!(
-                    "Broken link in {} to {} : {}",
-                    page_path.to_string_lossy(),
-                    link,
-                    link_checker::message(&check_res)
-                ));
+                let status = check_res.as_ref().ok().map(|code| code.as_u16());
+                add_issue(page_path, link, status, link_checker::message(&check_res));
+            }
+        }
+        Err(pool_err) => return Err(anyhow!(pool_err)),
+    }
+
+    if use_cache {
+        link_checker::save_cache()?;
+    }
+
+    sort_issues(&mut issues);
+    Ok(issues)
+}
+
+/// Makes the path of the file relative to the site root, with `/` as separator
+fn relative_file(issue: &LinkIssue, base_path: &Path) -> String {
+    issue.file.strip_prefix(base_path).unwrap_or(&issue.file).to_string_lossy().replace('\\', "/")
+}
+
+/// Renders the issues as JSON, with the file paths relative to the site root
+pub fn to_json_report(issues: &[LinkIssue], base_path: &Path) -> Result<String> {
+    let issues: Vec<_> = issues
+        .iter()
+        .map(|issue| LinkIssue {
+            file: PathBuf::from(relative_file(issue, base_path)),
+            ..issue.clone()
+        })
+        .collect();
+    Ok(serde_json::to_string_pretty(&json!({ "issues": issues }))?)
+}
+
+/// Renders the issues as a SARIF 2.1.0 log, with the file paths relative to the site root
+pub fn to_sarif_report(issues: &[LinkIssue], base_path: &Path) -> Result<String> {
+    let results: Vec<_> = issues
+        .iter()
+        .map(|issue| {
+            let rule_id = match issue.kind {
+                LinkKind::Internal => "broken-internal-link",
+                LinkKind::External => "broken-external-link",
+            };
+            let level = match issue.severity {
+                LinkCheckerLevel::Error => "error",
+                LinkCheckerLevel::Warn => "warning",
+            };
+            let mut location = json!({
+                "artifactLocation": { "uri": relative_file(issue, base_path) },
+            });
+            if let (Some(line), Some(column)) = (issue.line, issue.column) {
+                location["region"] = json!({ "startLine": line, "startColumn": column });
             }
+            json!({
+                "ruleId": rule_id,
+                "level": level,
+                "message": { "text": format!("Broken link to {}: {}", issue.target, issue.message) },
+                "locations": [{ "physicalLocation": location }],
+            })
+        })
+        .collect();
+
+    let sarif = json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "zola",
+                    "informationUri": "https://www.getzola.org",
+                    "rules": [
+                        {
+                            "id": "broken-internal-link",
+                            "shortDescription": { "text": "Link to an anchor that does not exist" },
+                        },
+                        {
+                            "id": "broken-external-link",
+                            "shortDescription": { "text": "Link to another website that is broken" },
+                        },
+                    ],
+                }
+            },
+            "results": results,
+        }],
+    });
+    Ok(serde_json::to_string_pretty(&sarif)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::write;
+    use tempfile::tempdir;
+
+    fn issue(kind: LinkKind, file: &str, line: Option<usize>) -> LinkIssue {
+        LinkIssue {
+            kind,
+            file: PathBuf::from(file),
+            line,
+            column: line.map(|_| 3),
+            target: "https://example.com".to_string(),
+            status: Some(404),
+            message: "404 Not Found".to_string(),
+            severity: LinkCheckerLevel::Warn,
         }
-        Err(pool_err) => messages.push(pool_err.to_string()),
     }
 
-    messages
+    #[test]
+    fn can_locate_links() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("page.md");
+        write(&path, "+++\n+++\n[a](https://a.com) and [é](https://a.com)\n\n[b](#b)").unwrap();
+
+        let mut locator = LinkLocator::default();
+        assert_eq!(locator.locate(&path, &["https://a.com"]), (Some(3), Some(5)));
+        // Same link found again: next occurrence
+        assert_eq!(locator.locate(&path, &["https://a.com"]), (Some(3), Some(28)));
+        assert_eq!(locator.locate(&path, &["https://a.com"]), (None, None));
+        assert_eq!(locator.locate(&path, &["@/page.md#b", "#b"]), (Some(5), Some(5)));
+        assert_eq!(locator.locate(&dir.path().join("nope.md"), &["#b"]), (None, None));
+    }
+
+    #[test]
+    fn can_render_json_report() {
+        let issues = vec![issue(LinkKind::External, "/site/content/page.md", Some(2))];
+        let report = to_json_report(&issues, Path::new("/site")).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&report).unwrap();
+        assert_eq!(
+            value,
+            json!({"issues": [{
+                "kind": "external",
+                "file": "content/page.md",
+                "line": 2,
+                "column": 3,
+                "target": "https://example.com",
+                "status": 404,
+                "message": "404 Not Found",
+                "severity": "warn",
+            }]})
+        );
+    }
+
+    #[test]
+    fn can_render_sarif_report() {
+        let issues = vec![
+            issue(LinkKind::External, "/site/content/page.md", Some(2)),
+            issue(LinkKind::Internal, "/site/content/other.md", None),
+        ];
+        let report = to_sarif_report(&issues, Path::new("/site")).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&report).unwrap();
+        assert_eq!(value["version"], "2.1.0");
+        let results = &value["runs"][0]["results"];
+        assert_eq!(results[0]["ruleId"], "broken-external-link");
+        assert_eq!(results[0]["level"], "warning");
+        assert_eq!(
+            results[0]["locations"][0]["physicalLocation"],
+            json!({
+                "artifactLocation": { "uri": "content/page.md" },
+                "region": { "startLine": 2, "startColumn": 3 },
+            })
+        );
+        assert_eq!(results[1]["ruleId"], "broken-internal-link");
+        assert!(results[1]["locations"][0]["physicalLocation"].get("region").is_none());
+    }
 }
@@ -9,7 +9,9 @@ use crate::anyhow;
 use errors::{Result, bail};
 use utils::fs::{create_directory, create_file};
 
-pub fn compile_sass(base_path: &Path, output_path: &Path) -> Result<()> {
+/// Compiles the Sass files of the site into the output directory and returns the paths
+/// of the CSS files written
+pub fn compile_sass(base_path: &Path, output_path: &Path) -> Result<Vec<PathBuf>> {
     create_directory(output_path)?;
 
     let sass_path = {
@@ -49,7 +51,7 @@ pub fn compile_sass(base_path: &Path, output_path: &Path) -> Result<()> {
         }
     }
 
-    Ok(())
+    Ok(compiled_paths.into_iter().map(|(_, path)| path).collect())
 }
 
 fn is_partial_scss(entry: &DirEntry) -> bool {
@@ -85,7 +85,8 @@ pub fn find_entries<'a>(
         if let Some(paginate_by) = s.paginate_by()
             && !config.should_exclude_paginated_pages_in_sitemap()
         {
-            let number_pagers = (s.pages.len() as f64 / paginate_by as f64).ceil() as isize;
+            let number_pagers =
+                (s.pages_to_paginate().len() as f64 / paginate_by as f64).ceil() as isize;
             for i in 1..=number_pagers {
                 let permalink = format!("{}{}/{}/", s.permalink, s.meta.paginate_path, i);
                 entries.insert(SitemapEntry::new(Cow::Owned(permalink), &None));
@@ -8,7 +8,9 @@ use ahash::AHashMap;
 use common::{build_site, build_site_with_setup};
 use config::TaxonomyConfig;
 use content::Page;
+use markdown::pulldown_cmark::Event;
 use site::Site;
+use site::link_checking::{self, LinkKind};
 use site::sitemap;
 use utils::types::InsertAnchor;
 
@@ -106,13 +108,15 @@ fn errors_on_unknown_taxonomies() {
     let (mut site, _, _) = build_site("test_site");
     let mut page = Page::default();
     page.file.path = PathBuf::from("unknown/taxo.md");
+    page.file.relative = "unknown/taxo.md".to_string();
     page.meta.taxonomies.insert("wrong".to_string(), vec![]);
     let res = site.add_page(page, false);
     assert!(res.is_err());
     let err = res.unwrap_err();
     assert_eq!(
         err.to_string(),
-        "Page `unknown/taxo.md` has taxonomy `wrong` which is not defined in config.toml"
+        "Found 1 front matter error(s)
+  1. Invalid front matter in `unknown/taxo.md`: taxonomy `wrong` is not defined in config.toml"
     );
 }
 
@@ -866,6 +870,157 @@ fn can_check_site() {
     site.load().expect("link check test_site");
 }
 
+#[test]
+fn can_report_broken_anchors() {
+    let (site, _tmp_dir, _public) = build_site("test_site");
+    assert!(site.link_issues.is_empty());
+
+    let page_path = site.base_path.join("content/posts/simple.md");
+    {
+        let mut library = site.library.write().unwrap();
+        let page = library.pages.get_mut(&page_path).unwrap();
+        page.internal_links.push(("hello.md".to_string(), Some("missing".to_string())));
+    }
+
+    let issues = link_checking::check_internal_links_with_anchors(&site);
+    assert_eq!(issues.len(), 1);
+    assert_eq!(issues[0].kind, LinkKind::Internal);
+    assert_eq!(issues[0].file, page_path);
+    assert_eq!(issues[0].target, "@/hello.md#missing");
+    assert_eq!(issues[0].severity, config::LinkCheckerLevel::Error);
+    // The link isn't in the markdown file so we can't know where it is
+    assert_eq!(issues[0].line, None);
+    assert_eq!(
+        issues[0].to_string(),
+        format!(
+            "The anchor in the link `@/hello.md#missing` in {} does not exist.",
+            page_path.to_string_lossy()
+        )
+    );
+}
+
+#[test]
+fn reports_external_links_when_internal_ones_are_broken() {
+    let tmp_dir = tempfile::tempdir().expect("create temp dir");
+    let path = tmp_dir.path();
+    std::fs::create_dir_all(path.join("content")).unwrap();
+    std::fs::write(path.join("config.toml"), "base_url = \"https://example.com\"\n").unwrap();
+    std::fs::write(
+        path.join("content").join("links.md"),
+        "+++\n+++\n[anchor](@/links.md#missing) and [offline](http://127.0.0.1:9/)\n",
+    )
+    .unwrap();
+
+    let mut site = Site::new(path, path.join("config.toml")).unwrap();
+    site.config.enable_check_mode();
+    let err = site.load().unwrap_err().to_string();
+    assert!(err.starts_with("Found 1 broken internal anchor link(s)"), "{err}");
+    assert!(err.contains("Found 1 broken external link(s)"), "{err}");
+    let kinds: Vec<_> = site.link_issues.iter().map(|issue| issue.kind).collect();
+    assert_eq!(kinds, [LinkKind::Internal, LinkKind::External]);
+}
+
+#[test]
+fn only_caches_rendered_fences_when_building() {
+    let tmp_dir = tempfile::tempdir().expect("create temp dir");
+    let path = tmp_dir.path();
+    std::fs::create_dir_all(path.join("content")).unwrap();
+    std::fs::create_dir_all(path.join("templates")).unwrap();
+    std::fs::write(
+        path.join("config.toml"),
+        "base_url = \"https://example.com\"\n[markdown]\nfence_renderers = [\"dot\"]\n",
+    )
+    .unwrap();
+    std::fs::write(path.join("templates").join("page.html"), "{{ page.content | safe }}").unwrap();
+    std::fs::write(
+        path.join("content").join("graph.md"),
+        "+++\n+++\n```dot\ndigraph { a -> b; }\n```\n",
+    )
+    .unwrap();
+    let fences = path.join(".zola-cache").join("fences");
+
+    let mut site = Site::new(path, path.join("config.toml")).unwrap();
+    site.load().unwrap();
+    assert!(!fences.exists());
+    site.set_output_path(path.join("public"));
+    site.build().unwrap();
+    assert_eq!(std::fs::read_dir(&fences).unwrap().count(), 1);
+    assert!(file_contains!(path.join("public"), "graph/index.html", "<svg"));
+}
+
+#[derive(Debug)]
+struct Shout;
+
+impl markdown::MarkdownTransform for Shout {
+    fn name(&self) -> &str {
+        "shout"
+    }
+
+    fn transform<'a>(
+        &self,
+        events: Vec<Event<'a>>,
+        _context: &markdown::RenderContext,
+    ) -> errors::Result<Vec<Event<'a>>> {
+        Ok(events
+            .into_iter()
+            .map(|e| match e {
+                Event::Text(t) => Event::Text(t.to_uppercase().into()),
+                _ => e,
+            })
+            .collect())
+    }
+}
+
+#[test]
+fn can_register_markdown_transforms_on_the_site() {
+    let tmp_dir = tempfile::tempdir().expect("create temp dir");
+    let path = tmp_dir.path();
+    std::fs::create_dir_all(path.join("content")).unwrap();
+    std::fs::write(path.join("config.toml"), "base_url = \"https://example.com\"\n").unwrap();
+    std::fs::write(path.join("content").join("_index.md"), "+++\n+++\nsection").unwrap();
+    std::fs::write(path.join("content").join("page.md"), "+++\n+++\npage").unwrap();
+
+    let mut site = Site::new(path, path.join("config.toml")).unwrap();
+    site.add_markdown_transform(Shout);
+    site.load().unwrap();
+    let library = site.library.read().unwrap();
+    assert_eq!(library.pages[&path.join("content").join("page.md")].content, "<p>PAGE</p>\n");
+    assert_eq!(
+        library.sections[&path.join("content").join("_index.md")].content,
+        "<p>SECTION</p>\n"
+    );
+}
+
+#[test]
+fn only_loads_pages_in_the_enabled_content_formats() {
+    let tmp_dir = tempfile::tempdir().expect("create temp dir");
+    let path = tmp_dir.path();
+    std::fs::create_dir_all(path.join("content").join("notes")).unwrap();
+    std::fs::write(
+        path.join("config.toml"),
+        "base_url = \"https://example.com\"\ncontent_formats = [\"org\"]\n",
+    )
+    .unwrap();
+    std::fs::write(path.join("content").join("notes").join("index.md"), "+++\n+++\n").unwrap();
+    std::fs::write(path.join("content").join("notes").join("todo.org"), "+++\n+++\n* Todo\n")
+        .unwrap();
+    std::fs::write(path.join("content").join("notes").join("draft.dj"), "+++\n+++\n# Draft\n")
+        .unwrap();
+    // Notebooks are common assets of pages, so they need to be enabled too
+    std::fs::write(path.join("content").join("notes").join("data.ipynb"), r#"{"cells": []}"#)
+        .unwrap();
+
+    let mut site = Site::new(path, path.join("config.toml")).unwrap();
+    site.load().unwrap();
+    let library = site.library.read().unwrap();
+    let mut pages: Vec<_> = library.pages.values().map(|p| p.file.filename.as_str()).collect();
+    pages.sort();
+    assert_eq!(pages, ["index.md", "todo.org"]);
+    let notes = library.pages.values().find(|p| p.file.filename == "index.md").unwrap();
+    let assets: Vec<_> = notes.assets.iter().map(|a| a.file_name().unwrap()).collect();
+    assert_eq!(assets, ["data.ipynb", "draft.dj"]);
+}
+
 #[test]
 #[should_panic]
 fn panics_on_invalid_external_domain() {
@@ -941,7 +1096,324 @@ fn can_find_site_and_page_authors() {
     assert_eq!(0, p2.meta.authors.len());
 }
 
+#[test]
+fn can_build_site_incrementally() {
+    let mut src = env::current_dir().unwrap().parent().unwrap().parent().unwrap().to_path_buf();
+    src.push("test_site");
+    let tmp_dir = tempfile::tempdir().expect("create temp dir");
+    let path = tmp_dir.path().join("site");
+    utils::fs::copy_directory(&src, &path, false, None).unwrap();
+    let public = tmp_dir.path().join("public");
+
+    let build = || {
+        let mut site = Site::new(&path, path.join("config.toml")).unwrap();
+        site.enable_build_cache();
+        site.set_cache_path(tmp_dir.path().join("cache"));
+        site.set_output_path(&public);
+        site.load().unwrap();
+        site.build().unwrap();
+        site.build_cache().unwrap().reused()
+    };
+
+    // Nothing to re-use the first time
+    assert_eq!(build(), 0);
+    assert!(file_contains!(public, "hello/index.html", "should be loaded"));
+    let all_reused = build();
+    assert!(all_reused > 0);
+    assert!(file_contains!(public, "hello/index.html", "should be loaded"));
+
+    // Editing a page only re-renders what depends on it
+    let hello = path.join("content").join("hello.md");
+    let content = std::fs::read_to_string(&hello).unwrap();
+    std::fs::write(&hello, content.replace("should be loaded", "was edited")).unwrap();
+    let reused = build();
+    assert!(reused > 0 && reused < all_reused, "{} {}", reused, all_reused);
+    assert!(file_contains!(public, "hello/index.html", "was edited"));
+
+    // The output is kept between builds, only what isn't generated anymore is removed
+    std::fs::write(public.join("kept.txt"), "").unwrap();
+    std::fs::remove_file(&hello).unwrap();
+    build();
+    assert!(public.join("kept.txt").exists());
+    assert!(!public.join("hello").exists());
+    assert!(public.join("posts").join("index.html").exists());
+}
+
+#[test]
+fn errors_on_invalid_shortcode_arguments() {
+    let mut src = env::current_dir().unwrap().parent().unwrap().parent().unwrap().to_path_buf();
+    src.push("test_site");
+    let tmp_dir = tempfile::tempdir().expect("create temp dir");
+    let path = tmp_dir.path().join("site");
+    utils::fs::copy_directory(&src, &path, false, None).unwrap();
+    std::fs::write(
+        path.join("templates").join("shortcodes").join("typed.html"),
+        "{#\n+++\n[args.title]\ntype = \"string\"\nrequired = true\n+++\n#}{{ title }}",
+    )
+    .unwrap();
+    std::fs::write(path.join("content").join("typed.md"), "+++\n+++\n{{ typed(titel=\"Hi\") }}")
+        .unwrap();
+
+    let mut site = Site::new(&path, path.join("config.toml")).unwrap();
+    let err = site.load().unwrap_err();
+    let errors: Vec<_> = err.chain().map(|e| e.to_string()).collect();
+    assert!(
+        errors.iter().any(|e| e.starts_with(
+            "Invalid call to the shortcode `typed` in \"typed.md\": unknown argument `titel`, did you mean `title`?"
+        )),
+        "{:?}",
+        errors
+    );
+}
+
+#[test]
+fn can_render_output_formats() {
+    let (_, _tmp_dir, public) = build_site_with_setup("test_site", |mut site| {
+        site.config.output_formats = vec!["json".to_string()];
+        site.tera
+            .add_raw_template("page.json", r#"{"title": {{ page.title | json_encode() }}}"#)
+            .unwrap();
+        (site, true)
+    });
+
+    assert!(file_contains!(
+        public,
+        "posts/simple/index.json",
+        r#"{"title": "Simple article with shortcodes"}"#
+    ));
+    assert!(file_exists!(public, "posts/simple/index.html"));
+    // There is no `section.json` template so sections are only rendered to HTML
+    assert!(!file_exists!(public, "posts/index.json"));
+}
+
+#[test]
+fn can_render_output_formats_of_every_pager() {
+    let (_, _tmp_dir, public) = build_site_with_setup("test_site", |mut site| {
+        site.config.output_formats = vec!["json".to_string()];
+        site.tera
+            .add_raw_template(
+                "section_paginated.json",
+                r#"{"index": {{ paginator.current_index }}}"#,
+            )
+            .unwrap();
+        (site, true)
+    });
+
+    assert!(file_contains!(public, "posts/index.json", r#"{"index": 1}"#));
+    assert!(file_contains!(public, "posts/page/2/index.json", r#"{"index": 2}"#));
+    // The first pager is only a redirect in HTML
+    assert!(!file_exists!(public, "posts/page/1/index.json"));
+}
+
+#[test]
+fn can_find_related_pages() {
+    let (site, _tmp_dir, public) = build_site_with_setup("test_site", |mut site| {
+        site.config.related.limit = 2;
+        site.config.output_formats = vec!["json".to_string()];
+        site.tera
+            .add_raw_template(
+                "page.json",
+                r#"{{ page.related | map(attribute="relative_path") | json_encode() }}"#,
+            )
+            .unwrap();
+        (site, true)
+    });
+
+    let library = site.library.read().unwrap();
+    let page = &library.pages[&site.base_path.join("content/posts/simple.md")];
+    assert!(!page.related.is_empty());
+    assert!(page.related.len() <= 2);
+    assert!(!page.related.contains(&page.file.path));
+    let expected = format!(
+        "[{}]",
+        page.related
+            .iter()
+            .map(|p| format!("\"{}\"", library.pages[p].file.relative))
+            .collect::<Vec<_>>()
+            .join(",")
+    );
+    assert!(file_contains!(public, "posts/simple/index.json", &expected));
+}
+
 // Follows test_site/themes/sample/templates/current_path.html
 fn current_path(path: &str) -> String {
     format!("[current_path]({})", path)
 }
+
+#[test]
+fn hides_scheduled_content_unless_asked() {
+    let mut src = env::current_dir().unwrap().parent().unwrap().parent().unwrap().to_path_buf();
+    src.push("test_site");
+    let tmp_dir = tempfile::tempdir().expect("create temp dir");
+    let path = tmp_dir.path().join("site");
+    utils::fs::copy_directory(&src, &path, false, None).unwrap();
+    let content = path.join("content");
+    std::fs::write(
+        content.join("posts").join("upcoming.md"),
+        "+++\ntitle = \"Upcoming\"\ndate = 2020-01-01\npublish_date = 2999-01-01\n[taxonomies]\ncategories = [\"Scheduled\"]\n+++\n",
+    )
+    .unwrap();
+    std::fs::write(
+        content.join("posts").join("outdated.md"),
+        "+++\ntitle = \"Outdated\"\ndate = 2020-01-01\nexpiry_date = 2020-06-01T12:00:00Z\n+++\n",
+    )
+    .unwrap();
+    std::fs::create_dir(content.join("launch")).unwrap();
+    std::fs::write(
+        content.join("launch").join("_index.md"),
+        "+++\ntitle = \"Launch\"\npublish_date = 2999-01-01\n+++\n",
+    )
+    .unwrap();
+    std::fs::write(content.join("launch").join("teaser.md"), "+++\ntitle = \"Teaser\"\n+++\n")
+        .unwrap();
+
+    let mut site = Site::new(&path, path.join("config.toml")).unwrap();
+    site.load().unwrap();
+    let public = tmp_dir.path().join("public");
+    site.set_output_path(&public);
+    site.build().unwrap();
+    {
+        let library = site.library.read().unwrap();
+        for file in ["posts/upcoming.md", "posts/outdated.md", "launch/teaser.md"] {
+            assert!(!library.pages.contains_key(&content.join(file)), "{file} is loaded");
+        }
+        assert!(!library.sections.contains_key(&content.join("launch").join("_index.md")));
+    }
+    assert!(!file_exists!(public, "posts/upcoming/index.html"));
+    assert!(!file_contains!(public, "sitemap.xml", "upcoming"));
+    assert!(!file_contains!(public, "sitemap.xml", "outdated"));
+    assert!(!file_exists!(public, "categories/scheduled/index.html"));
+
+    let mut site = Site::new(&path, path.join("config.toml")).unwrap();
+    site.include_future();
+    site.include_expired();
+    site.load().unwrap();
+    let library = site.library.read().unwrap();
+    for file in ["posts/upcoming.md", "posts/outdated.md", "launch/teaser.md"] {
+        assert!(library.pages.contains_key(&content.join(file)), "{file} is not loaded");
+    }
+}
+
+#[test]
+fn can_cascade_front_matter_to_descendants() {
+    let mut src = env::current_dir().unwrap().parent().unwrap().parent().unwrap().to_path_buf();
+    src.push("test_site");
+    let tmp_dir = tempfile::tempdir().expect("create temp dir");
+    let path = tmp_dir.path().join("site");
+    utils::fs::copy_directory(&src, &path, false, None).unwrap();
+    let guides = path.join("content").join("guides");
+    std::fs::create_dir_all(guides.join("advanced")).unwrap();
+    std::fs::write(
+        guides.join("_index.md"),
+        r#"+++
+title = "Guides"
+[cascade]
+authors = ["Jane"]
+[cascade.taxonomies]
+categories = ["Guides"]
+[cascade.extra]
+series = "Guides"
++++
+"#,
+    )
+    .unwrap();
+    std::fs::write(
+        guides.join("advanced").join("_index.md"),
+        "+++\ntitle = \"Advanced\"\n[cascade]\npath = \"deep-*\"\n[cascade.extra]\nlevel = \"hard\"\n+++\n",
+    )
+    .unwrap();
+    std::fs::write(
+        guides.join("intro.md"),
+        "+++\ntitle = \"Intro\"\n[extra]\nseries = \"Basics\"\n+++\n",
+    )
+    .unwrap();
+    std::fs::write(
+        guides.join("advanced").join("deep-dive.md"),
+        "+++\ntitle = \"Deep dive\"\n+++\n",
+    )
+    .unwrap();
+    std::fs::write(guides.join("advanced").join("tips.md"), "+++\ntitle = \"Tips\"\n+++\n")
+        .unwrap();
+
+    let mut site = Site::new(&path, path.join("config.toml")).unwrap();
+    site.load().unwrap();
+    let public = tmp_dir.path().join("public");
+    site.set_output_path(&public);
+    site.build().unwrap();
+
+    let library = site.library.read().unwrap();
+    let intro = &library.pages[&guides.join("intro.md")];
+    assert_eq!(intro.meta.authors, ["Jane"]);
+    assert_eq!(intro.meta.extra["series"], "Basics");
+    let deep_dive = &library.pages[&guides.join("advanced").join("deep-dive.md")];
+    assert_eq!(deep_dive.meta.extra["series"], "Guides");
+    assert_eq!(deep_dive.meta.extra["level"], "hard");
+    let tips = &library.pages[&guides.join("advanced").join("tips.md")];
+    assert!(!tips.meta.extra.contains_key("level"));
+    let advanced = &library.sections[&guides.join("advanced").join("_index.md")];
+    assert_eq!(advanced.meta.extra["series"], "Guides");
+
+    let categories = site.taxonomies.iter().find(|t| t.kind.name == "categories").unwrap();
+    let guides_term = categories.items.iter().find(|t| t.name == "Guides").unwrap();
+    assert_eq!(guides_term.pages.len(), 3);
+    assert!(file_exists!(public, "categories/guides/index.html"));
+}
+
+#[test]
+fn reports_every_page_schema_error() {
+    let mut src = env::current_dir().unwrap().parent().unwrap().parent().unwrap().to_path_buf();
+    src.push("test_site");
+    let tmp_dir = tempfile::tempdir().expect("create temp dir");
+    let path = tmp_dir.path().join("site");
+    utils::fs::copy_directory(&src, &path, false, None).unwrap();
+    let guides = path.join("content").join("guides");
+    std::fs::create_dir_all(guides.join("advanced")).unwrap();
+    std::fs::write(
+        guides.join("_index.md"),
+        r#"+++
+title = "Guides"
+[page_schema.extra.cover_image]
+type = "string"
+required = true
+[page_schema.extra.layout]
+enum = ["wide", "narrow"]
+default = "narrow"
++++
+"#,
+    )
+    .unwrap();
+    std::fs::write(guides.join("advanced").join("_index.md"), "+++\ntitle = \"Advanced\"\n+++\n")
+        .unwrap();
+    std::fs::write(
+        guides.join("intro.md"),
+        "+++\ntitle = \"Intro\"\n[extra]\ncover_image = \"intro.png\"\n+++\n",
+    )
+    .unwrap();
+    std::fs::write(
+        guides.join("advanced").join("tips.md"),
+        "+++\ntitle = \"Tips\"\n[taxonomies]\nseries = [\"Guides\"]\n[extra]\ncover_iamge = \"tips.png\"\nlayout = \"full\"\n+++\n",
+    )
+    .unwrap();
+
+    let mut site = Site::new(&path, path.join("config.toml")).unwrap();
+    let err = site.load().unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Found 4 front matter error(s)
+  1. Invalid front matter in `guides/advanced/tips.md`: unknown key `extra.cover_iamge`, did you mean `extra.cover_image`?
+  2. Invalid front matter in `guides/advanced/tips.md`: missing required key `extra.cover_image`
+  3. Invalid front matter in `guides/advanced/tips.md`: `extra.layout` should be one of `\"wide\"`, `\"narrow\"`, got `\"full\"`
+  4. Invalid front matter in `guides/advanced/tips.md`: taxonomy `series` is not defined in config.toml"
+    );
+
+    std::fs::write(
+        guides.join("advanced").join("tips.md"),
+        "+++\ntitle = \"Tips\"\n[extra]\ncover_image = \"tips.png\"\n+++\n",
+    )
+    .unwrap();
+    let mut site = Site::new(&path, path.join("config.toml")).unwrap();
+    site.load().unwrap();
+    let library = site.library.read().unwrap();
+    let tips = &library.pages[&guides.join("advanced").join("tips.md")];
+    assert_eq!(tips.meta.extra["layout"], "narrow");
+}
@@ -0,0 +1,94 @@
+//! Keeps track of what a template reads through the global functions while it is being rendered,
+//! so an incremental build can find out whether a previously rendered page is still up to date.
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "value", rename_all = "snake_case")]
+pub enum Dependency {
+    /// A page fetched with `get_page`, identified by the path to its markdown file
+    Page(PathBuf),
+    /// A section fetched with `get_section`, identified by the path to its markdown file
+    Section(PathBuf),
+    /// A taxonomy used through `get_taxonomy`, `get_taxonomy_term` or `get_taxonomy_url`,
+    /// identified by `{name}-{lang}`
+    Taxonomy(String),
+    /// Internal links resolved through `get_url` or the `markdown` filter
+    Permalinks,
+    /// The filenames, slugs and titles of the pages `[[wiki links]]` are resolved against
+    WikiLinks,
+    /// A local file read by `load_data`, `get_url(cachebust=true)`, `get_hash` etc
+    File(PathBuf),
+    /// Something we can't know whether it changed between 2 builds, like a remote `load_data`,
+    /// `now()` or `resize_image`, which needs to be enqueued on every build.
+    Volatile,
+}
+
+thread_local! {
+    static RECORDED: RefCell<Option<HashSet<Dependency>>> = const { RefCell::new(None) };
+}
+
+/// Records a dependency for the render currently tracked on this thread, if there is one.
+/// Tera renders a template on the thread calling it so a thread local is enough even with rayon.
+pub fn record(dependency: Dependency) {
+    RECORDED.with(|recorded| {
+        if let Some(deps) = recorded.borrow_mut().as_mut() {
+            deps.insert(dependency);
+        }
+    });
+}
+
+/// Runs the given closure and returns its result along with all the dependencies recorded
+/// while it ran, sorted so they can be stored and compared across builds.
+pub fn track<T>(f: impl FnOnce() -> T) -> (T, Vec<Dependency>) {
+    let previous = RECORDED.with(|recorded| recorded.borrow_mut().replace(HashSet::new()));
+    let res = f();
+    let deps = RECORDED.with(|recorded| {
+        let deps = recorded.borrow_mut().take().unwrap_or_default();
+        *recorded.borrow_mut() = previous;
+        deps
+    });
+    let mut deps: Vec<_> = deps.into_iter().collect();
+    deps.sort();
+    (res, deps)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_records_when_tracking() {
+        record(Dependency::Volatile);
+        let (res, deps) = track(|| {
+            record(Dependency::File(PathBuf::from("data.toml")));
+            record(Dependency::Page(PathBuf::from("content/a.md")));
+            record(Dependency::File(PathBuf::from("data.toml")));
+            42
+        });
+        assert_eq!(res, 42);
+        assert_eq!(
+            deps,
+            vec![
+                Dependency::Page(PathBuf::from("content/a.md")),
+                Dependency::File(PathBuf::from("data.toml")),
+            ]
+        );
+        let (_, deps) = track(|| ());
+        assert!(deps.is_empty());
+    }
+
+    #[test]
+    fn nested_tracking_is_isolated() {
+        let (inner, outer) = track(|| {
+            record(Dependency::Volatile);
+            let (_, inner) = track(|| record(Dependency::Taxonomy("tags-en".to_owned())));
+            inner
+        });
+        assert_eq!(inner, vec![Dependency::Taxonomy("tags-en".to_owned())]);
+        assert_eq!(outer, vec![Dependency::Volatile]);
+    }
+}
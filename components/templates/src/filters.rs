@@ -5,6 +5,8 @@ use std::sync::{Arc, Mutex};
 
 use config::Config;
 
+use crate::dependencies::{Dependency, record};
+
 use base64::engine::{Engine, general_purpose::STANDARD as standard_b64};
 use markdown::{RenderContext, render_content};
 use regex::Regex;
@@ -12,17 +14,24 @@ use tera::{
     Error as TeraError, Filter as TeraFilter, Result as TeraResult, Tera, Value, to_value,
     try_get_value,
 };
+use utils::templates::ShortcodeDefinition;
 
 #[derive(Debug)]
 pub struct MarkdownFilter {
     config: Config,
     permalinks: HashMap<String, String>,
     tera: Tera,
+    shortcode_definitions: HashMap<String, ShortcodeDefinition>,
 }
 
 impl MarkdownFilter {
-    pub fn new(config: Config, permalinks: HashMap<String, String>, tera: Tera) -> Self {
-        Self { config, permalinks, tera }
+    pub fn new(
+        config: Config,
+        permalinks: HashMap<String, String>,
+        tera: Tera,
+        shortcode_definitions: HashMap<String, ShortcodeDefinition>,
+    ) -> Self {
+        Self { config, permalinks, tera, shortcode_definitions }
     }
 }
 
@@ -35,10 +44,10 @@ impl TeraFilter for MarkdownFilter {
         let mut context = RenderContext::from_config(&self.config);
         context.permalinks = Cow::Borrowed(&self.permalinks);
         context.tera = Cow::Borrowed(&self.tera);
-        let def = utils::templates::get_shortcodes(&self.tera);
-        context.set_shortcode_definitions(&def);
+        context.set_shortcode_definitions(&self.shortcode_definitions);
 
         let s = try_get_value!("markdown", "value", String, value);
+        record(Dependency::Permalinks);
         let inline = match args.get("inline") {
             Some(val) => try_get_value!("markdown", "inline", bool, val),
             None => false,
@@ -183,8 +192,9 @@ mod tests {
 
     #[test]
     fn markdown_filter() {
-        let result = MarkdownFilter::new(Config::default(), HashMap::new(), Tera::default())
-            .filter(&to_value("# Hey").unwrap(), &HashMap::new());
+        let result =
+            MarkdownFilter::new(Config::default(), HashMap::new(), Tera::default(), HashMap::new())
+                .filter(&to_value("# Hey").unwrap(), &HashMap::new());
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), to_value("<h1 id=\"hey\">Hey</h1>\n").unwrap());
     }
@@ -199,7 +209,8 @@ mod tests {
         let permalinks = HashMap::new();
         let mut tera = Tera::default();
         tera.add_raw_template("shortcodes/explicitlang.html", "a{{ lang }}a").unwrap();
-        let filter = MarkdownFilter { config, permalinks, tera };
+        let shortcode_definitions = utils::templates::get_shortcodes(&tera).unwrap();
+        let filter = MarkdownFilter { config, permalinks, tera, shortcode_definitions };
         let result = filter.filter(&to_value("{{ explicitlang(lang='jp') }}").unwrap(), &args);
         println!("{:?}", result);
         assert!(result.is_ok());
@@ -210,33 +221,47 @@ mod tests {
     fn markdown_filter_inline() {
         let mut args = HashMap::new();
         args.insert("inline".to_string(), to_value(true).unwrap());
-        let result = MarkdownFilter::new(Config::default(), HashMap::new(), Tera::default())
-            .filter(
-                &to_value("Using `map`, `filter`, and `fold` instead of `for`").unwrap(),
-                &args,
-            );
+        let result =
+            MarkdownFilter::new(Config::default(), HashMap::new(), Tera::default(), HashMap::new())
+                .filter(
+                    &to_value("Using `map`, `filter`, and `fold` instead of `for`").unwrap(),
+                    &args,
+                );
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), to_value("Using <code>map</code>, <code>filter</code>, and <code>fold</code> instead of <code>for</code>").unwrap());
     }
 
+    #[test]
+    fn markdown_filter_sidenotes() {
+        let mut config = Config::default();
+        config.markdown.footnote_style = config::FootnoteStyle::Sidenote;
+        let result = MarkdownFilter::new(config, HashMap::new(), Tera::default(), HashMap::new())
+            .filter(&to_value("Hey[^1]\n\n[^1]: A note.").unwrap(), &HashMap::new());
+        let html = result.unwrap();
+        let html = html.as_str().unwrap();
+        assert!(html.starts_with("<p>Hey<sup class=\"footnote-reference\" id=\"fr-1-1\">"));
+        assert!(html.contains("<span class=\"sidenote-paragraph\">A note.</span></span></p>"));
+    }
+
     // https://github.com/Keats/gutenberg/issues/417
     #[test]
     fn markdown_filter_inline_tables() {
         let mut args = HashMap::new();
         args.insert("inline".to_string(), to_value(true).unwrap());
-        let result = MarkdownFilter::new(Config::default(), HashMap::new(), Tera::default())
-            .filter(
-                &to_value(
-                    r#"
+        let result =
+            MarkdownFilter::new(Config::default(), HashMap::new(), Tera::default(), HashMap::new())
+                .filter(
+                    &to_value(
+                        r#"
 |id|author_id|       timestamp_created|title                 |content           |
 |-:|--------:|-----------------------:|:---------------------|:-----------------|
 | 1|        1|2018-09-05 08:03:43.141Z|How to train your ORM |Badly written blog|
 | 2|        1|2018-08-22 13:11:50.050Z|How to bake a nice pie|Badly written blog|
         "#,
-                )
-                .unwrap(),
-                &args,
-            );
+                    )
+                    .unwrap(),
+                    &args,
+                );
         assert!(result.is_ok());
         assert!(result.unwrap().as_str().unwrap().contains("<table>"));
     }
@@ -257,13 +282,14 @@ mod tests {
         config.markdown.external_links_target_blank = true;
 
         let md = "Hello <https://google.com> :smile: ...";
-        let result = MarkdownFilter::new(config.clone(), HashMap::new(), Tera::default())
-            .filter(&to_value(md).unwrap(), &HashMap::new());
+        let result =
+            MarkdownFilter::new(config.clone(), HashMap::new(), Tera::default(), HashMap::new())
+                .filter(&to_value(md).unwrap(), &HashMap::new());
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), to_value("<p>Hello <a rel=\"noopener external\" target=\"_blank\" href=\"https://google.com\">https://google.com</a> 😄 …</p>\n").unwrap());
 
         let md = "```py\ni=0\n```";
-        let result = MarkdownFilter::new(config, HashMap::new(), Tera::default())
+        let result = MarkdownFilter::new(config, HashMap::new(), Tera::default(), HashMap::new())
             .filter(&to_value(md).unwrap(), &HashMap::new());
         assert!(result.is_ok());
         assert!(result.unwrap().as_str().unwrap().contains("style"));
@@ -274,8 +300,9 @@ mod tests {
         let mut permalinks = HashMap::new();
         permalinks.insert("blog/_index.md".to_string(), "/foo/blog".to_string());
         let md = "Hello. Check out [my blog](@/blog/_index.md)!";
-        let result = MarkdownFilter::new(Config::default(), permalinks, Tera::default())
-            .filter(&to_value(md).unwrap(), &HashMap::new());
+        let result =
+            MarkdownFilter::new(Config::default(), permalinks, Tera::default(), HashMap::new())
+                .filter(&to_value(md).unwrap(), &HashMap::new());
         assert!(result.is_ok());
         assert_eq!(
             result.unwrap(),
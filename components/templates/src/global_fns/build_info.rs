@@ -3,6 +3,8 @@ use tera::{Function as TeraFn, Result, Value, from_value, to_value};
 use chrono::prelude::*;
 use std::collections::HashMap;
 
+use crate::dependencies::{Dependency, record};
+
 #[derive(Debug)]
 pub struct Now {
     local: DateTime<Local>,
@@ -17,6 +19,7 @@ impl Now {
 
 impl TeraFn for Now {
     fn call(&self, args: &HashMap<String, Value>) -> Result<Value> {
+        record(Dependency::Volatile);
         let use_utc = match args.get("utc") {
             Some(val) => match from_value::<bool>(val.clone()) {
                 Ok(v) => v,
@@ -6,6 +6,8 @@ use std::sync::{Arc, RwLock};
 use tera::{Function as TeraFn, Result, Value, from_value, to_value};
 use utils::slugs::{SlugifyStrategy, slugify_paths};
 
+use crate::dependencies::{Dependency, record};
+
 #[derive(Debug)]
 pub struct GetTaxonomyUrl {
     taxonomies: HashMap<String, HashMap<String, String>>,
@@ -48,7 +50,9 @@ impl TeraFn for GetTaxonomyUrl {
         )
         .unwrap_or(true);
 
-        let container = match (self.taxonomies.get(&format!("{}-{}", kind, lang)), required) {
+        let key = format!("{}-{}", kind, lang);
+        record(Dependency::Taxonomy(key.clone()));
+        let container = match (self.taxonomies.get(&key), required) {
             (Some(c), _) => c,
             (None, false) => return Ok(Value::Null),
             (None, true) => {
@@ -142,6 +146,7 @@ impl TeraFn for GetPage {
         get_path_with_lang(&path, lang.as_deref(), &self.default_lang, &self.supported_languages)
             .and_then(|path_with_lang| {
                 let full_path = self.base_path.join(path_with_lang.as_ref());
+                record(Dependency::Page(full_path.clone()));
                 let library = self.library.read().unwrap();
 
                 match library.pages.get(&full_path) {
@@ -203,6 +208,7 @@ impl TeraFn for GetSection {
         )
         .and_then(|path_with_lang| {
             let full_path = self.base_path.join(path_with_lang.as_ref());
+            record(Dependency::Section(full_path.clone()));
             let library = self.library.read().unwrap();
 
             match library.sections.get(&full_path) {
@@ -262,7 +268,9 @@ impl TeraFn for GetTaxonomy {
             optional_arg!(String, args.get("lang"), "`get_taxonomy`: `lang` must be a string")
                 .unwrap_or_else(|| self.default_lang.clone());
 
-        match (self.taxonomies.get(&format!("{}-{}", kind, lang)), required) {
+        let key = format!("{}-{}", kind, lang);
+        record(Dependency::Taxonomy(key.clone()));
+        match (self.taxonomies.get(&key), required) {
             (Some(t), _) => Ok(to_value(t.to_serialized(&self.library.read().unwrap())).unwrap()),
             (None, false) => Ok(Value::Null),
             (None, true) => {
@@ -320,7 +328,9 @@ impl TeraFn for GetTaxonomyTerm {
             optional_arg!(String, args.get("lang"), "`get_taxonomy_term`: `lang` must be a string")
                 .unwrap_or_else(|| self.default_lang.clone());
 
-        let tax: &Taxonomy = match (self.taxonomies.get(&format!("{}-{}", kind, lang)), required) {
+        let key = format!("{}-{}", kind, lang);
+        record(Dependency::Taxonomy(key.clone()));
+        let tax: &Taxonomy = match (self.taxonomies.get(&key), required) {
             (Some(t), _) => t,
             (None, false) => {
                 return Ok(Value::Null);
@@ -3,6 +3,7 @@ use std::fs;
 use std::io::Read;
 use std::path::PathBuf;
 
+use crate::dependencies::{Dependency, record};
 use crate::global_fns::helpers::search_for_file;
 use config::Config;
 
@@ -87,6 +88,7 @@ impl TeraFn for GetUrl {
         // if it starts with @/, resolve it as an internal link
         if path.starts_with("@/") {
             let path_with_lang = make_path_with_lang(path, &lang, &self.config)?;
+            record(Dependency::Permalinks);
 
             match resolve_internal_link(&path_with_lang, &self.permalinks) {
                 Ok(resolved) => Ok(to_value(resolved.permalink).unwrap()),
@@ -123,7 +125,10 @@ impl TeraFn for GetUrl {
                     &self.output_path,
                 )
                 .map_err(|e| format!("`get_url`: {}", e))?
-                .and_then(|(p, _)| fs::File::open(p).ok())
+                .and_then(|(p, _)| {
+                    record(Dependency::File(p.clone()));
+                    fs::File::open(p).ok()
+                })
                 .and_then(|mut f| {
                     let mut contents = Vec::new();
                     f.read_to_end(&mut contents).ok()?;
@@ -198,6 +203,7 @@ impl TeraFn for GetHash {
                         }
                     };
 
+                record(Dependency::File(file_path.clone()));
                 let mut f = match fs::File::open(file_path) {
                     Ok(f) => f,
                     Err(e) => {
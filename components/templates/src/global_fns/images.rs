@@ -4,6 +4,7 @@ use std::sync::{Arc, Mutex};
 
 use tera::{Function as TeraFn, Result, Value, from_value, to_value};
 
+use crate::dependencies::{Dependency, record};
 use crate::global_fns::helpers::search_for_file;
 
 #[derive(Debug)]
@@ -70,6 +71,9 @@ impl TeraFn for ResizeImage {
                 }
             };
 
+        // The processor needs to know about every image used in the build, even if the page
+        // using it didn't change, otherwise the processed image would be deleted.
+        record(Dependency::Volatile);
         let response = imageproc
             .enqueue(resize_op, unified_path, file_path, &format, quality, speed)
             .map_err(|e| format!("`resize_image`: {}", e))?;
@@ -120,6 +124,7 @@ impl TeraFn for GetImageMetadata {
                 }
             };
 
+        record(Dependency::File(src_path.clone()));
         let mut cache = self.result_cache.lock().expect("result cache lock");
         if let Some(cached_result) = cache.get(&unified_path) {
             return Ok(cached_result.clone());
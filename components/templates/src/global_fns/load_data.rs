@@ -16,6 +16,7 @@ use utils::de::fix_toml_dates;
 use utils::fs::{get_file_time, read_file};
 use {nom_bibtex, serde_json, serde_yaml, toml};
 
+use crate::dependencies::{Dependency, record};
 use crate::global_fns::helpers::search_for_file;
 
 const GET_DATA_ARGUMENT_ERROR_MESSAGE: &str =
@@ -306,6 +307,11 @@ impl TeraFn for LoadData {
             }
             (Ok(Some(data_source)), _) => data_source,
         };
+        match &data_source {
+            DataSource::Path(path) => record(Dependency::File(path.clone())),
+            DataSource::Url(_) => record(Dependency::Volatile),
+            DataSource::Literal(_) => (),
+        }
 
         let file_format = get_output_format_from_args(format_arg, &data_source)?;
         let cache_key = data_source.get_cache_key(
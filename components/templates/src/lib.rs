@@ -1,9 +1,10 @@
+pub mod dependencies;
 pub mod filters;
 pub mod global_fns;
 
 use std::path::Path;
 
-use config::Config;
+use config::{Config, TEMPLATE_EXTENSIONS};
 use once_cell::sync::Lazy;
 use tera::{Context, Tera};
 
@@ -24,6 +25,7 @@ pub static ZOLA_TERA: Lazy<Tera> = Lazy::new(|| {
         ),
         ("__zola_builtins/anchor-link.html", include_str!("builtins/anchor-link.html")),
         ("__zola_builtins/summary-cutoff.html", include_str!("builtins/summary-cutoff.html")),
+        ("__zola_builtins/references.html", include_str!("builtins/references.html")),
         ("internal/alias.html", include_str!("builtins/internal/alias.html")),
     ])
     .unwrap();
@@ -44,11 +46,9 @@ pub fn render_redirect_template(url: &str, tera: &Tera) -> Result<String> {
 }
 
 pub fn load_tera(path: &Path, config: &Config) -> Result<Tera> {
-    let tpl_glob = format!(
-        "{}/{}",
-        path.to_string_lossy().replace('\\', "/"),
-        "templates/**/*.{*ml,md,txt,json,ics}"
-    );
+    let extensions = TEMPLATE_EXTENSIONS.join(",");
+    let tpl_glob =
+        format!("{}/templates/**/*.{{{}}}", path.to_string_lossy().replace('\\', "/"), extensions);
 
     // Only parsing as we might be extending templates from themes and that would error
     // as we haven't loaded them yet
@@ -63,9 +63,10 @@ pub fn load_tera(path: &Path, config: &Config) -> Result<Tera> {
         }
 
         let theme_tpl_glob = format!(
-            "{}/themes/{}/templates/**/*.{{*ml,md}}",
+            "{}/themes/{}/templates/**/*.{{{}}}",
             path.to_string_lossy().replace('\\', "/"),
-            theme
+            theme,
+            extensions
         );
         let mut tera_theme =
             Tera::parse(&theme_tpl_glob).context("Error parsing templates from themes")?;
@@ -1,11 +1,27 @@
+use std::collections::BTreeSet;
+use std::sync::LazyLock;
+
 use regex::Regex;
 use regex::escape;
 
+static ANCHOR_IDS_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"\s(?i)(?:id|name) *= *(?:"([^"]*)"|'([^']*)'|([^\s"'>]+))"#).unwrap()
+});
+
 pub fn has_anchor_id(content: &str, anchor: &str) -> bool {
     let checks = anchor_id_checks(anchor);
     checks.is_match(content)
 }
 
+/// Finds all the ids and names of the elements of an HTML page, the anchors that links can point to
+pub fn find_anchor_ids(content: &str) -> BTreeSet<String> {
+    ANCHOR_IDS_RE
+        .captures_iter(content)
+        .filter_map(|caps| caps.get(1).or_else(|| caps.get(2)).or_else(|| caps.get(3)))
+        .map(|m| m.as_str().to_string())
+        .collect()
+}
+
 fn anchor_id_checks(anchor: &str) -> Regex {
     Regex::new(&format!(r#"\s(?i)(id|name) *= *("|')*{}("|'| |>)+"#, escape(anchor))).unwrap()
 }
@@ -18,7 +34,7 @@ pub fn is_special_anchor(anchor: &str) -> bool {
 
 #[cfg(test)]
 mod tests {
-    use super::{anchor_id_checks, is_special_anchor};
+    use super::{anchor_id_checks, find_anchor_ids, is_special_anchor};
 
     fn check(anchor: &str, content: &str) -> bool {
         anchor_id_checks(anchor).is_match(content)
@@ -59,6 +75,15 @@ id="fred">"#));
         assert!(!m(r#"<a notid="fred">"#));
     }
 
+    #[test]
+    fn can_find_anchor_ids() {
+        let ids = find_anchor_ids(
+            r#"<h1 id="title">T</h1><a name='fred'></a><div
+ID=bare>x</div><p data-id="nope" notid="nope"></p>"#,
+        );
+        assert_eq!(ids.into_iter().collect::<Vec<_>>(), vec!["bare", "fred", "title"]);
+    }
+
     #[test]
     fn test_is_special_anchor() {
         assert!(is_special_anchor(""));
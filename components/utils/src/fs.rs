@@ -2,7 +2,7 @@ use filetime::{FileTime, set_file_mtime};
 use globset::GlobSet;
 use std::fs::{File, copy, create_dir_all, metadata, remove_dir_all, remove_file};
 use std::io::prelude::*;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::time::SystemTime;
 use walkdir::WalkDir;
 
@@ -109,12 +109,15 @@ pub fn copy_file_if_needed(src: &Path, dest: &Path, hard_link: bool) -> Result<(
     Ok(())
 }
 
+/// Copies the content of `src` into `dest` and returns the paths of the files copied,
+/// relative to `dest`
 pub fn copy_directory(
     src: &Path,
     dest: &Path,
     hard_link: bool,
     ignore_globset: Option<&GlobSet>,
-) -> Result<()> {
+) -> Result<Vec<PathBuf>> {
+    let mut copied = Vec::new();
     for entry in
         WalkDir::new(src).follow_links(true).into_iter().filter_map(std::result::Result::ok)
     {
@@ -141,9 +144,10 @@ pub fn copy_directory(
                     hard_link
                 )
             })?;
+            copied.push(relative_path.to_path_buf());
         }
     }
-    Ok(())
+    Ok(copied)
 }
 
 pub fn get_file_time(path: &Path) -> Option<SystemTime> {
@@ -3,8 +3,10 @@ pub mod de;
 pub mod fs;
 pub mod globs;
 pub mod net;
+pub mod schema;
 pub mod site;
 pub mod slugs;
+pub mod strings;
 pub mod table_of_contents;
 pub mod templates;
 pub mod types;
@@ -0,0 +1,248 @@
+//! The schema the `extra` and `taxonomies` of pages are checked against, declared in the
+//! `page_schema` table of a section, of the config or of the theme.
+use std::collections::{BTreeMap, HashMap};
+
+use serde::{Deserialize, Serialize};
+use tera::{Map, Value};
+
+use errors::{Result, bail};
+
+use crate::strings::closest_match;
+use crate::types::ValueType;
+
+/// The declaration of one key of `extra`
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FieldSchema {
+    #[serde(rename = "type", default)]
+    pub kind: ValueType,
+    #[serde(default)]
+    pub required: bool,
+    /// The only values allowed, if set
+    #[serde(rename = "enum")]
+    pub allowed: Option<Vec<Value>>,
+    pub default: Option<Value>,
+    pub description: Option<String>,
+}
+
+/// The declaration of one taxonomy
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TaxonomySchema {
+    #[serde(default)]
+    pub required: bool,
+    /// The only terms allowed, if set
+    #[serde(rename = "enum")]
+    pub allowed: Option<Vec<String>>,
+    pub default: Option<Vec<String>>,
+    pub description: Option<String>,
+}
+
+/// What the `extra` and `taxonomies` of pages need to contain. When keys of `extra` are
+/// declared, the other keys are errors to catch typos.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FrontMatterSchema {
+    #[serde(default)]
+    pub extra: BTreeMap<String, FieldSchema>,
+    #[serde(default)]
+    pub taxonomies: BTreeMap<String, TaxonomySchema>,
+}
+
+/// Formats the allowed values as "`a`, `b`"
+fn format_allowed<T: std::fmt::Display>(allowed: &[T]) -> String {
+    allowed.iter().map(|v| format!("`{}`", v)).collect::<Vec<_>>().join(", ")
+}
+
+impl FrontMatterSchema {
+    /// Errors on keys both required and with a default, and on defaults the schema itself rejects
+    pub fn validate(&self) -> Result<()> {
+        for (name, field) in &self.extra {
+            if let Some(default) = &field.default {
+                if field.required {
+                    bail!("`extra.{}` can't be both required and have a default", name);
+                }
+                if !field.kind.matches(default) {
+                    bail!("The default of `extra.{}` should be {}", name, field.kind);
+                }
+                if let Some(allowed) = &field.allowed
+                    && !allowed.contains(default)
+                {
+                    bail!(
+                        "The default of `extra.{}` should be one of {}",
+                        name,
+                        format_allowed(allowed)
+                    );
+                }
+            }
+        }
+        for (name, taxonomy) in &self.taxonomies {
+            if taxonomy.default.is_some() && taxonomy.required {
+                bail!("The taxonomy `{}` can't be both required and have a default", name);
+            }
+            if let (Some(default), Some(allowed)) = (&taxonomy.default, &taxonomy.allowed)
+                && let Some(term) = default.iter().find(|t| !allowed.contains(t))
+            {
+                bail!("The default term `{}` of the taxonomy `{}` isn't allowed", term, name);
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks the `extra` and `taxonomies` of a page and fills in the defaults of the missing
+    /// ones. Returns every problem found rather than stopping at the first one.
+    pub fn check(
+        &self,
+        extra: &mut Map<String, Value>,
+        taxonomies: &mut HashMap<String, Vec<String>>,
+    ) -> Vec<String> {
+        let mut errors = Vec::new();
+
+        if !self.extra.is_empty() {
+            for key in extra.keys() {
+                if self.extra.contains_key(key) {
+                    continue;
+                }
+                match closest_match(key, self.extra.keys().map(String::as_str)) {
+                    Some(close) => errors.push(format!(
+                        "unknown key `extra.{}`, did you mean `extra.{}`?",
+                        key, close
+                    )),
+                    None => errors.push(format!("unknown key `extra.{}`", key)),
+                }
+            }
+        }
+
+        for (name, field) in &self.extra {
+            let Some(value) = extra.get(name) else {
+                if let Some(default) = &field.default {
+                    extra.insert(name.clone(), default.clone());
+                } else if field.required {
+                    errors.push(format!("missing required key `extra.{}`", name));
+                }
+                continue;
+            };
+            if !field.kind.matches(value) {
+                errors.push(format!("`extra.{}` should be {}, got `{}`", name, field.kind, value));
+            } else if let Some(allowed) = &field.allowed
+                && !allowed.contains(value)
+            {
+                errors.push(format!(
+                    "`extra.{}` should be one of {}, got `{}`",
+                    name,
+                    format_allowed(allowed),
+                    value
+                ));
+            }
+        }
+
+        for (name, taxonomy) in &self.taxonomies {
+            let Some(terms) = taxonomies.get(name).filter(|terms| !terms.is_empty()) else {
+                if let Some(default) = &taxonomy.default {
+                    taxonomies.insert(name.clone(), default.clone());
+                } else if taxonomy.required {
+                    errors.push(format!("missing required taxonomy `{}`", name));
+                }
+                continue;
+            };
+            if let Some(allowed) = &taxonomy.allowed {
+                for term in terms.iter().filter(|t| !allowed.contains(t)) {
+                    errors.push(format!(
+                        "the term `{}` of `{}` should be one of {}",
+                        term,
+                        name,
+                        format_allowed(allowed)
+                    ));
+                }
+            }
+        }
+
+        errors
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tera::to_value;
+
+    fn schema() -> FrontMatterSchema {
+        toml::from_str(
+            r#"
+[extra.cover_image]
+type = "string"
+required = true
+
+[extra.layout]
+enum = ["wide", "narrow"]
+default = "narrow"
+
+[extra.reading_level]
+type = "integer"
+
+[taxonomies.tags]
+required = true
+enum = ["rust", "web"]
+
+[taxonomies.categories]
+default = ["misc"]
+"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn can_fill_defaults() {
+        let mut extra = Map::new();
+        extra.insert("cover_image".to_string(), to_value("cover.png").unwrap());
+        let mut taxonomies = HashMap::new();
+        taxonomies.insert("tags".to_string(), vec!["rust".to_string()]);
+
+        assert!(schema().check(&mut extra, &mut taxonomies).is_empty());
+        assert_eq!(extra["layout"], to_value("narrow").unwrap());
+        assert_eq!(taxonomies["categories"], ["misc"]);
+    }
+
+    #[test]
+    fn reports_every_error() {
+        let mut extra = Map::new();
+        extra.insert("cover_iamge".to_string(), to_value("cover.png").unwrap());
+        extra.insert("layout".to_string(), to_value("full").unwrap());
+        extra.insert("reading_level".to_string(), to_value("easy").unwrap());
+        let mut taxonomies = HashMap::new();
+        taxonomies.insert("tags".to_string(), vec!["rust".to_string(), "go".to_string()]);
+
+        assert_eq!(
+            schema().check(&mut extra, &mut taxonomies),
+            [
+                "unknown key `extra.cover_iamge`, did you mean `extra.cover_image`?",
+                "missing required key `extra.cover_image`",
+                "`extra.layout` should be one of `\"wide\"`, `\"narrow\"`, got `\"full\"`",
+                "`extra.reading_level` should be an integer, got `\"easy\"`",
+                "the term `go` of `tags` should be one of `rust`, `web`",
+            ]
+        );
+    }
+
+    #[test]
+    fn errors_on_invalid_defaults() {
+        let cases = [
+            (
+                "[extra.layout]\nrequired = true\ndefault = \"wide\"",
+                "`extra.layout` can't be both required and have a default",
+            ),
+            (
+                "[extra.layout]\ntype = \"integer\"\ndefault = \"wide\"",
+                "The default of `extra.layout` should be an integer",
+            ),
+            (
+                "[taxonomies.tags]\nenum = [\"rust\"]\ndefault = [\"go\"]",
+                "The default term `go` of the taxonomy `tags` isn't allowed",
+            ),
+        ];
+        for (toml, error) in cases {
+            let schema: FrontMatterSchema = toml::from_str(toml).unwrap();
+            assert_eq!(schema.validate().unwrap_err().to_string(), error);
+        }
+    }
+}
@@ -1,12 +1,15 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 /// Populated while receiving events from the markdown parser
-#[derive(Debug, Default, PartialEq, Eq, Clone, Serialize)]
+#[derive(Debug, Default, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub struct Heading {
     pub level: u32,
     pub id: String,
     pub permalink: String,
     pub title: String,
+    /// The section number like `2.3.1`, when headings are numbered
+    #[serde(default)]
+    pub number: Option<String>,
     pub children: Vec<Heading>,
 }
 
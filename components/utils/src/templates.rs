@@ -1,8 +1,12 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
 
-use tera::{Context, Tera};
+use serde::{Deserialize, Serialize};
+use tera::{Context, Tera, Value};
 
-use errors::{Result, bail};
+use errors::{Context as ErrorContext, Result, bail};
+
+use crate::types::ValueType;
 
 const DEFAULT_TPL: &str = include_str!("default_tpl.html");
 
@@ -21,16 +25,77 @@ pub enum ShortcodeFileType {
     Html,
 }
 
+/// The declaration of one argument of a shortcode
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ShortcodeArg {
+    #[serde(rename = "type", default)]
+    pub kind: ValueType,
+    #[serde(default)]
+    pub required: bool,
+    pub default: Option<Value>,
+    pub description: Option<String>,
+}
+
+/// The arguments a shortcode declares in a `+++` TOML block inside a comment at the top of
+/// its template. Shortcodes without one accept any arguments.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ShortcodeSchema {
+    #[serde(default)]
+    pub args: BTreeMap<String, ShortcodeArg>,
+}
+
+impl ShortcodeSchema {
+    /// Parses the declaration at the top of the source of a shortcode template, if there is one:
+    ///
+    /// ```text
+    /// {#
+    /// +++
+    /// [args.id]
+    /// type = "string"
+    /// required = true
+    /// +++
+    /// #}
+    /// ```
+    pub fn from_template_source(source: &str) -> Result<Option<Self>> {
+        let Some(comment) = source.trim_start().strip_prefix("{#") else {
+            return Ok(None);
+        };
+        let comment = comment.strip_prefix('-').unwrap_or(comment);
+        let Some(block) = comment.trim_start().strip_prefix("+++") else {
+            return Ok(None);
+        };
+        let Some((toml, _)) = block.split_once("+++") else {
+            bail!("The argument declaration is never closed with `+++`");
+        };
+
+        let schema: ShortcodeSchema = toml::from_str(toml)?;
+        for (name, arg) in &schema.args {
+            if let Some(default) = &arg.default {
+                if arg.required {
+                    bail!("The argument `{}` can't be both required and have a default", name);
+                }
+                if !arg.kind.matches(default) {
+                    bail!("The default of the argument `{}` should be {}", name, arg.kind);
+                }
+            }
+        }
+        Ok(Some(schema))
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct ShortcodeDefinition {
     pub file_type: ShortcodeFileType,
     pub tera_name: String,
+    pub schema: Option<ShortcodeSchema>,
 }
 impl ShortcodeDefinition {
     pub fn new(file_type: ShortcodeFileType, tera_name: &str) -> ShortcodeDefinition {
         let tera_name = tera_name.to_string();
 
-        ShortcodeDefinition { file_type, tera_name }
+        ShortcodeDefinition { file_type, tera_name, schema: None }
     }
 }
 
@@ -52,8 +117,19 @@ impl ShortcodeInvocationCounter {
     }
 }
 
-/// Fetches all the shortcodes from the Tera instances
-pub fn get_shortcodes(tera: &Tera) -> HashMap<String, ShortcodeDefinition> {
+/// Reads the argument declaration of a shortcode loaded from a file
+fn load_shortcode_schema(name: &str, path: Option<&str>) -> Result<Option<ShortcodeSchema>> {
+    let Some(path) = path else {
+        return Ok(None);
+    };
+    let source = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read the template of the shortcode `{}`", name))?;
+    ShortcodeSchema::from_template_source(&source)
+        .with_context(|| format!("Invalid argument declaration in the shortcode `{}`", name))
+}
+
+/// Fetches all the shortcodes from the Tera instances, along with their argument declarations
+pub fn get_shortcodes(tera: &Tera) -> Result<HashMap<String, ShortcodeDefinition>> {
     let mut shortcode_definitions = HashMap::new();
 
     for (identifier, template) in tera.templates.iter() {
@@ -65,10 +141,10 @@ pub fn get_shortcodes(tera: &Tera) -> HashMap<String, ShortcodeDefinition> {
 
         if template.name.starts_with("shortcodes/") {
             let head_len = "shortcodes/".len();
-            shortcode_definitions.insert(
-                identifier[head_len..(identifier.len() - ext_len - 1)].to_string(),
-                ShortcodeDefinition::new(file_type, &template.name),
-            );
+            let name = &identifier[head_len..(identifier.len() - ext_len - 1)];
+            let mut definition = ShortcodeDefinition::new(file_type, &template.name);
+            definition.schema = load_shortcode_schema(name, template.path.as_deref())?;
+            shortcode_definitions.insert(name.to_string(), definition);
             continue;
         }
 
@@ -84,7 +160,7 @@ pub fn get_shortcodes(tera: &Tera) -> HashMap<String, ShortcodeDefinition> {
         }
     }
 
-    shortcode_definitions
+    Ok(shortcode_definitions)
 }
 
 /// Renders the given template with the given context, but also ensures that, if the default file
@@ -167,10 +243,10 @@ pub fn check_template_fallbacks<'a>(
 
 #[cfg(test)]
 mod tests {
-    use crate::templates::{check_template_fallbacks, get_shortcodes};
+    use crate::templates::{ShortcodeSchema, ValueType, check_template_fallbacks, get_shortcodes};
 
     use super::rewrite_theme_paths;
-    use tera::Tera;
+    use tera::{Tera, to_value};
 
     #[test]
     fn can_rewrite_all_paths_of_theme() {
@@ -219,7 +295,57 @@ mod tests {
         let mut tera = Tera::parse("test-templates/*.html").unwrap();
         tera.add_raw_template("__zola_builtins/shortcodes/youtube.html", "Builtin").unwrap();
         tera.add_raw_template("shortcodes/youtube.html", "Hello").unwrap();
-        let definitions = get_shortcodes(&tera);
+        let definitions = get_shortcodes(&tera).unwrap();
         assert_eq!(definitions["youtube"].tera_name, "shortcodes/youtube.html");
     }
+
+    #[test]
+    fn can_parse_shortcode_schema() {
+        let source = r#"{#-
++++
+[args.id]
+type = "string"
+required = true
+description = "The id of the video"
+
+[args.autoplay]
+type = "boolean"
+default = false
+
+[args.class]
++++
+-#}
+<iframe src="https://www.youtube.com/embed/{{ id }}"></iframe>"#;
+        let schema = ShortcodeSchema::from_template_source(source).unwrap().unwrap();
+        assert_eq!(schema.args.len(), 3);
+        assert_eq!(schema.args["id"].kind, ValueType::String);
+        assert!(schema.args["id"].required);
+        assert_eq!(schema.args["id"].description.as_deref(), Some("The id of the video"));
+        assert_eq!(schema.args["autoplay"].default, Some(to_value(false).unwrap()));
+        assert_eq!(schema.args["class"].kind, ValueType::Any);
+    }
+
+    #[test]
+    fn shortcode_without_declaration_has_no_schema() {
+        assert_eq!(ShortcodeSchema::from_template_source("{# a comment #}<p></p>").unwrap(), None);
+        assert_eq!(ShortcodeSchema::from_template_source("<p>+++</p>").unwrap(), None);
+    }
+
+    #[test]
+    fn errors_on_invalid_shortcode_schema() {
+        let errors = [
+            ("{#\n+++\n[args.a]\ntype = \"string\"\n#}", "never closed"),
+            ("{#\n+++\n[args.a]\ntype = \"text\"\n+++\n#}", "unknown variant"),
+            ("{#\n+++\n[args.a]\nrequird = true\n+++\n#}", "unknown field"),
+            (
+                "{#\n+++\n[args.a]\ntype = \"integer\"\ndefault = \"1\"\n+++\n#}",
+                "should be an integer",
+            ),
+            ("{#\n+++\n[args.a]\nrequired = true\ndefault = 1\n+++\n#}", "both required"),
+        ];
+        for (source, error) in errors {
+            let err = ShortcodeSchema::from_template_source(source).unwrap_err();
+            assert!(err.to_string().contains(error), "{}: {}", source, err);
+        }
+    }
 }
@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use tera::Value;
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
@@ -14,3 +15,74 @@ impl InsertAnchor {
         matches!(self, InsertAnchor::Left | InsertAnchor::Right)
     }
 }
+
+/// The markup languages content files can be written in, found from their extension
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContentFormat {
+    #[default]
+    Markdown,
+    Djot,
+    Org,
+    /// Jupyter notebooks
+    Notebook,
+}
+
+impl ContentFormat {
+    /// The extensions of the content files, in the same order as the variants
+    pub const EXTENSIONS: [&'static str; 4] = ["md", "dj", "org", "ipynb"];
+
+    pub fn from_path(path: &std::path::Path) -> Option<ContentFormat> {
+        match path.extension()?.to_str()? {
+            "md" => Some(ContentFormat::Markdown),
+            "dj" => Some(ContentFormat::Djot),
+            "org" => Some(ContentFormat::Org),
+            "ipynb" => Some(ContentFormat::Notebook),
+            _ => None,
+        }
+    }
+}
+
+/// The type a shortcode argument or a key of a `page_schema` can be declared with
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ValueType {
+    String,
+    Integer,
+    Float,
+    Boolean,
+    Array,
+    Table,
+    #[default]
+    Any,
+}
+
+impl ValueType {
+    /// Whether the given value is of that type. Integers are valid floats.
+    pub fn matches(&self, value: &Value) -> bool {
+        match self {
+            ValueType::String => value.is_string(),
+            ValueType::Integer => value.is_i64() || value.is_u64(),
+            ValueType::Float => value.is_number(),
+            ValueType::Boolean => value.is_boolean(),
+            ValueType::Array => value.is_array(),
+            ValueType::Table => value.is_object(),
+            ValueType::Any => true,
+        }
+    }
+}
+
+impl std::fmt::Display for ValueType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            ValueType::String => "a string",
+            ValueType::Integer => "an integer",
+            ValueType::Float => "a float",
+            ValueType::Boolean => "a boolean",
+            ValueType::Array => "an array",
+            ValueType::Table => "a table",
+            ValueType::Any => "any value",
+        };
+        write!(f, "{}", name)
+    }
+}
@@ -1,7 +1,7 @@
 use std::net::IpAddr;
 use std::path::PathBuf;
 
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
 use clap_complete::Shell;
 
 #[derive(Parser)]
@@ -37,6 +37,12 @@ pub enum Command {
         force: bool,
     },
 
+    /// Create a new page or section from an archetype
+    New {
+        /// Path of the Markdown file to create from the root of the site, eg `content/blog/my-post.md`
+        path: PathBuf,
+    },
+
     /// Deletes the output directory if there is one and builds the site
     Build {
         /// Force the base URL to be that value (defaults to the one in the config file)
@@ -55,9 +61,21 @@ pub enum Command {
         #[clap(long)]
         drafts: bool,
 
+        /// Include the content whose `publish_date` is in the future
+        #[clap(long)]
+        future: bool,
+
+        /// Include the content whose `expiry_date` has passed
+        #[clap(long)]
+        expired: bool,
+
         /// Minify generated HTML files
         #[clap(long)]
         minify: bool,
+
+        /// Re-use the pages rendered by the previous build when nothing they depend on changed
+        #[clap(long)]
+        incremental: bool,
     },
 
     /// Serve the site. Rebuild and reload on change automatically
@@ -87,6 +105,14 @@ pub enum Command {
         #[clap(long)]
         drafts: bool,
 
+        /// Include the content whose `publish_date` is in the future
+        #[clap(long)]
+        future: bool,
+
+        /// Include the content whose `expiry_date` has passed
+        #[clap(long)]
+        expired: bool,
+
         /// Open site in the default browser
         #[clap(short = 'O', long)]
         open: bool,
@@ -117,9 +143,21 @@ pub enum Command {
         /// Include drafts when loading the site
         #[clap(long)]
         drafts: bool,
+        /// Include the content whose `publish_date` is in the future
+        #[clap(long)]
+        future: bool,
+        /// Include the content whose `expiry_date` has passed
+        #[clap(long)]
+        expired: bool,
         /// Skip external links
         #[clap(long)]
         skip_external_links: bool,
+        /// Check again the external links cached by the previous runs
+        #[clap(long)]
+        refresh_links: bool,
+        /// Print the broken links found on stdout in that format
+        #[clap(long, value_enum, default_value_t = CheckFormat::Human)]
+        format: CheckFormat,
     },
 
     /// Generate shell completion
@@ -129,3 +167,13 @@ pub enum Command {
         shell: Shell,
     },
 }
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum CheckFormat {
+    /// Only log the broken links
+    Human,
+    /// A JSON object with the list of broken links
+    Json,
+    /// A SARIF 2.1.0 log, for code scanning tools
+    Sarif,
+}
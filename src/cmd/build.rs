@@ -5,6 +5,7 @@ use site::Site;
 
 use crate::messages;
 
+#[allow(clippy::too_many_arguments)]
 pub fn build(
     root_dir: &Path,
     config_file: &Path,
@@ -12,7 +13,10 @@ pub fn build(
     output_dir: Option<&Path>,
     force: bool,
     include_drafts: bool,
+    include_future: bool,
+    include_expired: bool,
     minify: bool,
+    incremental: bool,
 ) -> Result<()> {
     let mut site = Site::new(root_dir, config_file)?;
     if let Some(output_dir) = output_dir {
@@ -31,11 +35,22 @@ pub fn build(
     if include_drafts {
         site.include_drafts();
     }
+    if include_future {
+        site.include_future();
+    }
+    if include_expired {
+        site.include_expired();
+    }
     if minify {
         site.minify();
     }
+    if incremental {
+        site.enable_build_cache();
+    }
     site.load()?;
     messages::notify_site_size(&site);
     messages::warn_about_ignored_pages(&site);
-    site.build()
+    site.build()?;
+    messages::notify_build_cache(&site);
+    Ok(())
 }
@@ -2,16 +2,23 @@ use std::path::{Path, PathBuf};
 
 use errors::Result;
 use site::Site;
+use site::link_checking::{to_json_report, to_sarif_report};
 
+use crate::cli::CheckFormat;
 use crate::messages;
 
+#[allow(clippy::too_many_arguments)]
 pub fn check(
     root_dir: &Path,
     config_file: &Path,
     base_path: Option<&str>,
     base_url: Option<&str>,
     include_drafts: bool,
+    include_future: bool,
+    include_expired: bool,
     skip_external_links: bool,
+    refresh_links: bool,
+    format: CheckFormat,
 ) -> Result<()> {
     let bp = base_path.map(PathBuf::from).unwrap_or_else(|| PathBuf::from(root_dir));
     let mut site = Site::new(bp, config_file)?;
@@ -23,10 +30,29 @@ pub fn check(
     if include_drafts {
         site.include_drafts();
     }
+    if include_future {
+        site.include_future();
+    }
+    if include_expired {
+        site.include_expired();
+    }
     if skip_external_links {
         site.skip_external_links_check();
     }
-    site.load()?;
+    if refresh_links {
+        site.refresh_external_links();
+    }
+    let loaded = site.load();
+    // The report is printed even if the site failed to load because of broken links
+    let report = match format {
+        CheckFormat::Human => None,
+        CheckFormat::Json => Some(to_json_report(&site.link_issues, &site.base_path)?),
+        CheckFormat::Sarif => Some(to_sarif_report(&site.link_issues, &site.base_path)?),
+    };
+    if let Some(report) = report {
+        println!("{}", report);
+    }
+    loaded?;
     messages::check_site_summary(&site);
     messages::warn_about_ignored_pages(&site);
     Ok(())
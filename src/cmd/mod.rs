@@ -1,9 +1,11 @@
 mod build;
 mod check;
 mod init;
+mod new;
 mod serve;
 
 pub use self::build::build;
 pub use self::check::check;
 pub use self::init::create_new_project;
+pub use self::new::create_new_content;
 pub use self::serve::serve;
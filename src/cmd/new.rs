@@ -0,0 +1,277 @@
+use std::path::{Component, Path, PathBuf};
+
+use config::Config;
+use content::{FileInfo, slug_from_filename};
+use errors::{Context as _, Result, bail};
+use tera::{Context, Tera};
+use time::format_description::well_known::Rfc3339;
+use time::macros::format_description;
+use time::{OffsetDateTime, UtcOffset};
+use utils::fs::{create_file, read_file};
+
+const DEFAULT_PAGE_ARCHETYPE: &str = r#"+++
+title = "{{ title }}"
+date = {{ date }}
+draft = true
+{%- if taxonomies %}
+
+[taxonomies]
+{%- for taxonomy in taxonomies %}
+{{ taxonomy }} = []
+{%- endfor %}
+{%- endif %}
++++
+"#;
+
+const DEFAULT_SECTION_ARCHETYPE: &str = r#"+++
+title = "{{ title }}"
++++
+"#;
+
+/// Turns a slug like `my-first-post` into `My First Post`
+fn title_from_slug(slug: &str) -> String {
+    slug.split(['-', '_'])
+        .filter(|w| !w.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Finds the archetype to use, looking first in the site `archetypes` directory and then in the
+/// theme one:
+/// - `_index.md` for sections
+/// - for pages, the file named after the closest section, eg `blog/2024.md` then `blog.md`
+///   for `content/blog/2024/my-post.md`, then `default.md`
+fn find_archetype(
+    root_dir: &Path,
+    config: &Config,
+    components: &[String],
+    is_section: bool,
+) -> Option<PathBuf> {
+    let mut candidates = Vec::new();
+    if is_section {
+        candidates.push(PathBuf::from("_index.md"));
+    } else {
+        for i in (1..=components.len()).rev() {
+            candidates.push(PathBuf::from(format!("{}.md", components[..i].join("/"))));
+        }
+        candidates.push(PathBuf::from("default.md"));
+    }
+
+    let mut dirs = vec![root_dir.join("archetypes")];
+    if let Some(theme) = &config.theme {
+        dirs.push(root_dir.join("themes").join(theme).join("archetypes"));
+    }
+
+    dirs.iter().flat_map(|dir| candidates.iter().map(move |c| dir.join(c))).find(|p| p.exists())
+}
+
+/// Creates a new page or section at the given path by rendering its archetype.
+/// A relative path is relative to the root of the site, wherever the command is run from.
+pub fn create_new_content(
+    root_dir: &Path,
+    config_file: &Path,
+    path: &Path,
+    utc_offset: UtcOffset,
+) -> Result<()> {
+    let config = Config::from_file(config_file)?;
+    let content_dir = root_dir.join("content");
+    let path = &root_dir.join(path);
+    if !path.starts_with(&content_dir) || path.components().any(|c| c == Component::ParentDir) {
+        bail!("`{}` is not in the content directory `{}`", path.display(), content_dir.display());
+    }
+    if path.extension().is_none_or(|ext| ext != "md") {
+        bail!("`{}` is not a Markdown file", path.display());
+    }
+    if path.exists() {
+        bail!("`{}` already exists", path.display());
+    }
+
+    let is_section =
+        path.file_name().unwrap().to_string_lossy().split('.').next() == Some("_index");
+    let mut file = if is_section {
+        FileInfo::new_section(path, root_dir)
+    } else {
+        FileInfo::new_page(path, root_dir)
+    };
+    let lang = file.find_language(&config.default_language, &config.other_languages_codes())?;
+
+    // For sections and colocated pages, the slug comes from the directory name. Pages get the
+    // slug they will have once built, without the date their filename starts with.
+    let slug = if is_section {
+        path.parent().unwrap().file_name().unwrap().to_string_lossy().to_string()
+    } else if file.colocated_path.is_some() {
+        let dir_name = path.parent().unwrap().file_name().unwrap().to_string_lossy();
+        slug_from_filename(&dir_name, &config).0
+    } else {
+        slug_from_filename(&file.name, &config).0
+    };
+    let title = if path.parent() == Some(content_dir.as_path()) && is_section {
+        config.title.clone().unwrap_or_default()
+    } else {
+        title_from_slug(&slug)
+    };
+    let taxonomies: Vec<_> =
+        config.languages[&lang].taxonomies.iter().map(|t| t.name.as_str()).collect();
+
+    let now = OffsetDateTime::now_utc().to_offset(utc_offset);
+    let mut context = Context::new();
+    context.insert("title", &title);
+    context.insert("slug", &slug);
+    context.insert("lang", &lang);
+    context.insert("section", &file.components.join("/"));
+    context.insert("taxonomies", &taxonomies);
+    context.insert("date", &now.format(format_description!("[year]-[month]-[day]"))?);
+    context.insert("datetime", &now.format(&Rfc3339)?);
+    context.insert("config", &config.serialize(&lang));
+
+    let rendered = match find_archetype(root_dir, &config, &file.components, is_section) {
+        Some(archetype) => {
+            let template = read_file(&archetype)?;
+            Tera::one_off(&template, &context, false)
+                .with_context(|| format!("Failed to render archetype `{}`", archetype.display()))?
+        }
+        None => {
+            let template =
+                if is_section { DEFAULT_SECTION_ARCHETYPE } else { DEFAULT_PAGE_ARCHETYPE };
+            Tera::one_off(template, &context, false)?
+        }
+    };
+
+    create_file(path, &rendered)?;
+    console::success(&format!("Created {}", path.display()));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env::temp_dir;
+    use std::fs::{create_dir_all, remove_dir_all};
+
+    const CONFIG: &str = r#"
+base_url = "https://example.com"
+title = "My site"
+taxonomies = [{ name = "tags" }, { name = "categories" }]
+
+[languages.fr]
+taxonomies = [{ name = "tags" }]
+"#;
+
+    fn setup(name: &str) -> PathBuf {
+        let mut dir = temp_dir();
+        dir.push(name);
+        if dir.exists() {
+            remove_dir_all(&dir).expect("Could not free test directory");
+        }
+        create_dir_all(dir.join("content")).expect("Could not create test directory");
+        create_file(&dir.join("zola.toml"), CONFIG).unwrap();
+        dir
+    }
+
+    fn create(dir: &Path, path: &str) -> Result<String> {
+        create_new_content(dir, &dir.join("zola.toml"), Path::new(path), UtcOffset::UTC)?;
+        read_file(&dir.join(path))
+    }
+
+    #[test]
+    fn can_get_title_from_slug() {
+        assert_eq!(title_from_slug("my-first_post"), "My First Post");
+        assert_eq!(title_from_slug("hello"), "Hello");
+        assert_eq!(title_from_slug("--"), "");
+    }
+
+    #[test]
+    fn can_create_page_with_builtin_archetype() {
+        let dir = setup("test_new_builtin_page");
+        let content = create(&dir, "content/blog/my-post.md").unwrap();
+        let date = OffsetDateTime::now_utc().format(format_description!("[year]-[month]-[day]"));
+        assert_eq!(
+            content,
+            format!(
+                "+++\ntitle = \"My Post\"\ndate = {}\ndraft = true\n\n[taxonomies]\ntags = []\ncategories = []\n+++\n",
+                date.unwrap()
+            )
+        );
+        remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn can_create_sections() {
+        let dir = setup("test_new_section");
+        let content = create(&dir, "content/blog/_index.md").unwrap();
+        assert_eq!(content, "+++\ntitle = \"Blog\"\n+++\n");
+        let content = create(&dir, "content/_index.md").unwrap();
+        assert_eq!(content, "+++\ntitle = \"My site\"\n+++\n");
+        remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn can_use_section_archetype() {
+        let dir = setup("test_new_section_archetype");
+        create_dir_all(dir.join("archetypes")).unwrap();
+        create_file(&dir.join("archetypes/default.md"), "default").unwrap();
+        create_file(
+            &dir.join("archetypes/blog.md"),
+            "{{ title }} {{ slug }} {{ lang }} {{ section }} {{ taxonomies | join(sep=',') }}",
+        )
+        .unwrap();
+        let content = create(&dir, "content/blog/2024/hello-world/index.fr.md").unwrap();
+        assert_eq!(content, "Hello World hello-world fr blog/2024 tags");
+        let content = create(&dir, "content/docs/intro.md").unwrap();
+        assert_eq!(content, "default");
+        remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn can_strip_date_from_slug() {
+        let dir = setup("test_new_dated_page");
+        create_dir_all(dir.join("archetypes")).unwrap();
+        create_file(&dir.join("archetypes/default.md"), "{{ title }} {{ slug }}").unwrap();
+        let content = create(&dir, "content/blog/2024-01-01-my-post.md").unwrap();
+        assert_eq!(content, "My Post my-post");
+        let content = create(&dir, "content/blog/2024-01-02_Second Post/index.md").unwrap();
+        assert_eq!(content, "Second Post second-post");
+        remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn errors_on_invalid_paths() {
+        let dir = setup("test_new_invalid");
+        create_file(&dir.join("content/existing.md"), "").unwrap();
+        assert!(
+            create(&dir, "content/existing.md").unwrap_err().to_string().contains("already exists")
+        );
+        assert!(
+            create(&dir, "static/page.md")
+                .unwrap_err()
+                .to_string()
+                .contains("is not in the content")
+        );
+        assert!(
+            create(&dir, "content/../static/page.md")
+                .unwrap_err()
+                .to_string()
+                .contains("is not in the content")
+        );
+        assert!(
+            create(&dir, "content/page.txt")
+                .unwrap_err()
+                .to_string()
+                .contains("not a Markdown file")
+        );
+        assert!(
+            create(&dir, "content/page.de.md")
+                .unwrap_err()
+                .to_string()
+                .contains("language code of de")
+        );
+        remove_dir_all(&dir).unwrap();
+    }
+}
@@ -22,6 +22,7 @@
 // WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
 
 use std::cell::Cell;
+use std::collections::HashSet;
 use std::ffi::OsStr;
 use std::net::{IpAddr, SocketAddr, TcpListener};
 use std::path::{MAIN_SEPARATOR, Path, PathBuf};
@@ -48,7 +49,8 @@ use time::{OffsetDateTime, UtcOffset};
 use tokio::sync::broadcast;
 
 use log;
-use notify_debouncer_full::{new_debouncer, notify::RecursiveMode};
+use notify_debouncer_full::notify::{RecommendedWatcher, RecursiveMode};
+use notify_debouncer_full::{Debouncer, RecommendedCache, new_debouncer};
 use relative_path::{RelativePath, RelativePathBuf};
 
 use errors::{Context, Error, Result, anyhow};
@@ -450,6 +452,8 @@ fn create_new_site(
     base_url: Option<&str>,
     config_file: &Path,
     include_drafts: bool,
+    include_future: bool,
+    include_expired: bool,
     store_html: bool,
     mut no_port_append: bool,
 ) -> Result<(Site, SocketAddr, String)> {
@@ -487,6 +491,12 @@ fn create_new_site(
     if include_drafts {
         site.include_drafts();
     }
+    if include_future {
+        site.include_future();
+    }
+    if include_expired {
+        site.include_expired();
+    }
     site.load()?;
     // With Axum, WebSocket runs on the same server as HTTP
     site.enable_live_reload_with_port(interface_port);
@@ -507,6 +517,8 @@ pub fn serve(
     config_file: &Path,
     open: bool,
     include_drafts: bool,
+    include_future: bool,
+    include_expired: bool,
     store_html: bool,
     fast_rebuild: bool,
     no_port_append: bool,
@@ -524,6 +536,8 @@ pub fn serve(
         base_url,
         config_file,
         include_drafts,
+        include_future,
+        include_expired,
         store_html,
         no_port_append,
     )?;
@@ -587,6 +601,29 @@ pub fn serve(
         }
     }
 
+    // Files included in code blocks can be outside of the folders watched above, so their
+    // folders are watched as well, and updated after every rebuild
+    let watch_included_dirs = |debouncer: &mut Debouncer<RecommendedWatcher, RecommendedCache>,
+                               included_files: &HashSet<PathBuf>,
+                               included_dirs: &mut HashSet<PathBuf>| {
+        for dir in included_files.iter().filter_map(|p| p.parent()) {
+            let is_watched = dir == root_dir
+                || watchers.iter().any(|w| w != root_dir_str && dir.starts_with(root_dir.join(w)));
+            if is_watched || included_dirs.contains(dir) {
+                continue;
+            }
+            match debouncer.watch(dir, RecursiveMode::NonRecursive) {
+                Ok(()) => {
+                    included_dirs.insert(dir.to_path_buf());
+                }
+                Err(e) => log::warn!("Can't watch `{}` for changes: {e}", dir.display()),
+            }
+        }
+    };
+    let mut included_files = site.included_files();
+    let mut included_dirs = HashSet::new();
+    watch_included_dirs(&mut debouncer, &included_files, &mut included_dirs);
+
     let output_path = site.output_path.clone();
     create_directory(&output_path)?;
 
@@ -669,7 +706,7 @@ pub fn serve(
         log::info!("Sass file(s) changed {combined_paths}");
         rebuild_done_handling(
             &broadcaster,
-            compile_sass(&site.base_path, &site.output_path),
+            compile_sass(&site.base_path, &site.output_path).map(|_| ()),
             &site.sass_path.to_string_lossy(),
         );
     };
@@ -725,6 +762,8 @@ pub fn serve(
         base_url,
         config_file,
         include_drafts,
+        include_future,
+        include_expired,
         store_html,
         no_port_append,
     ) {
@@ -755,6 +794,7 @@ pub fn serve(
                     root_dir,
                     &config_path,
                     &site.config.ignored_content_globset,
+                    &included_files,
                 );
                 if changes.is_empty() {
                     continue;
@@ -788,7 +828,7 @@ pub fn serve(
                                             .to_string_lossy();
                                         let res = if filename == "_index.md" {
                                             site.add_and_render_section(full_path)
-                                        } else if filename.ends_with(".md") {
+                                        } else if site.config.content_format(full_path).is_some() {
                                             site.add_and_render_page(full_path)
                                         } else {
                                             // an asset changed? a folder renamed?
@@ -870,9 +910,28 @@ pub fn serve(
                                 site = s;
                             }
                         }
+                        ChangeKind::IncludedFile => {
+                            for (_, full_path, event_kind) in change_group.iter() {
+                                if *event_kind == SimpleFileSystemEventKind::Remove {
+                                    log::info!("-> Included file removed {}", full_path.display());
+                                } else {
+                                    log::info!("-> Included file changed {}", full_path.display());
+                                }
+                            }
+                            if let Some(s) = recreate_site() {
+                                site = s;
+                            }
+                        }
                         ChangeKind::ExtraPath => {
-                            let full_paths: Vec<&PathBuf> =
-                                change_group.iter().map(|(_, p, _)| p).collect();
+                            // Other files in the folders of the included files don't matter
+                            let full_paths: Vec<&PathBuf> = change_group
+                                .iter()
+                                .map(|(_, p, _)| p)
+                                .filter(|p| !p.parent().is_some_and(|d| included_dirs.contains(d)))
+                                .collect();
+                            if full_paths.is_empty() {
+                                continue;
+                            }
                             let combined_paths = full_paths
                                 .iter()
                                 .map(|p| p.display().to_string())
@@ -888,6 +947,9 @@ pub fn serve(
                     };
                     messages::report_elapsed_time(start);
                 }
+                // The content may include other files now
+                included_files = site.included_files();
+                watch_included_dirs(&mut debouncer, &included_files, &mut included_dirs);
             }
             Ok(Err(e)) => log::error!("File system event errors: {e:?}"),
             Err(e) => log::error!("File system event receiver errors: {e:?}"),
@@ -967,6 +1029,8 @@ mod tests {
             &config_file,
             include_drafts,
             false,
+            false,
+            false,
             no_port_append,
         )
         .unwrap();
@@ -6,6 +6,7 @@ use globset::GlobSet;
 use log;
 use notify_debouncer_full::DebouncedEvent;
 use notify_debouncer_full::notify::event::*;
+use std::collections::HashSet;
 use std::fs::read_dir;
 use std::path::{Path, PathBuf};
 use utils::fs::is_temp_file;
@@ -20,6 +21,8 @@ pub enum ChangeKind {
     Config,
     /// A change in one of the extra paths to watch provided by the user.
     ExtraPath,
+    /// A change in a file included in a code block of the content.
+    IncludedFile,
 }
 
 /// This enum abstracts over the fine-grained group of enums in `notify`.
@@ -66,6 +69,7 @@ pub fn filter_events(
     root_dir: &Path,
     config_path: &Path,
     ignored_content_globset: &Option<GlobSet>,
+    included_files: &HashSet<PathBuf>,
 ) -> HashMap<ChangeKind, Vec<MeaningfulEvent>> {
     // Arrange events from oldest to newest.
     events.sort_by(|e1, e2| e1.time.cmp(&e2.time));
@@ -91,6 +95,15 @@ pub fn filter_events(
         }
         let path = event.event.paths[0].clone();
 
+        // Included files can be anywhere, even peer to config.toml, and are reported even when
+        // deleted so the missing include is reported by the rebuild
+        if canonical_path(&path).is_some_and(|p| included_files.contains(&p)) {
+            let (_, partial_p) = detect_change_kind(root_dir, &path, config_path);
+            meaningful_events
+                .insert(path, (partial_p, simple_kind.unwrap(), ChangeKind::IncludedFile));
+            continue;
+        }
+
         // Since we debounce things, some files might already not exist anymore by the
         // time we get to them
         if !path.exists() {
@@ -130,6 +143,14 @@ pub fn filter_events(
     changes
 }
 
+/// Canonicalizes the path, or its parent folder if the file doesn't exist anymore
+fn canonical_path(path: &Path) -> Option<PathBuf> {
+    path.canonicalize().ok().or_else(|| {
+        let parent = path.parent()?.canonicalize().ok()?;
+        Some(parent.join(path.file_name()?))
+    })
+}
+
 fn is_ignored_file(ignored_content_globset: &Option<GlobSet>, path: &Path) -> bool {
     match ignored_content_globset {
         Some(gs) => gs.is_match(path),
@@ -175,8 +196,8 @@ mod tests {
     use std::path::{Path, PathBuf};
 
     use super::{
-        ChangeKind, SimpleFileSystemEventKind, detect_change_kind, get_relevant_event_kind,
-        is_temp_file,
+        ChangeKind, SimpleFileSystemEventKind, canonical_path, detect_change_kind,
+        get_relevant_event_kind, is_temp_file,
     };
 
     // This test makes sure we at least have code coverage on the `notify` event kinds we care
@@ -320,4 +341,12 @@ mod tests {
         let config_filename = Path::new("config.toml");
         assert_eq!(expected, detect_change_kind(pwd, path, config_filename));
     }
+
+    #[test]
+    fn can_canonicalize_deleted_files() {
+        let src = Path::new("src").canonicalize().unwrap();
+        assert_eq!(canonical_path(Path::new("src/main.rs")), Some(src.join("main.rs")));
+        assert_eq!(canonical_path(Path::new("src/deleted.rs")), Some(src.join("deleted.rs")));
+        assert_eq!(canonical_path(Path::new("nowhere/deleted.rs")), None);
+    }
 }
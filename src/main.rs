@@ -122,7 +122,28 @@ fn main() {
                 std::process::exit(1);
             }
         }
-        Command::Build { base_url, output_dir, force, drafts, minify } => {
+        Command::New { path } => {
+            let (root_dir, config_file) = get_config_file_path(&cli_dir, cli.config.as_deref());
+            if let Err(e) = cmd::create_new_content(
+                &root_dir,
+                &config_file,
+                &path,
+                UtcOffset::current_local_offset().unwrap_or(UtcOffset::UTC),
+            ) {
+                messages::unravel_errors("Failed to create the content", &e);
+                std::process::exit(1);
+            }
+        }
+        Command::Build {
+            base_url,
+            output_dir,
+            force,
+            drafts,
+            future,
+            expired,
+            minify,
+            incremental,
+        } => {
             log::info!("Building site...");
             let start = Instant::now();
             let (root_dir, config_file) = get_config_file_path(&cli_dir, cli.config.as_deref());
@@ -133,7 +154,10 @@ fn main() {
                 output_dir.as_deref(),
                 force,
                 drafts,
+                future,
+                expired,
                 minify,
+                incremental,
             ) {
                 Ok(()) => messages::report_elapsed_time(start),
                 Err(e) => {
@@ -149,6 +173,8 @@ fn main() {
             force,
             base_url,
             drafts,
+            future,
+            expired,
             open,
             store_html,
             fast,
@@ -180,6 +206,8 @@ fn main() {
                 &config_file,
                 open,
                 drafts,
+                future,
+                expired,
                 store_html,
                 fast,
                 no_port_append,
@@ -191,11 +219,22 @@ fn main() {
                 std::process::exit(1);
             }
         }
-        Command::Check { drafts, skip_external_links } => {
+        Command::Check { drafts, future, expired, skip_external_links, refresh_links, format } => {
             log::info!("Checking site...");
             let start = Instant::now();
             let (root_dir, config_file) = get_config_file_path(&cli_dir, cli.config.as_deref());
-            match cmd::check(&root_dir, &config_file, None, None, drafts, skip_external_links) {
+            match cmd::check(
+                &root_dir,
+                &config_file,
+                None,
+                None,
+                drafts,
+                future,
+                expired,
+                skip_external_links,
+                refresh_links,
+                format,
+            ) {
                 Ok(()) => messages::report_elapsed_time(start),
                 Err(e) => {
                     messages::unravel_errors("Failed to check the site", &e);
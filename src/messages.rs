@@ -17,6 +17,13 @@ pub fn notify_site_size(site: &Site) {
     );
 }
 
+/// Display in the console how much of the previous build was re-used, if the build cache was used
+pub fn notify_build_cache(site: &Site) {
+    if let Some(cache) = site.build_cache() {
+        log::info!("-> Re-used {} renders from the build cache", cache.reused());
+    }
+}
+
 /// Display in the console only the number of pages/sections in the site
 pub fn check_site_summary(site: &Site) {
     let library = site.library.read().unwrap();